@@ -0,0 +1,255 @@
+use serde_json::{Map, Value};
+
+use crate::Document;
+
+/// The elements that hold text leaves and inline elements.
+const INLINE_CONTAINERS: &[&str] = &["p", "h1", "h2", "h3", "h4", "h5", "h6", "lic", "a"];
+
+/// An element being built.
+pub(crate) struct OpenElement {
+    pub(crate) element: Map<String, Value>,
+    children: Vec<Value>,
+    /// Opened for inline content that is not in a paragraph, like the text of a
+    /// tight list item, and closed by the next block.
+    implicit: bool,
+}
+
+/// SlateBuilder builds the Slate value of a document from the elements and texts
+/// of another format, in document order. It keeps the value normalized: inline
+/// content outside of a paragraph gets one, and the inline elements have text
+/// leaves around them.
+#[derive(Default)]
+pub(crate) struct SlateBuilder {
+    pub(crate) stack: Vec<OpenElement>,
+    root: Vec<Value>,
+    /// The marks of the next text leaves.
+    pub(crate) marks: Vec<&'static str>,
+}
+
+impl SlateBuilder {
+    /// Adds a text leaf with the current marks, in a paragraph if needed.
+    pub(crate) fn text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.ensure_inline_container();
+        self.push_text(text);
+    }
+
+    pub(crate) fn push_text(&mut self, text: &str) {
+        let mut leaf = Map::new();
+        for mark in &self.marks {
+            leaf.insert(mark.to_string(), Value::Bool(true));
+        }
+        let children = match self.stack.last_mut() {
+            Some(open) => &mut open.children,
+            None => &mut self.root,
+        };
+        // Leaves with the same marks are merged, like Slate's normalization does.
+        if let Some(Value::Object(last)) = children.last_mut() {
+            let same_marks =
+                last.len() == leaf.len() + 1 && leaf.keys().all(|key| last.contains_key(key));
+            if let (true, Some(Value::String(contents))) = (same_marks, last.get_mut("text")) {
+                contents.push_str(text);
+                return;
+            }
+        }
+        leaf.insert("text".to_string(), Value::String(text.to_string()));
+        children.push(Value::Object(leaf));
+    }
+
+    pub(crate) fn push_element(
+        &mut self,
+        name: &str,
+        mut element: Map<String, Value>,
+        implicit: bool,
+    ) {
+        element.insert("type".to_string(), Value::String(name.to_string()));
+        self.stack.push(OpenElement {
+            element,
+            children: vec![],
+            implicit,
+        });
+    }
+
+    /// Opens a paragraph, or the content of a list item, for inline content that is
+    /// not in one.
+    pub(crate) fn ensure_inline_container(&mut self) {
+        if self.in_inline_container() {
+            return;
+        }
+        let name = if self.top_is("li") { "lic" } else { "p" };
+        self.push_element(name, Map::new(), true);
+    }
+
+    /// Checks if the open element was opened for inline content outside of a
+    /// paragraph.
+    pub(crate) fn is_implicit(&self) -> bool {
+        self.stack.last().map_or(false, |open| open.implicit)
+    }
+
+    /// Checks if the open element has no children yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.stack
+            .last()
+            .map_or(true, |open| open.children.is_empty())
+    }
+
+    /// Checks if the open element holds inline content.
+    pub(crate) fn in_inline_container(&self) -> bool {
+        self.stack.last().map_or(false, is_inline_container)
+    }
+
+    /// Checks if the inline content so far is empty or ends with whitespace, where
+    /// collapsible whitespace is dropped.
+    pub(crate) fn after_whitespace(&self) -> bool {
+        for open in self.stack.iter().rev() {
+            if !is_inline_container(open) {
+                return true;
+            }
+            match open.children.last() {
+                Some(Value::Object(last)) => {
+                    return match last.get("text") {
+                        Some(Value::String(text)) => text.ends_with(char::is_whitespace),
+                        _ => false,
+                    }
+                }
+                Some(_) => return false,
+                // An empty link follows the content of its parent.
+                None if is_type(&open.element, "a") => continue,
+                None => return true,
+            }
+        }
+        true
+    }
+
+    /// Removes the trailing whitespace of the open element's inline content.
+    pub(crate) fn trim_end(&mut self) {
+        let open = match self.stack.last_mut() {
+            Some(open) => open,
+            None => return,
+        };
+        if let Some(Value::Object(last)) = open.children.last_mut() {
+            if let Some(Value::String(text)) = last.get_mut("text") {
+                text.truncate(text.trim_end().len());
+                if text.is_empty() {
+                    open.children.pop();
+                }
+            }
+        }
+    }
+
+    pub(crate) fn close_implicit(&mut self) {
+        if self.is_implicit() {
+            self.close_paragraph();
+        }
+    }
+
+    /// Closes a paragraph. A paragraph with images only is replaced by the images,
+    /// which are blocks in Plate.
+    pub(crate) fn close_paragraph(&mut self) {
+        let open = match self.stack.last() {
+            Some(open) => open,
+            None => return,
+        };
+        let images_only = is_type(&open.element, "p")
+            && open
+                .children
+                .iter()
+                .any(|child| child.get("type") == Some(&Value::from("img")))
+            && open.children.iter().all(|child| {
+                child.get("type") == Some(&Value::from("img"))
+                    || child
+                        .get("text")
+                        .and_then(Value::as_str)
+                        .map_or(false, |t| t.trim().is_empty())
+            });
+        if !images_only {
+            return self.close();
+        }
+        let open = self.stack.pop().expect("checked above");
+        for image in open
+            .children
+            .into_iter()
+            .filter(|child| child.get("type").is_some())
+        {
+            self.append(image);
+        }
+    }
+
+    pub(crate) fn close(&mut self) {
+        let open = match self.stack.pop() {
+            Some(open) => open,
+            None => return,
+        };
+        let mut element = open.element;
+        let is_container = INLINE_CONTAINERS.iter().any(|name| is_type(&element, name));
+        let children = match is_container {
+            true => with_edge_texts(open.children),
+            false if open.children.is_empty() => vec![json_text("")],
+            false => open.children,
+        };
+        element.insert("children".to_string(), Value::Array(children));
+        self.append(Value::Object(element));
+    }
+
+    fn append(&mut self, node: Value) {
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => self.root.push(node),
+        }
+    }
+
+    pub(crate) fn top_is(&self, name: &str) -> bool {
+        self.stack
+            .last()
+            .map_or(false, |open| is_type(&open.element, name))
+    }
+
+    /// Closes the open elements and returns the document.
+    pub(crate) fn into_document(mut self) -> Document {
+        while !self.stack.is_empty() {
+            self.close();
+        }
+        Document::from_slate_value(&Value::Array(self.root))
+            .expect("the built value is valid Slate JSON")
+    }
+}
+
+fn is_inline_container(open: &OpenElement) -> bool {
+    INLINE_CONTAINERS
+        .iter()
+        .any(|name| is_type(&open.element, name))
+}
+
+pub(crate) fn is_type(element: &Map<String, Value>, name: &str) -> bool {
+    element.get("type").and_then(Value::as_str) == Some(name)
+}
+
+fn json_text(text: &str) -> Value {
+    let mut leaf = Map::new();
+    leaf.insert("text".to_string(), Value::String(text.to_string()));
+    Value::Object(leaf)
+}
+
+/// Puts text leaves around and between the inline elements, as Slate requires.
+fn with_edge_texts(children: Vec<Value>) -> Vec<Value> {
+    let mut result = Vec::with_capacity(children.len() + 2);
+    for child in children {
+        let is_text = child.get("text").is_some();
+        let after_text = result
+            .last()
+            .map_or(false, |last: &Value| last.get("text").is_some());
+        if !is_text && !after_text {
+            result.push(json_text(""));
+        }
+        result.push(child);
+    }
+    if result
+        .last()
+        .map_or(true, |last| last.get("text").is_none())
+    {
+        result.push(json_text(""));
+    }
+    result
+}
@@ -0,0 +1,284 @@
+use std::ops::Range;
+
+use serde_json::{Map, Value};
+
+use crate::{Document, Path};
+
+/// Change is a difference between two documents, see [`Document::diff`].
+///
+/// Removed nodes and the source of moves are addressed by their path in the old
+/// document, everything else by its path in the new document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// The node was inserted at the path of the new document.
+    Inserted { path: Path, node: Value },
+    /// The node at the path of the old document was removed.
+    Removed { path: Path, node: Value },
+    /// The node moved unchanged from `from` in the old document to `to` in the new
+    /// document.
+    Moved { from: Path, to: Path },
+    /// An attribute of the node changed, `None` if it is missing on that side.
+    AttributeChanged {
+        path: Path,
+        key: String,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+    /// The old text in `range`, in UTF-16 code units like [`Point`](crate::Point)
+    /// offsets, was replaced with `text`.
+    TextChanged {
+        path: Path,
+        range: Range<usize>,
+        text: String,
+    },
+}
+
+impl Document {
+    /// Compares the document with `other`, the newer version, and returns the
+    /// changes in document order.
+    ///
+    /// The children of each pair of matched nodes are aligned by their longest
+    /// common subsequence of nodes of the same kind, elements of the same type or
+    /// text leaves, preferring identical nodes. The paired nodes are compared in
+    /// turn. What stays unpaired is removed or inserted, unless an identical node
+    /// was removed or inserted elsewhere, which makes it a move.
+    ///
+    /// ```
+    /// use dom_query::{Change, Document, Path};
+    /// use serde_json::json;
+    ///
+    /// let old = Document::from_slate_html("<h1>Title</h1><p>Hello</p>");
+    /// let new = Document::from_slate_html("<p>Intro</p><h1>Title</h1><p>Hello world</p>");
+    ///
+    /// assert_eq!(
+    ///     old.diff(&new),
+    ///     vec![
+    ///         Change::Inserted {
+    ///             path: Path::new(vec![0]),
+    ///             node: json!({"type": "p", "children": [{"text": "Intro"}]}),
+    ///         },
+    ///         Change::TextChanged {
+    ///             path: Path::new(vec![2, 0]),
+    ///             range: 5..5,
+    ///             text: " world".to_string(),
+    ///         },
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff(&self, other: &Document) -> Vec<Change> {
+        let old = self.to_slate_value();
+        let new = other.to_slate_value();
+        let mut changes = vec![];
+        diff_children(
+            as_slice(&old),
+            as_slice(&new),
+            &Path::default(),
+            &Path::default(),
+            &mut changes,
+        );
+        find_moves(changes)
+    }
+}
+
+fn diff_children(
+    old: &[Value],
+    new: &[Value],
+    old_path: &Path,
+    new_path: &Path,
+    changes: &mut Vec<Change>,
+) {
+    let mut pairs = align(old, new);
+    pairs.push((old.len(), new.len()));
+
+    let (mut i, mut j) = (0, 0);
+    for (pair_i, pair_j) in pairs {
+        for (index, node) in old.iter().enumerate().take(pair_i).skip(i) {
+            changes.push(Change::Removed {
+                path: old_path.child(index),
+                node: node.clone(),
+            });
+        }
+        for (index, node) in new.iter().enumerate().take(pair_j).skip(j) {
+            changes.push(Change::Inserted {
+                path: new_path.child(index),
+                node: node.clone(),
+            });
+        }
+        if pair_i < old.len() && old[pair_i] != new[pair_j] {
+            diff_node(
+                &old[pair_i],
+                &new[pair_j],
+                &old_path.child(pair_i),
+                &new_path.child(pair_j),
+                changes,
+            );
+        }
+        i = pair_i + 1;
+        j = pair_j + 1;
+    }
+}
+
+/// Compares two nodes of the same kind.
+fn diff_node(
+    old: &Value,
+    new: &Value,
+    old_path: &Path,
+    new_path: &Path,
+    changes: &mut Vec<Change>,
+) {
+    let (old, new) = match (old.as_object(), new.as_object()) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return,
+    };
+
+    for key in old
+        .keys()
+        .chain(new.keys().filter(|key| !old.contains_key(*key)))
+    {
+        if matches!(key.as_str(), "type" | "children" | "text") {
+            continue;
+        }
+        let (old_value, new_value) = (old.get(key), new.get(key));
+        if old_value != new_value {
+            changes.push(Change::AttributeChanged {
+                path: new_path.clone(),
+                key: key.clone(),
+                old: old_value.cloned(),
+                new: new_value.cloned(),
+            });
+        }
+    }
+
+    if let (Some(Value::String(old_text)), Some(Value::String(new_text))) =
+        (old.get("text"), new.get("text"))
+    {
+        if let Some((range, text)) = diff_text(old_text, new_text) {
+            changes.push(Change::TextChanged {
+                path: new_path.clone(),
+                range,
+                text,
+            });
+        }
+    }
+
+    diff_children(children(old), children(new), old_path, new_path, changes);
+}
+
+/// Returns the range of the old text that was replaced, in UTF-16 code units, and
+/// its replacement, by trimming the common prefix and suffix of characters.
+fn diff_text(old: &str, new: &str) -> Option<(Range<usize>, String)> {
+    if old == new {
+        return None;
+    }
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let text = new[prefix..new.len() - suffix].iter().collect();
+    let units = |chars: &[char]| chars.iter().map(|c| c.len_utf16()).sum::<usize>();
+    let start = units(&old[..prefix]);
+    Some((start..start + units(&old[prefix..old.len() - suffix]), text))
+}
+
+/// Turns the pairs of a removed and an identical inserted node into moves, in the
+/// place of the removal.
+fn find_moves(changes: Vec<Change>) -> Vec<Change> {
+    let mut inserted: Vec<Option<(Path, Value)>> = changes
+        .iter()
+        .map(|change| match change {
+            Change::Inserted { path, node } => Some((path.clone(), node.clone())),
+            _ => None,
+        })
+        .collect();
+    let mut moved = vec![false; changes.len()];
+
+    let mut result = Vec::with_capacity(changes.len());
+    for (index, change) in changes.into_iter().enumerate() {
+        match change {
+            Change::Removed { path, node } => {
+                let target = inserted
+                    .iter_mut()
+                    .enumerate()
+                    .find(|(_, slot)| matches!(slot, Some((_, n)) if *n == node));
+                match target {
+                    Some((target_index, slot)) => {
+                        let (to, _) = slot.take().unwrap();
+                        moved[target_index] = true;
+                        result.push((index, Change::Moved { from: path, to }));
+                    }
+                    None => result.push((index, Change::Removed { path, node })),
+                }
+            }
+            change => result.push((index, change)),
+        }
+    }
+    result
+        .into_iter()
+        .filter(|(index, _)| !moved[*index])
+        .map(|(_, change)| change)
+        .collect()
+}
+
+/// Returns the index pairs of the nodes of the same kind, in order, aligned as a
+/// longest common subsequence. Identical nodes weigh more, but not more than two
+/// pairs of changed nodes.
+fn align(old: &[Value], new: &[Value]) -> Vec<(usize, usize)> {
+    let weight = |a: &Value, b: &Value| match (a == b, same_kind(a, b)) {
+        (true, _) => 3,
+        (false, true) => 2,
+        (false, false) => 0,
+    };
+
+    let (n, m) = (old.len(), new.len());
+    let mut scores = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            let skip = scores[i + 1][j].max(scores[i][j + 1]);
+            scores[i][j] = match weight(&old[i], &new[j]) {
+                0 => skip,
+                w => skip.max(scores[i + 1][j + 1] + w),
+            };
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        let w = weight(&old[i], &new[j]);
+        if w > 0 && scores[i][j] == scores[i + 1][j + 1] + w {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if scores[i][j] == scores[i + 1][j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Checks if both nodes are text leaves or elements of the same type.
+fn same_kind(a: &Value, b: &Value) -> bool {
+    match (a.get("text"), b.get("text")) {
+        (Some(_), Some(_)) => true,
+        (None, None) => a.get("type") == b.get("type"),
+        _ => false,
+    }
+}
+
+fn children(object: &Map<String, Value>) -> &[Value] {
+    object.get("children").map_or(&[], as_slice)
+}
+
+fn as_slice(value: &Value) -> &[Value] {
+    match value {
+        Value::Array(values) => values,
+        _ => &[],
+    }
+}
@@ -1,11 +1,14 @@
 use core::panic;
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::ops::Deref;
 
 use crate::dom_tree::{children_of, InnerNode, NodeRef, Tree};
-use crate::Node;
+use crate::span::{self, Source};
+use crate::{Node, Range, SourceSpan};
 use html5ever::serialize::{Serialize, Serializer, TraversalScope};
-use html5ever::{parse_document, LocalName};
+use html5ever::tree_builder::TreeBuilderOpts;
+use html5ever::{parse_document, LocalName, ParseOpts};
 use markup5ever::interface::tree_builder;
 use markup5ever::interface::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
 use markup5ever::Attribute;
@@ -18,7 +21,7 @@ use crate::entities::{HashSetFx, NodeId, NodeIdMap};
 
 use markup5ever::serialize::TraversalScope::{ChildrenOnly, IncludeNode};
 use markup5ever::{local_name, namespace_url, ns};
-use std::{io, usize};
+use std::io;
 
 /// Document represents an HTML document to be manipulated.
 pub struct Document {
@@ -30,6 +33,9 @@ pub struct Document {
 
     /// The document's quirks mode.
     pub quirks_mode: QuirksMode,
+
+    /// The Slate selection, updated by the applied operations.
+    pub(crate) selection: RefCell<Option<Range>>,
 }
 
 impl Default for Document {
@@ -38,6 +44,7 @@ impl Default for Document {
             tree: Tree::new(NodeData::Document),
             errors: vec![],
             quirks_mode: tree_builder::NoQuirks,
+            selection: RefCell::new(None),
         }
     }
 }
@@ -46,27 +53,281 @@ impl Document {
     pub fn from_slate_html(html: &str) -> Document {
         parse_document(DocumentTreeSink::default(), Default::default()).one(html)
     }
+
+    /// Parses the HTML like [`Document::from_slate_html`], keeping the nodes the
+    /// options ask for.
+    ///
+    /// ```
+    /// use dom_query::{Document, ParseOptions};
+    ///
+    /// let html = "<!DOCTYPE html><p>Hello</p><!-- draft -->";
+    /// let options = ParseOptions::new().keep_comments(true).keep_doctype(true);
+    /// let doc = Document::from_slate_html_with(html, &options);
+    /// assert_eq!(&*doc.html(), "<p><text>Hello</text></p><!-- draft -->");
+    /// assert!(doc.root().outer_html().starts_with("<!DOCTYPE html><html>"));
+    /// ```
+    pub fn from_slate_html_with(html: &str, options: &ParseOptions) -> Document {
+        let mut sink = DocumentTreeSink::new(options.clone());
+        if options.source_spans || options.preserve_case {
+            sink.3 = Some(Source::new(html, options.xml));
+            return match options.xml {
+                true => span::parse_xml(sink, html, xml_prolog_len(html)),
+                false => span::parse_html(sink, html),
+            };
+        }
+        if options.xml {
+            // XML has a single root, the content goes in the wrappers HTML adds.
+            let (prolog, content) = html.split_at(xml_prolog_len(html));
+            let mut parser = xml5ever::driver::parse_document(sink, Default::default());
+            parser.process(prolog.into());
+            parser.process("<html><head/><body>".into());
+            parser.process(content.into());
+            parser.process("</body></html>".into());
+            return parser.finish();
+        }
+        parse_document(sink, Default::default()).one(html)
+    }
+}
+
+/// Parses HTML to insert in a tree parsed with the options. The content goes in the
+/// node with id 2, like `html5ever::parse_fragment` puts it.
+pub(crate) fn parse_fragment(html: StrTendril, options: &ParseOptions) -> Document {
+    // The spans would point in the fragment, not in the source of the tree.
+    let mut sink = DocumentTreeSink::new(options.clone().source_spans(false));
+    let context = QualName::new(None, ns!(html), local_name!(""));
+    if options.xml {
+        // The context takes id 1, `html` takes id 2.
+        sink.create_element(context, vec![], Default::default());
+        let mut parser = xml5ever::driver::parse_document(sink, Default::default());
+        parser.process("<html>".into());
+        parser.process(html);
+        parser.process("</html>".into());
+        return parser.finish();
+    }
+    let opts = ParseOpts {
+        tokenizer: Default::default(),
+        tree_builder: TreeBuilderOpts {
+            exact_errors: false,
+            scripting_enabled: true,
+            iframe_srcdoc: false,
+            drop_doctype: true,
+            ignore_missing_rules: false,
+            quirks_mode: tree_builder::NoQuirks,
+        },
+    };
+    if options.preserve_case {
+        sink.3 = Some(Source::new(&html, false));
+        return span::parse_html_fragment(sink, &html, context, opts);
+    }
+    html5ever::parse_fragment(sink, opts, context, Vec::new()).one(html)
+}
+
+/// Returns the length of the prolog of an XML source: the declaration, doctype,
+/// comments and processing instructions before the content, and the whitespace
+/// around them.
+fn xml_prolog_len(xml: &str) -> usize {
+    let mut len = 0;
+    loop {
+        let rest = &xml[len..];
+        let trimmed = rest.trim_start();
+        len += rest.len() - trimmed.len();
+        let end = if trimmed.starts_with("<!--") {
+            trimmed.find("-->").map(|end| end + 3)
+        } else if trimmed.starts_with("<!") || trimmed.starts_with("<?") {
+            trimmed.find('>').map(|end| end + 1)
+        } else {
+            None
+        };
+        match end {
+            Some(end) => len += end,
+            None => return len,
+        }
+    }
+}
+
+/// ParseOptions configures the nodes kept by [`Document::from_slate_html_with`].
+/// Comments, the doctype and processing instructions are dropped by default, and
+/// source spans are not recorded.
+///
+/// Kept nodes are left out of the Slate value and have no Slate path, so the paths
+/// and the operations address the same nodes as without them.
+///
+/// The HTML tree construction rules apply by default, see [`ParseOptions::xml`] to
+/// build the tree as nested in the source.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    keep_comments: bool,
+    keep_doctype: bool,
+    keep_processing_instructions: bool,
+    source_spans: bool,
+    pub(crate) xml: bool,
+    preserve_case: bool,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the `<!-- -->` comments.
+    pub fn keep_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    /// Keeps the `<!DOCTYPE>`.
+    pub fn keep_doctype(mut self, keep: bool) -> Self {
+        self.keep_doctype = keep;
+        self
+    }
+
+    /// Keeps the `<?target data?>` processing instructions. HTML has none, its
+    /// parser reads them as comments.
+    pub fn keep_processing_instructions(mut self, keep: bool) -> Self {
+        self.keep_processing_instructions = keep;
+        self
+    }
+
+    /// Records where the elements and text leaves came from in the source, see
+    /// [`Node::source_span`](crate::Node::source_span).
+    ///
+    /// ```
+    /// use dom_query::{Document, ParseOptions};
+    ///
+    /// let html = "<h1><text>Title</text></h1>\n<p><text>Hello</text></p>";
+    /// let doc = Document::from_slate_html_with(html, &ParseOptions::new().source_spans(true));
+    /// let span = doc.select("p text").nodes()[0].source_span().unwrap();
+    /// assert_eq!((span.start.line, span.start.column), (2, 4));
+    /// assert_eq!(&html[span.start.offset..span.end.offset], "<text>Hello</text>");
+    /// ```
+    pub fn source_spans(mut self, record: bool) -> Self {
+        self.source_spans = record;
+        self
+    }
+
+    /// Parses the source as XML, which builds the tree exactly as nested in the
+    /// source: no element is closed, moved or added by the HTML rules, like a `p`
+    /// closing the `p` it is in or a `table` moving its text out. The content is
+    /// still placed in `html>body`, so the document has the usual shape.
+    ///
+    /// Every element must be closed, `<br/>` included, and only the XML entities
+    /// are known. The document serializes as XML, writing the end tag of every
+    /// element, so what it writes reads back the same.
+    ///
+    /// ```
+    /// use dom_query::{Document, ParseOptions};
+    ///
+    /// let html = "<p><text>a</text><p><text>b</text></p></p><table><text>c</text></table>";
+    /// let doc = Document::from_slate_html_with(html, &ParseOptions::new().xml(true));
+    /// assert_eq!(&*doc.html(), html);
+    ///
+    /// let doc = Document::from_slate_html(html);
+    /// assert_ne!(&*doc.html(), html);
+    /// ```
+    pub fn xml(mut self, xml: bool) -> Self {
+        self.xml = xml;
+        self
+    }
+
+    /// Keeps the case of the element and attribute names as written in the source,
+    /// like the `codeBlock` type or the `textAlign` property, which HTML lowercases.
+    /// The XML mode always keeps it. Selectors match the names as written then,
+    /// while they match lowercased in HTML.
+    ///
+    /// ```
+    /// use dom_query::{Document, ParseOptions};
+    ///
+    /// let html = r#"<codeBlock textAlign="left"><text>a</text></codeBlock>"#;
+    /// let doc = Document::from_slate_html_with(html, &ParseOptions::new().preserve_case(true));
+    /// assert_eq!(&*doc.html(), html);
+    /// assert_eq!(doc.select("codeBlock[textAlign]").length(), 1);
+    ///
+    /// let doc = Document::from_slate_html(html);
+    /// assert_eq!(&*doc.html(), r#"<codeblock textalign="left"><text>a</text></codeblock>"#);
+    /// ```
+    pub fn preserve_case(mut self, preserve: bool) -> Self {
+        self.preserve_case = preserve;
+        self
+    }
 }
 
 impl Document {
     /// Return the underlying root document node.
     #[inline]
-    pub fn root(&self) -> NodeRef<NodeData> {
+    pub fn root(&self) -> NodeRef<'_, NodeData> {
         self.tree.root()
     }
 }
 
-pub struct DocumentTreeSink(Document, NodeIdMap);
-impl Default for DocumentTreeSink {
-    fn default() -> Self {
-        Self(
-            Document::default(),
-            Default::default(),
-            // AtomicUsize::new(usize::MAX),
-        )
-    }
-}
+#[derive(Default)]
+pub struct DocumentTreeSink(Document, NodeIdMap, ParseOptions, Option<Source>);
+
 impl DocumentTreeSink {
+    pub fn new(options: ParseOptions) -> Self {
+        Self(Document::default(), NodeIdMap::default(), options, None)
+    }
+
+    /// Creates a text leaf for text without tags.
+    fn create_text(&mut self, text: StrTendril) -> NodeId {
+        let id = self.0.tree.create_node(NodeData::Text(Text::new(text)));
+        if let Some(source) = self.3.as_mut() {
+            source.text(id);
+        }
+        id
+    }
+
+    /// Appends text to a text node, returns whether it is one.
+    fn append_text_to(&mut self, id: &NodeId, text: &str) -> bool {
+        let appended = self
+            .0
+            .tree
+            .update_node(id, |node| append_to_existing_text(node, text))
+            .unwrap_or(false);
+        if let Some(source) = self.3.as_mut().filter(|_| appended) {
+            source.append_text(*id);
+        }
+        appended
+    }
+
+    pub(crate) fn source(&mut self) -> Option<&mut Source> {
+        self.3.as_mut()
+    }
+
+    /// Takes what the options ask for from the start tag of the token processed by
+    /// the tree builder.
+    pub(crate) fn token_processed(&mut self) {
+        let source = match self.3.as_mut() {
+            Some(source) => source,
+            None => return,
+        };
+        let (id, tag) = match source.processed() {
+            Some(processed) => processed,
+            None => return,
+        };
+        if self.2.source_spans {
+            let span = source.tag_span(&tag);
+            self.0.tree.update_node(&id, |node| node.source_span = Some(span));
+        }
+        if self.2.preserve_case {
+            self.0.tree.update_node(&id, |node| match node.data {
+                NodeData::Element(ref mut e) => {
+                    if e.name.eq_ignore_ascii_case(&tag.name) {
+                        e.name = tag.name.as_str().into();
+                    }
+                    restore_attr_case(&mut e.attrs, &tag.attrs);
+                }
+                NodeData::Text(ref mut t) => restore_attr_case(&mut t.attrs, &tag.attrs),
+                _ => {}
+            });
+        }
+    }
+
+    fn set_source_span(&mut self, id: &NodeId, span: Option<SourceSpan>) {
+        if span.is_some() {
+            self.0.tree.update_node(id, |node| node.source_span = span);
+        }
+    }
+
     fn ignored(&self) -> NodeId {
         NodeId::new(usize::MAX)
         // NodeId::new(self.2.fetch_sub(1, std::sync::atomic::Ordering::Release))
@@ -82,7 +343,14 @@ impl TreeSink for DocumentTreeSink {
 
     // Consume this sink and return the overall result of parsing.
     #[inline]
-    fn finish(self) -> Document {
+    fn finish(mut self) -> Document {
+        self.0.tree.options = self.2.clone();
+        self.0.tree.lowercase_names = !self.2.xml && !self.2.preserve_case;
+        if let Some(source) = self.3.take().filter(|_| self.2.source_spans) {
+            for (id, span) in source.finish(&self.0.tree) {
+                self.set_source_span(&id, Some(span));
+            }
+        }
         self.0
     }
 
@@ -105,8 +373,15 @@ impl TreeSink for DocumentTreeSink {
     // Get a handle to a template's template contents. The tree builder promises this will never be called with
     // something else than a template element.
     #[inline]
-    fn get_template_contents(&mut self, _target: &NodeId) -> NodeId {
-        self.ignored()
+    fn get_template_contents(&mut self, target: &NodeId) -> NodeId {
+        if self.is_ignored(target) {
+            return self.ignored();
+        }
+        self.0
+            .tree
+            .query_node(target, |node| node.template_contents)
+            .flatten()
+            .unwrap_or_else(|| self.ignored())
     }
 
     // Set the document's quirks mode.
@@ -124,7 +399,7 @@ impl TreeSink for DocumentTreeSink {
     // What is the name of the element?
     // Should never be called on a non-element node; Feel free to `panic!`.
     #[inline]
-    fn elem_name(&self, target: &NodeId) -> ExpandedName {
+    fn elem_name(&self, target: &NodeId) -> ExpandedName<'_> {
         self.1.get(target).expect("not an element").expanded()
     }
 
@@ -139,15 +414,14 @@ impl TreeSink for DocumentTreeSink {
         attrs: Vec<Attribute>,
         flags: ElementFlags,
     ) -> NodeId {
-        if flags.template {
-            panic!("not implemented")
-        }
-
         if name.local.deref() == "text" {
             let id = self.0.tree.create_node(NodeData::Text(Text::with_attrs(
                 "",
                 attrs.into_iter().map(Into::into).collect(),
             )));
+            if let Some(source) = self.3.as_mut() {
+                source.element(id, name.local.clone());
+            }
             self.1.insert(id, name.clone());
             return id;
         }
@@ -156,20 +430,38 @@ impl TreeSink for DocumentTreeSink {
             name.local.to_string(),
             attrs.into_iter().map(Into::into).collect(),
         )));
+        if flags.template {
+            let contents = self.0.tree.create_node(NodeData::Fragment);
+            self.0
+                .tree
+                .update_node(&id, |node| node.template_contents = Some(contents));
+        }
+        if let Some(source) = self.3.as_mut() {
+            source.element(id, name.local.clone());
+        }
         self.1.insert(id, name.clone());
         id
     }
 
     // Create a comment node.
     #[inline]
-    fn create_comment(&mut self, _text: StrTendril) -> NodeId {
-        self.ignored()
+    fn create_comment(&mut self, text: StrTendril) -> NodeId {
+        if !self.2.keep_comments {
+            return self.ignored();
+        }
+        self.0.tree.create_node(NodeData::Comment { contents: text })
     }
 
     // Create a Processing Instruction node.
     #[inline]
-    fn create_pi(&mut self, _target: StrTendril, _data: StrTendril) -> NodeId {
-        self.ignored()
+    fn create_pi(&mut self, target: StrTendril, data: StrTendril) -> NodeId {
+        if !self.2.keep_processing_instructions {
+            return self.ignored();
+        }
+        self.0.tree.create_node(NodeData::ProcessingInstruction {
+            target,
+            contents: data,
+        })
     }
 
     // Append a node as the last child of the given node. If this would produce adjacent sibling text nodes, it
@@ -192,16 +484,21 @@ impl TreeSink for DocumentTreeSink {
                 // text should only come as a childof <text>, if not wrap in <text>
                 if let Some(node) = self.0.tree.get(parent) {
                     if node.is_text() {
-                        self.0
-                            .tree
-                            .update_node(parent, |node| append_to_existing_text(node, &text));
+                        self.append_text_to(parent, &text);
                     } else {
-                        if let Some(first_child) = self.0.tree.first_child_of(parent) {
-                            self.0.tree.update_node(&first_child.id, |node| {
-                                append_to_existing_text(node, &text)
-                            });
+                        // Text after a kept comment starts a new leaf.
+                        let after_comment = self
+                            .0
+                            .tree
+                            .last_child_of(parent)
+                            .map_or(false, |child| !child.is_element() && !child.is_text());
+                        if after_comment {
+                            let node = self.create_text(text);
+                            self.0.tree.append_child_of(parent, &node);
+                        } else if let Some(first_child) = self.0.tree.first_child_of(parent) {
+                            self.append_text_to(&first_child.id, &text);
                         } else {
-                            let node = self.0.tree.create_node(NodeData::Text(Text::new(text)));
+                            let node = self.create_text(text);
                             self.0.tree.append_child_of(parent, &node);
                         }
                     }
@@ -220,20 +517,14 @@ impl TreeSink for DocumentTreeSink {
         }
         match child {
             NodeOrText::AppendText(text) => {
-                let prev_sibling = self.0.tree.prev_sibling_of(sibling);
-                let merged = prev_sibling
-                    .and_then(|sibling| {
-                        self.0
-                            .tree
-                            .update_node(&sibling.id, |node| append_to_existing_text(node, &text))
-                    })
-                    .unwrap_or(false);
+                let prev_sibling = self.0.tree.prev_sibling_of(sibling).map(|node| node.id);
+                let merged = prev_sibling.map_or(false, |id| self.append_text_to(&id, &text));
 
                 if merged {
                     return;
                 }
 
-                let id = self.0.tree.create_node(NodeData::Text(Text::new(text)));
+                let id = self.create_text(text);
                 self.0.tree.append_prev_sibling_of(sibling, &id);
             }
 
@@ -276,10 +567,20 @@ impl TreeSink for DocumentTreeSink {
     #[inline]
     fn append_doctype_to_document(
         &mut self,
-        _name: StrTendril,
-        _public_id: StrTendril,
-        _system_id: StrTendril,
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
     ) {
+        if !self.2.keep_doctype {
+            return;
+        }
+        let root_id = self.0.tree.root_id();
+        let id = self.0.tree.create_node(NodeData::Doctype {
+            name,
+            public_id,
+            system_id,
+        });
+        self.0.tree.append_child_of(&root_id, &id);
     }
 
     // Add each attribute to the given element, if no attribute with that name already exists. The tree builder
@@ -333,6 +634,15 @@ impl TreeSink for DocumentTreeSink {
     }
 }
 
+/// Renames the attributes, lowercased by HTML, as written in the source.
+fn restore_attr_case(attrs: &mut [Attrib], names: &[String]) {
+    for attr in attrs {
+        if let Some(name) = names.iter().find(|name| name.eq_ignore_ascii_case(&attr.name)) {
+            attr.name = name.as_str().into();
+        }
+    }
+}
+
 fn append_to_existing_text(prev: &mut InnerNode<NodeData>, text: &str) -> bool {
     match prev.data {
         NodeData::Text(ref mut t) => {
@@ -362,7 +672,7 @@ impl Attrib {
 }
 
 fn parse_json_or_use_as_string(value: &str) -> serde_json::Value {
-    if let Ok(value) = serde_json::from_str(&value) {
+    if let Ok(value) = serde_json::from_str(value) {
         return value;
     }
     serde_json::Value::String(value.into())
@@ -383,13 +693,32 @@ pub enum NodeData {
     /// The `Tree` itself - the root node of a HTML tree.
     Document,
 
+    /// The contents of a `template` element, a detached tree, see
+    /// [`Node::template_contents`].
+    Fragment,
+
     /// Text with attributes.
     Text(Text),
 
     /// An element with attributes.
     Element(Element),
-    // // a ghost node for representing comment/pi/doctype which are ignored when parsing
-    // Ghost,
+
+    /// A comment, kept if the parse options ask for it.
+    Comment { contents: StrTendril },
+
+    /// The `DOCTYPE` with its name, public id and system id, kept if the parse
+    /// options ask for it.
+    Doctype {
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
+    },
+
+    /// A processing instruction, kept if the parse options ask for it.
+    ProcessingInstruction {
+        target: StrTendril,
+        contents: StrTendril,
+    },
 }
 
 impl From<Text> for NodeData {
@@ -495,7 +824,7 @@ fn set_attr(attrs: &mut Vec<Attrib>, name: &str, value: serde_json::Value) {
     }
 }
 
-fn html_attribs(attrs: &Vec<Attrib>) -> Vec<(QualName, String)> {
+fn html_attribs(attrs: &[Attrib]) -> Vec<(QualName, String)> {
     attrs
         .iter()
         .map(|attr| {
@@ -518,7 +847,7 @@ enum SerializeOp {
 pub struct SerializableNodeRef<'a>(Node<'a>);
 
 impl<'a> From<NodeRef<'a, NodeData>> for SerializableNodeRef<'a> {
-    fn from(h: NodeRef<'a, NodeData>) -> SerializableNodeRef {
+    fn from(h: NodeRef<'a, NodeData>) -> SerializableNodeRef<'a> {
         SerializableNodeRef(h)
     }
 }
@@ -530,9 +859,21 @@ impl<'a> Serialize for SerializableNodeRef<'a> {
     {
         let nodes = self.0.tree.nodes.borrow();
         let id = self.0.id;
+        // The XML serializer declares the namespaces of the names.
+        let ns = match self.0.tree.options.xml {
+            true => ns!(),
+            false => ns!(html),
+        };
+        // The children of a template are its contents.
+        let contents_of = |id: NodeId| {
+            nodes
+                .get(id.value)
+                .and_then(|node| node.template_contents)
+                .unwrap_or(id)
+        };
         let mut ops = match traversal_scope {
             IncludeNode => vec![SerializeOp::Open(id)],
-            ChildrenOnly(_) => children_of(&nodes, &id)
+            ChildrenOnly(_) => children_of(&nodes, &contents_of(id))
                 .into_iter()
                 .map(SerializeOp::Open)
                 .collect(),
@@ -552,13 +893,14 @@ impl<'a> Serialize for SerializableNodeRef<'a> {
                         NodeData::Element(ref e) => {
                             let html_attribs = e.html_attribs();
                             serializer.start_elem(
-                                QualName::new(None, ns!(html), LocalName::from(&e.name[..])),
+                                QualName::new(None, ns.clone(), LocalName::from(&e.name[..])),
                                 html_attribs.iter().map(|v| (&v.0, &v.1[..])),
                             )?;
 
                             ops.insert(0, SerializeOp::Close(e.name.clone()));
 
-                            for child_id in children_of(&nodes, &id).into_iter().rev() {
+                            let children = children_of(&nodes, &contents_of(id));
+                            for child_id in children.into_iter().rev() {
                                 ops.insert(0, SerializeOp::Open(child_id));
                             }
 
@@ -567,7 +909,7 @@ impl<'a> Serialize for SerializableNodeRef<'a> {
                         NodeData::Text(ref e) => {
                             let html_attribs = e.html_attribs();
                             serializer.start_elem(
-                                QualName::new(None, ns!(html), local_name!("text")),
+                                QualName::new(None, ns.clone(), local_name!("text")),
                                 html_attribs.iter().map(|v| (&v.0, &v.1[..])),
                             )?;
 
@@ -577,7 +919,17 @@ impl<'a> Serialize for SerializableNodeRef<'a> {
 
                             Ok(())
                         }
-                        NodeData::Document => {
+                        NodeData::Comment { ref contents } => serializer.write_comment(contents),
+                        NodeData::Doctype {
+                            ref name,
+                            ref public_id,
+                            ref system_id,
+                        } => serializer.write_doctype(&doctype(name, public_id, system_id)),
+                        NodeData::ProcessingInstruction {
+                            ref target,
+                            ref contents,
+                        } => serializer.write_processing_instruction(target, contents),
+                        NodeData::Document | NodeData::Fragment => {
                             for child_id in children_of(&nodes, &id).into_iter().rev() {
                                 ops.insert(0, SerializeOp::Open(child_id));
                             }
@@ -586,7 +938,7 @@ impl<'a> Serialize for SerializableNodeRef<'a> {
                     }
                 }
                 SerializeOp::Close(name) => {
-                    serializer.end_elem(QualName::new(None, ns!(html), LocalName::from(&name[..])))
+                    serializer.end_elem(QualName::new(None, ns.clone(), LocalName::from(&name[..])))
                 }
             }?
         }
@@ -595,6 +947,21 @@ impl<'a> Serialize for SerializableNodeRef<'a> {
     }
 }
 
+/// Returns what goes between `<!DOCTYPE` and `>`, the name followed by the public
+/// and system ids if there are any. The serializers write it as is.
+fn doctype(name: &str, public_id: &str, system_id: &str) -> String {
+    let quote = |id: &str| match id.contains('"') {
+        true => format!("'{}'", id),
+        false => format!("\"{}\"", id),
+    };
+    match (public_id.is_empty(), system_id.is_empty()) {
+        (true, true) => name.to_string(),
+        (true, false) => format!("{} SYSTEM {}", name, quote(system_id)),
+        (false, true) => format!("{} PUBLIC {}", name, quote(public_id)),
+        (false, false) => format!("{} PUBLIC {} {}", name, quote(public_id), quote(system_id)),
+    }
+}
+
 impl Tree<NodeData> {
     pub fn set_name(&mut self, id: NodeId, name: StrTendril) {
         self.update_node(&id, |node| {
@@ -611,4 +978,22 @@ impl Tree<NodeData> {
         })
         .flatten()
     }
+
+    /// Returns the node whose children are the Slate content. html5ever wraps
+    /// parsed content in `html>body` (or just `html` for fragments), the wrappers
+    /// are not part of the Slate value.
+    pub(crate) fn content_root_id(&self) -> NodeId {
+        let root_id = self.root_id();
+        let child_named = |id: &NodeId, name: &str| {
+            self.children_of(id)
+                .into_iter()
+                .find(|child| self.get_name(&child.id).map_or(false, |n| &n[..] == name))
+                .map(|child| child.id)
+        };
+
+        match child_named(&root_id, "html") {
+            Some(html_id) => child_named(&html_id, "body").unwrap_or(html_id),
+            None => root_id,
+        }
+    }
 }
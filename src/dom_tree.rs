@@ -1,6 +1,7 @@
 use crate::document::Attrib;
 use crate::entities::{HashSetFx, NodeId};
-use crate::{NodeData, SerializableNodeRef};
+use crate::recorder::Recorder;
+use crate::{NodeData, ParseOptions, SerializableNodeRef, SourceSpan};
 use html5ever::serialize::{serialize, SerializeOpts, TraversalScope};
 use std::cell::{Ref, RefCell};
 use std::fmt::{self, Debug};
@@ -38,6 +39,14 @@ fn contains_class(classes: &serde_json::Value, target_class: &str) -> bool {
 
 pub struct Tree<T> {
     pub(crate) nodes: RefCell<Vec<InnerNode<T>>>,
+    /// Where the mutations are recorded as Slate operations.
+    pub(crate) recorder: RefCell<Recorder>,
+    /// The options the tree was parsed with, which the HTML inserted in it is
+    /// parsed with too. The tree serializes as XML if it was parsed as XML.
+    pub(crate) options: ParseOptions,
+    /// Whether the HTML parser lowercased the names, and so selectors match them
+    /// lowercased.
+    pub(crate) lowercase_names: bool,
 }
 
 impl<T: Debug> Debug for Tree<T> {
@@ -51,6 +60,9 @@ impl<T: Clone> Clone for Tree<T> {
         let nodes = self.nodes.borrow();
         Self {
             nodes: RefCell::new(nodes.clone()),
+            recorder: Default::default(),
+            options: self.options.clone(),
+            lowercase_names: self.lowercase_names,
         }
     }
 }
@@ -64,6 +76,9 @@ impl<T: Debug> Tree<T> {
         let root_id = NodeId::new(0);
         Self {
             nodes: RefCell::new(vec![InnerNode::new(root_id, root)]),
+            recorder: Default::default(),
+            options: ParseOptions::default(),
+            lowercase_names: false,
         }
     }
 
@@ -75,7 +90,7 @@ impl<T: Debug> Tree<T> {
         new_child_id
     }
 
-    pub fn get(&self, id: &NodeId) -> Option<NodeRef<T>> {
+    pub fn get(&self, id: &NodeId) -> Option<NodeRef<'_, T>> {
         let nodes = self.nodes.borrow();
         let node = nodes.get(id.value).map(|_| NodeRef {
             id: *id,
@@ -84,18 +99,18 @@ impl<T: Debug> Tree<T> {
         node
     }
 
-    pub fn get_unchecked(&self, id: &NodeId) -> NodeRef<T> {
+    pub fn get_unchecked(&self, id: &NodeId) -> NodeRef<'_, T> {
         NodeRef {
             id: *id,
             tree: self,
         }
     }
 
-    pub fn root(&self) -> NodeRef<T> {
+    pub fn root(&self) -> NodeRef<'_, T> {
         self.get_unchecked(&NodeId::new(0))
     }
 
-    pub fn children_of(&self, id: &NodeId) -> Vec<NodeRef<T>> {
+    pub fn children_of(&self, id: &NodeId) -> Vec<NodeRef<'_, T>> {
         let nodes = self.nodes.borrow();
         children_of(&nodes, id)
             .into_iter()
@@ -103,31 +118,31 @@ impl<T: Debug> Tree<T> {
             .collect()
     }
 
-    pub fn first_child_of(&self, id: &NodeId) -> Option<NodeRef<T>> {
+    pub fn first_child_of(&self, id: &NodeId) -> Option<NodeRef<'_, T>> {
         let nodes = self.nodes.borrow();
         let node = nodes.get(id.value)?;
         node.first_child.map(|id| NodeRef { id, tree: self })
     }
 
-    pub fn last_child_of(&self, id: &NodeId) -> Option<NodeRef<T>> {
+    pub fn last_child_of(&self, id: &NodeId) -> Option<NodeRef<'_, T>> {
         let nodes = self.nodes.borrow();
         let node = nodes.get(id.value)?;
         node.last_child.map(|id| NodeRef { id, tree: self })
     }
 
-    pub fn parent_of(&self, id: &NodeId) -> Option<NodeRef<T>> {
+    pub fn parent_of(&self, id: &NodeId) -> Option<NodeRef<'_, T>> {
         let nodes = self.nodes.borrow();
         let node = nodes.get(id.value)?;
         node.parent.map(|id| NodeRef { id, tree: self })
     }
 
-    pub fn prev_sibling_of(&self, id: &NodeId) -> Option<NodeRef<T>> {
+    pub fn prev_sibling_of(&self, id: &NodeId) -> Option<NodeRef<'_, T>> {
         let nodes = self.nodes.borrow();
         let node = nodes.get(id.value)?;
         node.prev_sibling.map(|id| NodeRef { id, tree: self })
     }
 
-    pub fn next_sibling_of(&self, id: &NodeId) -> Option<NodeRef<T>> {
+    pub fn next_sibling_of(&self, id: &NodeId) -> Option<NodeRef<'_, T>> {
         let nodes = self.nodes.borrow();
         let node = nodes.get(id.value)?;
         node.next_sibling.map(|id| NodeRef { id, tree: self })
@@ -160,6 +175,8 @@ impl<T: Debug> Tree<T> {
     }
 
     pub fn append_child_of(&self, id: &NodeId, new_child_id: &NodeId) {
+        self.remove_from_parent(new_child_id);
+
         let mut nodes = self.nodes.borrow_mut();
         let last_child_id = nodes.get_mut(id.value).and_then(|node| node.last_child);
 
@@ -247,18 +264,19 @@ impl<T: Debug> Tree<T> {
                 i => fix_id(Some(NodeId::new(i)), offset),
             });
 
+            node.id = fix_id(node.id, offset);
+            node.prev_sibling = fix_id(node.prev_sibling, offset);
+            node.next_sibling = fix_id(node.next_sibling, offset);
+            node.first_child = fix_id(node.first_child, offset);
+            node.last_child = fix_id(node.last_child, offset);
+            node.template_contents = fix_id(node.template_contents, offset);
+
             // Update prev_sibling_id
             if !first_valid_child && node.parent == Some(*id) {
                 first_valid_child = true;
 
                 node.prev_sibling = parent_last_child_id;
             }
-
-            node.id = fix_id(node.id, offset);
-            node.prev_sibling = fix_id(node.prev_sibling, offset);
-            node.next_sibling = fix_id(node.next_sibling, offset);
-            node.first_child = fix_id(node.first_child, offset);
-            node.last_child = fix_id(node.last_child, offset);
         }
 
         // Put all the new nodes except the root node into the nodes.
@@ -322,7 +340,7 @@ impl<T: Debug> Tree<T> {
         }
 
         let mut last_valid_child = 0;
-        let mut first_valid_child = true;
+        let mut first_valid_child = false;
         // Fix nodes's ref id.
         for (i, node) in new_nodes.iter_mut().enumerate() {
             node.parent = node
@@ -333,12 +351,6 @@ impl<T: Debug> Tree<T> {
                     i => fix_id(Some(NodeId::new(i)), offset),
                 });
 
-            // Update first child's prev_sibling
-            if !first_valid_child && node.parent == Some(*id) {
-                first_valid_child = true;
-                node.prev_sibling = prev_sibling_id;
-            }
-
             if node.parent == parent_id {
                 last_valid_child = i;
             }
@@ -348,6 +360,13 @@ impl<T: Debug> Tree<T> {
             node.last_child = fix_id(node.last_child, offset);
             node.prev_sibling = fix_id(node.prev_sibling, offset);
             node.next_sibling = fix_id(node.next_sibling, offset);
+            node.template_contents = fix_id(node.template_contents, offset);
+
+            // Update first child's prev_sibling
+            if !first_valid_child && node.parent == parent_id {
+                first_valid_child = true;
+                node.prev_sibling = prev_sibling_id;
+            }
         }
 
         // Update last child's next_sibling.
@@ -524,6 +543,10 @@ pub struct InnerNode<T> {
     pub first_child: Option<NodeId>,
     pub last_child: Option<NodeId>,
     pub data: T,
+    /// Where the node came from in the parsed source, if it was recorded.
+    pub source_span: Option<SourceSpan>,
+    /// The fragment holding the contents of a `template` element.
+    pub template_contents: Option<NodeId>,
 }
 
 impl<T> InnerNode<T> {
@@ -536,6 +559,8 @@ impl<T> InnerNode<T> {
             first_child: None,
             last_child: None,
             data,
+            source_span: None,
+            template_contents: None,
         }
     }
 }
@@ -550,6 +575,8 @@ impl<T: Debug> Debug for InnerNode<T> {
             .field("first_child", &self.first_child)
             .field("last_child", &self.last_child)
             .field("data", &self.data)
+            .field("source_span", &self.source_span)
+            .field("template_contents", &self.template_contents)
             .finish()
     }
 }
@@ -567,9 +594,9 @@ impl InnerNode<NodeData> {
         matches!(self.data, NodeData::Text { .. })
     }
 
-    // pub fn is_comment(&self) -> bool {
-    //     matches!(self.data, NodeData::Comment { .. })
-    // }
+    pub fn is_comment(&self) -> bool {
+        matches!(self.data, NodeData::Comment { .. })
+    }
 }
 
 impl<T: Clone> Clone for InnerNode<T> {
@@ -582,6 +609,8 @@ impl<T: Clone> Clone for InnerNode<T> {
             first_child: self.first_child,
             last_child: self.last_child,
             data: self.data.clone(),
+            source_span: self.source_span,
+            template_contents: self.template_contents,
         }
     }
 }
@@ -627,35 +656,43 @@ impl<'a, T: Debug> NodeRef<'a, T> {
     pub fn next_sibling(&self) -> Option<Self> {
         self.tree.next_sibling_of(&self.id)
     }
+}
+
+impl<'a> Node<'a> {
     #[inline]
     pub fn remove_from_parent(&self) {
-        self.tree.remove_from_parent(&self.id)
+        self.tree
+            .record_move(&self.id, || self.tree.remove_from_parent(&self.id))
     }
     #[inline]
     pub fn remove_children(&self) {
-        self.tree.remove_children_of(&self.id)
+        self.tree
+            .record_remove_children(&self.id, || self.tree.remove_children_of(&self.id))
     }
     #[inline]
     pub fn append_prev_sibling(&self, id: &NodeId) {
-        self.tree.append_prev_sibling_of(&self.id, id)
+        self.tree
+            .record_move(id, || self.tree.append_prev_sibling_of(&self.id, id))
     }
     #[inline]
     pub fn append_child(&self, id: &NodeId) {
-        self.tree.append_child_of(&self.id, id)
+        self.tree
+            .record_move(id, || self.tree.append_child_of(&self.id, id))
     }
     #[inline]
-    pub fn append_children_from_another_tree(&self, tree: Tree<T>) {
-        self.tree.append_children_from_another_tree(&self.id, tree)
+    pub fn append_children_from_another_tree(&self, tree: Tree<NodeData>) {
+        self.tree.record_insert_children(&self.id, || {
+            self.tree.append_children_from_another_tree(&self.id, tree)
+        })
     }
     #[inline]
-    pub fn append_prev_siblings_from_another_tree(&self, tree: Tree<T>) {
-        self.tree
-            .append_prev_siblings_from_another_tree(&self.id, tree)
+    pub fn append_prev_siblings_from_another_tree(&self, tree: Tree<NodeData>) {
+        self.tree.record_insert_prev_siblings(&self.id, || {
+            self.tree
+                .append_prev_siblings_from_another_tree(&self.id, tree)
+        })
     }
 
-}
-
-impl<'a> Node<'a> {
     pub fn next_element_sibling(&self) -> Option<Node<'a>> {
         let nodes = self.tree.nodes.borrow();
         let mut node = nodes.get(self.id.value)?;
@@ -699,6 +736,15 @@ impl<'a> Node<'a> {
         })?
     }
 
+    /// Returns the fragment holding the contents of a `template` element, `None` for
+    /// other nodes. The contents are not children of the template: selectors do not
+    /// match them and they are not part of the Slate value, but they are serialized
+    /// as the children of the template.
+    pub fn template_contents(&self) -> Option<Node<'a>> {
+        let id = self.query(|node| node.template_contents)??;
+        Some(NodeRef::new(id, self.tree))
+    }
+
     pub fn has_class(&self, class: &str) -> bool {
         self.query(|node| match node.data {
             NodeData::Element(ref e) => has_class(&e.attrs, class),
@@ -713,14 +759,16 @@ impl<'a> Node<'a> {
             return;
         }
 
-        self.update(|node| match node.data {
-            NodeData::Element(ref mut e) => {
-                add_class(&mut e.attrs, class);
-            }
-            NodeData::Text(ref mut e) => {
-                add_class(&mut e.attrs, class);
-            }
-            _ => {}
+        self.tree.record_set_node(&self.id, || {
+            self.update(|node| match node.data {
+                NodeData::Element(ref mut e) => {
+                    add_class(&mut e.attrs, class);
+                }
+                NodeData::Text(ref mut e) => {
+                    add_class(&mut e.attrs, class);
+                }
+                _ => {}
+            });
         });
     }
 
@@ -729,14 +777,16 @@ impl<'a> Node<'a> {
             return;
         }
 
-        self.update(|node| match node.data {
-            NodeData::Element(ref mut e) => {
-                remove_class(&mut e.attrs, class);
-            }
-            NodeData::Text(ref mut e) => {
-                remove_class(&mut e.attrs, class);
-            }
-            _ => {}
+        self.tree.record_set_node(&self.id, || {
+            self.update(|node| match node.data {
+                NodeData::Element(ref mut e) => {
+                    remove_class(&mut e.attrs, class);
+                }
+                NodeData::Text(ref mut e) => {
+                    remove_class(&mut e.attrs, class);
+                }
+                _ => {}
+            });
         });
     }
 
@@ -782,14 +832,16 @@ impl<'a> Node<'a> {
     }
 
     pub fn set_attr(&self, name: &str, value: serde_json::Value) {
-        self.update(|node| match node.data {
-            NodeData::Element(ref mut e) => {
-                e.set_attr(name, value);
-            }
-            NodeData::Text(ref mut e) => {
-                e.set_attr(name, value);
-            }
-            _ => {}
+        self.tree.record_set_node(&self.id, || {
+            self.update(|node| match node.data {
+                NodeData::Element(ref mut e) => {
+                    e.set_attr(name, value);
+                }
+                NodeData::Text(ref mut e) => {
+                    e.set_attr(name, value);
+                }
+                _ => {}
+            });
         });
     }
     // pub(crate) fn set_attr_parse_json_or_use_as_string(&self, name: &str, value: &str) {
@@ -805,19 +857,21 @@ impl<'a> Node<'a> {
     // }
 
     pub fn remove_attr(&self, name: &str) {
-        self.update(|node| match node.data {
-            NodeData::Element(ref mut e) => {
-                e.remove_attr(name);
-            }
-            NodeData::Text(ref mut e) => {
-                e.remove_attr(name);
-            }
-            _ => {}
+        self.tree.record_set_node(&self.id, || {
+            self.update(|node| match node.data {
+                NodeData::Element(ref mut e) => {
+                    e.remove_attr(name);
+                }
+                NodeData::Text(ref mut e) => {
+                    e.remove_attr(name);
+                }
+                _ => {}
+            });
         });
     }
 }
 
-fn has_class(attrs: &Vec<Attrib>, class: &str) -> bool {
+fn has_class(attrs: &[Attrib], class: &str) -> bool {
     attrs
         .iter()
         .find(|attr| &attr.name[..] == "class")
@@ -826,8 +880,7 @@ fn has_class(attrs: &Vec<Attrib>, class: &str) -> bool {
 }
 
 fn add_class(attrs: &mut Vec<Attrib>, class: &str) {
-    let find = attrs.iter_mut().find(|attr| &attr.name[..] == "class");
-    let mut attr = find;
+    let attr = attrs.iter_mut().find(|attr| &attr.name[..] == "class");
 
     let set: HashSetFx<&str> = class
         .split(' ')
@@ -835,8 +888,8 @@ fn add_class(attrs: &mut Vec<Attrib>, class: &str) {
         .filter(|s| !s.is_empty())
         .collect();
 
-    if attr.is_some() {
-        let value = &mut attr.as_mut().unwrap().value;
+    if let Some(attr) = attr {
+        let value = &mut attr.value;
         for v in set {
             if !contains_class(value, v) {
                 json_push_slice(value, " ");
@@ -852,7 +905,7 @@ fn add_class(attrs: &mut Vec<Attrib>, class: &str) {
     }
 }
 
-fn remove_class(attrs: &mut Vec<Attrib>, class: &str) {
+fn remove_class(attrs: &mut [Attrib], class: &str) {
     if let Some(attr) = attrs.iter_mut().find(|attr| &attr.name[..] == "class") {
         let mut set: HashSetFx<String> = attr
             .get_value_as_str_if_string()
@@ -887,43 +940,41 @@ impl<'a> Node<'a> {
     pub fn is_text(&self) -> bool {
         self.query(|node| node.is_text()).unwrap_or(false)
     }
+
+    pub fn is_comment(&self) -> bool {
+        self.query(|node| node.is_comment()).unwrap_or(false)
+    }
 }
 
 impl<'a> Node<'a> {
     /// Returns the HTML representation of the DOM tree.
     /// Panics if serialization fails.
     pub fn outer_html(&self) -> StrTendril {
-      let inner: SerializableNodeRef = self.clone().into();
-
-      let mut result = vec![];
-      serialize(
-          &mut result,
-          &inner,
-          SerializeOpts {
-              scripting_enabled: true,
-              traversal_scope: TraversalScope::IncludeNode,
-              create_missing_parent: false,
-          },
-      )
-      .unwrap();
-      StrTendril::try_from_byte_slice(&result).unwrap()
-  }
-  pub fn inner_html(&self) -> StrTendril {
-    let inner: SerializableNodeRef = self.clone().into();
-
-    let mut result = vec![];
-    serialize(
-        &mut result,
-        &inner,
-        SerializeOpts {
-            scripting_enabled: true,
-            traversal_scope: TraversalScope::ChildrenOnly(None),
-            create_missing_parent: false,
-        },
-    )
-    .unwrap();
-    StrTendril::try_from_byte_slice(&result).unwrap()
-}
+        self.serialize_markup(TraversalScope::IncludeNode)
+    }
+
+    pub fn inner_html(&self) -> StrTendril {
+        self.serialize_markup(TraversalScope::ChildrenOnly(None))
+    }
+
+    /// Serializes the node, as XML if the tree was parsed as XML.
+    fn serialize_markup(&self, traversal_scope: TraversalScope) -> StrTendril {
+        let inner: SerializableNodeRef = self.clone().into();
+
+        let mut result = vec![];
+        if self.tree.options.xml {
+            let opts = xml5ever::serialize::SerializeOpts { traversal_scope };
+            xml5ever::serialize::serialize(&mut result, &inner, opts).unwrap();
+        } else {
+            let opts = SerializeOpts {
+                scripting_enabled: true,
+                traversal_scope,
+                create_missing_parent: false,
+            };
+            serialize(&mut result, &inner, opts).unwrap();
+        }
+        StrTendril::try_from_byte_slice(&result).unwrap()
+    }
 
     pub fn text(&self) -> StrTendril {
         let mut ops = vec![self.id];
@@ -933,7 +984,7 @@ impl<'a> Node<'a> {
             let id = ops.remove(0);
             if let Some(node) = nodes.get(id.value) {
                 match node.data {
-                    NodeData::Element(_) => {
+                    NodeData::Element(_) | NodeData::Fragment => {
                         for child in children_of(&nodes, &id).into_iter().rev() {
                             ops.insert(0, child);
                         }
@@ -954,7 +1005,7 @@ impl<'a> Node<'a> {
             let id = ops.remove(0);
             if let Some(node) = nodes.get(id.value) {
                 match node.data {
-                    NodeData::Element(_) => {
+                    NodeData::Element(_) | NodeData::Fragment => {
                         for child in children_of(&nodes, &id).into_iter().rev() {
                             ops.insert(0, child);
                         }
@@ -975,10 +1026,7 @@ impl<'a> Node<'a> {
 }
 
 fn json_push_slice(value: &mut serde_json::Value, slice: &str) {
-    match value {
-        serde_json::Value::String(s) => {
-            s.push_str(slice);
-        }
-        _ => {}
+    if let serde_json::Value::String(s) = value {
+        s.push_str(slice);
     }
 }
@@ -57,12 +57,11 @@ impl<'a> selectors::Element for Node<'a> {
 
     #[inline]
     fn is_html_element_in_html_document(&self) -> bool {
-        self.query(|node| match node.data {
-            NodeData::Element(_) => true,
-            NodeData::Text(_) => true,
-            _ => false,
-        })
-        .unwrap_or(false)
+        // Selectors are lowercased to match the names the HTML parser lowercased.
+        self.tree.lowercase_names
+            && self
+                .query(|node| matches!(node.data, NodeData::Element(_) | NodeData::Text(_)))
+                .unwrap_or(false)
     }
 
     #[inline]
@@ -83,12 +82,8 @@ impl<'a> selectors::Element for Node<'a> {
     #[inline]
     fn has_namespace(&self, ns: &<Self::Impl as SelectorImpl>::BorrowedNamespaceUrl) -> bool {
         self.query(|node| match node.data {
-            NodeData::Element(_) => {
-                return ns == &ns!(html);
-            }
-            NodeData::Text(_) => {
-                return ns == &ns!(html);
-            }
+            NodeData::Element(_) => ns == &ns!(html),
+            NodeData::Text(_) => ns == &ns!(html),
             _ => false,
         })
         .unwrap_or(false)
@@ -255,21 +250,21 @@ impl<'a> selectors::Element for Node<'a> {
 }
 
 fn attr_matches(
-    attribs: &Vec<Attrib>,
+    attribs: &[Attrib],
     local_name: &CssLocalName,
     operation: &AttrSelectorOperation<&crate::css::CssString>,
 ) -> bool {
-    return attribs.iter().any(|attr| {
+    attribs.iter().any(|attr| {
         if local_name.deref() != &attr.name[..] {
             return false;
         }
         match &attr.value {
-            serde_json::Value::String(v) => operation.eval_str(&v),
+            serde_json::Value::String(v) => operation.eval_str(v),
             serde_json::Value::Number(v) => operation.eval_str(&v.to_string()),
             serde_json::Value::Bool(v) => operation.eval_str(&v.to_string()),
             _ => false,
         }
-    });
+    })
 }
 
 fn has_descendant_match(
@@ -1,5 +1,3 @@
-use std::usize;
-
 use markup5ever::QualName;
 
 /// Node ID.
@@ -0,0 +1,150 @@
+use crate::{Document, Operation, OperationError};
+
+/// History is the undo stack of a document, the recorded operations grouped into
+/// batches. Undoing a batch applies the inverse of its operations in reverse order.
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    undos: Vec<Vec<Operation>>,
+    redos: Vec<Vec<Operation>>,
+    /// The operations of the batch that is not closed yet.
+    pending: Vec<Operation>,
+}
+
+impl History {
+    /// Adds an operation to the pending batch. A new change makes the undone batches
+    /// unreachable, so they are dropped.
+    pub(crate) fn record(&mut self, op: Operation) {
+        self.pending.push(op);
+        self.redos.clear();
+    }
+
+    fn close_batch(&mut self) {
+        if !self.pending.is_empty() {
+            self.undos.push(std::mem::take(&mut self.pending));
+        }
+    }
+}
+
+impl Document {
+    /// Starts keeping an undo history. From then on the recorded changes, see
+    /// [`Document::start_recording`], can be undone with [`Document::undo`].
+    pub fn enable_history(&self) {
+        let mut recorder = self.tree.recorder.borrow_mut();
+        if recorder.history.is_none() {
+            recorder.history = Some(History::default());
+        }
+    }
+
+    /// Stops keeping the undo history and drops it.
+    pub fn disable_history(&self) {
+        self.tree.recorder.borrow_mut().history = None;
+    }
+
+    /// Drops the undo and redo stacks, keeping the history enabled.
+    pub fn clear_history(&self) {
+        if let Some(history) = self.tree.recorder.borrow_mut().history.as_mut() {
+            *history = History::default();
+        }
+    }
+
+    /// Closes the current batch, the changes made after it are undone separately.
+    /// The batch is also closed by `undo`, `redo` and `batch`.
+    pub fn checkpoint(&self) {
+        if let Some(history) = self.tree.recorder.borrow_mut().history.as_mut() {
+            history.close_batch();
+        }
+    }
+
+    /// Runs `f` and groups the changes it makes into one batch, undone at once.
+    pub fn batch<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        self.checkpoint();
+        let r = f();
+        self.checkpoint();
+        r
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.tree
+            .recorder
+            .borrow()
+            .history
+            .as_ref()
+            .map_or(false, |h| !h.undos.is_empty() || !h.pending.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.tree
+            .recorder
+            .borrow()
+            .history
+            .as_ref()
+            .map_or(false, |h| !h.redos.is_empty())
+    }
+
+    /// Undoes the last batch of changes. Returns `false` if there is nothing to undo.
+    /// The operations applied to undo are recorded like any other, so connected
+    /// editors can follow. If one of them fails, the ones applied before it are
+    /// reverted and the batch stays on the undo stack.
+    pub fn undo(&self) -> Result<bool, OperationError> {
+        self.checkpoint();
+        let batch = match self.history_mut(|h| h.undos.pop()).flatten() {
+            Some(batch) => batch,
+            None => return Ok(false),
+        };
+
+        let inverses: Vec<_> = batch.iter().rev().map(Operation::inverse).collect();
+        if let Err(err) = self.apply_without_history(&inverses) {
+            self.history_mut(|h| h.undos.push(batch));
+            return Err(err);
+        }
+        self.history_mut(|h| h.redos.push(batch));
+        Ok(true)
+    }
+
+    /// Redoes the last undone batch. Returns `false` if there is nothing to redo.
+    /// Like [`Document::undo`], a batch that fails is reverted and stays on the
+    /// redo stack.
+    pub fn redo(&self) -> Result<bool, OperationError> {
+        self.checkpoint();
+        let batch = match self.history_mut(|h| h.redos.pop()).flatten() {
+            Some(batch) => batch,
+            None => return Ok(false),
+        };
+
+        if let Err(err) = self.apply_without_history(&batch) {
+            self.history_mut(|h| h.redos.push(batch));
+            return Err(err);
+        }
+        self.history_mut(|h| h.undos.push(batch));
+        Ok(true)
+    }
+
+    fn history_mut<F, B>(&self, f: F) -> Option<B>
+    where
+        F: FnOnce(&mut History) -> B,
+    {
+        self.tree.recorder.borrow_mut().history.as_mut().map(f)
+    }
+
+    /// Applies the operations with the history put aside, so they are not recorded
+    /// as new changes. If one fails, the ones applied before it are reverted.
+    fn apply_without_history(&self, ops: &[Operation]) -> Result<(), OperationError> {
+        let history = self.tree.recorder.borrow_mut().history.take();
+        let mut result = Ok(());
+        for (i, op) in ops.iter().enumerate() {
+            if let Err(err) = self.apply(op) {
+                for applied in ops[..i].iter().rev() {
+                    let reverted = self.apply(&applied.inverse());
+                    debug_assert!(reverted.is_ok(), "an applied operation can be reverted");
+                }
+                result = Err(err);
+                break;
+            }
+        }
+        self.tree.recorder.borrow_mut().history = history;
+        result
+    }
+}
@@ -0,0 +1,168 @@
+use std::ops;
+
+use crate::range::text_len;
+use crate::{Document, Node, Path, Point, Range, Selection};
+
+/// TextIndex maps character offsets in the concatenated text of the leaves, the
+/// string of `Node::text`, to points in the document and back. Like [`Point`],
+/// offsets count UTF-16 code units.
+///
+/// ```
+/// use dom_query::{Document, Path, Point, Range};
+///
+/// let doc = Document::from_slate_html(
+///     r#"<p><text>Hello </text><text bold="true">wörld</text></p><p><text>!</text></p>"#,
+/// );
+/// let index = doc.text_index();
+/// assert_eq!(index.text(), "Hello wörld!");
+///
+/// let range = index.range(6..11).unwrap();
+/// assert_eq!(
+///     range,
+///     Range::new(
+///         Point::new(Path::from(&[0, 1][..]), 0),
+///         Point::new(Path::from(&[0, 1][..]), 5),
+///     )
+/// );
+/// assert_eq!(index.span(&range), Some(6..11));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextIndex {
+    text: String,
+    leaves: Vec<TextLeaf>,
+}
+
+/// A text leaf of a [`TextIndex`], with its place in the concatenated text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextLeaf {
+    pub path: Path,
+    /// The offset of the start of the leaf.
+    pub start: usize,
+    /// The length of the leaf in UTF-16 code units.
+    pub len: usize,
+}
+
+impl TextLeaf {
+    /// Returns the offset right after the last character of the leaf.
+    pub fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+impl TextIndex {
+    fn new(leaves: Vec<(Path, Node<'_>)>) -> Self {
+        let mut text = String::new();
+        let mut start = 0;
+        let leaves = leaves
+            .into_iter()
+            .map(|(path, node)| {
+                let contents = node.text();
+                let len = text_len(&contents);
+                text.push_str(&contents);
+                let leaf = TextLeaf { path, start, len };
+                start += len;
+                leaf
+            })
+            .collect();
+        Self { text, leaves }
+    }
+
+    /// Returns the concatenated text of the leaves.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the length of the text in UTF-16 code units.
+    pub fn len(&self) -> usize {
+        self.leaves.last().map_or(0, TextLeaf::end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the leaves in the order of the text.
+    pub fn leaves(&self) -> &[TextLeaf] {
+        &self.leaves
+    }
+
+    /// Returns the leaf with the character at the offset, or the last leaf for the
+    /// end of the text. Returns `None` if the offset is past the end.
+    pub fn leaf_at(&self, offset: usize) -> Option<&TextLeaf> {
+        if offset > self.len() {
+            return None;
+        }
+        let index = self.leaves.partition_point(|leaf| leaf.end() <= offset);
+        self.leaves.get(index).or_else(|| self.leaves.last())
+    }
+
+    /// Returns the point at the offset. Between two leaves, the point is at the start
+    /// of the next leaf.
+    pub fn point(&self, offset: usize) -> Option<Point> {
+        let leaf = self.leaf_at(offset)?;
+        Some(Point::new(leaf.path.clone(), offset - leaf.start))
+    }
+
+    /// Returns the range covering the span of characters. The edges stay in the leaves
+    /// of the characters: between two leaves, the start is at the start of the next
+    /// leaf and the end at the end of the previous one.
+    pub fn range(&self, span: ops::Range<usize>) -> Option<Range> {
+        let anchor = self.point(span.start)?;
+        if span.end <= span.start {
+            return Some(Range::collapsed(anchor));
+        }
+        if span.end > self.len() {
+            return None;
+        }
+        let index = self.leaves.partition_point(|leaf| leaf.end() < span.end);
+        let leaf = &self.leaves[index];
+        let focus = Point::new(leaf.path.clone(), span.end - leaf.start);
+        Some(Range::new(anchor, focus))
+    }
+
+    /// Returns the offset of the point. Returns `None` if the point is not in one of
+    /// the leaves or is past the end of its leaf.
+    pub fn offset(&self, point: &Point) -> Option<usize> {
+        let leaf = self.leaves.iter().find(|leaf| leaf.path == point.path)?;
+        (point.offset <= leaf.len).then_some(leaf.start + point.offset)
+    }
+
+    /// Returns the span of characters the range covers, from its start to its end.
+    pub fn span(&self, range: &Range) -> Option<ops::Range<usize>> {
+        let (start, end) = range.edges();
+        Some(self.offset(start)?..self.offset(end)?)
+    }
+}
+
+impl Document {
+    /// Returns the index of the text leaves of the document content.
+    pub fn text_index(&self) -> TextIndex {
+        TextIndex::new(self.leaves())
+    }
+}
+
+impl<'a> Selection<'a> {
+    /// Returns the index of the text leaves of the matched nodes, in the order of the
+    /// nodes, like `Selection::text` concatenates them. The nodes outside of the
+    /// document content have no path and are left out.
+    pub fn text_index(&self) -> TextIndex {
+        let mut leaves = vec![];
+        for node in self.nodes() {
+            let path = match node.path() {
+                Some(path) => path,
+                None => continue,
+            };
+            let mut ops = vec![(path, node.clone())];
+            while let Some((path, node)) = ops.pop() {
+                if node.is_text() {
+                    leaves.push((path, node));
+                    continue;
+                }
+                for (i, child) in node.content_children().into_iter().enumerate().rev() {
+                    ops.push((path.child(i), child));
+                }
+            }
+        }
+        TextIndex::new(leaves)
+    }
+}
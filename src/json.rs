@@ -0,0 +1,325 @@
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+use tendril::StrTendril;
+
+use crate::dom_tree::Tree;
+use crate::{Attrib, Document, Element, Node, NodeData, NodeId, ParseOptions, Text};
+
+impl Document {
+    /// Creates a document from a Slate JSON string. The input is either an array of
+    /// Slate nodes (the editor value) or a single node.
+    ///
+    /// The nodes are placed inside `html>body`, so the document has the same shape
+    /// as one created by [`Document::from_slate_html`].
+    pub fn from_slate_json(json: &str) -> Result<Document, serde_json::Error> {
+        let value: Value = serde_json::from_str(json)?;
+        Self::from_slate_value(&value)
+    }
+
+    /// Creates a document from a Slate value. See [`Document::from_slate_json`].
+    pub fn from_slate_value(value: &Value) -> Result<Document, serde_json::Error> {
+        let nodes = match value {
+            Value::Array(nodes) => nodes.iter().collect(),
+            Value::Object(_) => vec![value],
+            _ => return Err(serde_json::Error::custom("expected an array of slate nodes")),
+        };
+
+        let mut document = Document::default();
+        // The names are kept as given, so are the names of the inserted HTML.
+        document.tree.options = ParseOptions::new().preserve_case(true);
+        let tree = &document.tree;
+
+        let html = tree.create_node(Element::new("html").into());
+        let head = tree.create_node(Element::new("head").into());
+        let body = tree.create_node(Element::new("body").into());
+        tree.append_child_of(&tree.root_id(), &html);
+        tree.append_child_of(&html, &head);
+        tree.append_child_of(&html, &body);
+
+        for node in nodes {
+            let id = tree.create_slate_node(node)?;
+            tree.append_child_of(&body, &id);
+        }
+
+        Ok(document)
+    }
+}
+
+impl Tree<NodeData> {
+    /// Creates a detached subtree from a Slate node. Nodes with a `text` key become
+    /// text leaves, any other object becomes an element named after its `type`.
+    pub(crate) fn create_slate_node(&self, value: &Value) -> Result<NodeId, serde_json::Error> {
+        let object = match value {
+            Value::Object(object) => object,
+            _ => return Err(serde_json::Error::custom("expected a slate node object")),
+        };
+
+        if object.contains_key("text") {
+            return Ok(self.create_node(text_from_map::<serde_json::Error>(object)?.into()));
+        }
+
+        let id = self.create_node(element_from_map::<serde_json::Error>(object)?.into());
+
+        match object.get("children") {
+            Some(Value::Array(children)) => {
+                for child in children {
+                    let child_id = self.create_slate_node(child)?;
+                    self.append_child_of(&id, &child_id);
+                }
+            }
+            None => {}
+            Some(_) => return Err(serde_json::Error::custom("slate `children` must be an array")),
+        }
+
+        Ok(id)
+    }
+}
+
+fn slate_map(attrs: &[Attrib]) -> Map<String, Value> {
+    attrs
+        .iter()
+        .map(|attr| (attr.name.to_string(), attr.value.clone()))
+        .collect()
+}
+
+fn slate_attrs(object: &Map<String, Value>, reserved: &[&str]) -> Vec<Attrib> {
+    object
+        .iter()
+        .filter(|(name, _)| !reserved.contains(&name.as_str()))
+        .map(|(name, value)| Attrib {
+            name: name.as_str().into(),
+            value: value.clone(),
+        })
+        .collect()
+}
+
+impl Document {
+    /// Returns the document content as a Slate value, an array of the top level nodes.
+    pub fn to_slate_value(&self) -> Value {
+        self.root().to_slate_value()
+    }
+
+    /// Returns the document content as a Slate JSON string.
+    pub fn to_slate_json(&self) -> String {
+        self.to_slate_value().to_string()
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Returns the node as a Slate value. Elements become `{type, children, ...attrs}`
+    /// and text leaves `{text, ...attrs}`. The document node becomes an array of its
+    /// content, without the `html>body` wrappers. Comments and processing
+    /// instructions are left out, and so are the attributes named like the Slate
+    /// keys, `type` and `children` on elements and `text` on leaves.
+    pub fn to_slate_value(&self) -> Value {
+        let tree = self.tree;
+        if self.is_document() {
+            let content_id = tree.content_root_id();
+            return Value::Array(
+                tree.children_of(&content_id)
+                    .iter()
+                    .filter(|child| child.is_element() || child.is_text())
+                    .map(|child| child.to_slate_value())
+                    .collect(),
+            );
+        }
+
+        let mut object = slate_map(&self.attrs());
+
+        let leaf = self.query(|node| match node.data {
+            NodeData::Text(ref t) => Some(t.contents.to_string()),
+            _ => None,
+        });
+        match leaf.flatten() {
+            Some(text) => {
+                object.insert("text".to_string(), Value::String(text));
+            }
+            None => {
+                let name = self.node_name().unwrap_or_default();
+                let children = self
+                    .children()
+                    .iter()
+                    .filter(|c| c.is_element() || c.is_text())
+                    .map(|c| c.to_slate_value())
+                    .collect();
+                object.insert("type".to_string(), Value::String(name.to_string()));
+                object.insert("children".to_string(), Value::Array(children));
+            }
+        }
+
+        Value::Object(object)
+    }
+}
+
+/// A document serializes as its Slate value, the content of `html>body` only. The
+/// head, the nodes outside of the Slate value, the parse errors and the quirks mode
+/// are not part of it, and a deserialized document is built like
+/// [`Document::from_slate_value`]. Slate objects have no key order, so attributes
+/// come back sorted by name, and attributes named like the Slate keys are lost, see
+/// [`Node::to_slate_value`].
+impl Serialize for Document {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_slate_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Document::from_slate_value(&value).map_err(D::Error::custom)
+    }
+}
+
+/// Elements and text leaves serialize as their Slate objects without children. The
+/// other nodes are not part of Slate values, they serialize as objects keyed by
+/// their kind: `{"document": true}`, `{"fragment": true}`, `{"comment": "..."}`,
+/// `{"doctype": "html", "publicId": "", "systemId": ""}` and
+/// `{"processingInstruction": "target", "data": "..."}`.
+impl Serialize for NodeData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = match self {
+            NodeData::Text(text) => return text.serialize(serializer),
+            NodeData::Element(element) => return element.serialize(serializer),
+            NodeData::Document => json!({"document": true}),
+            NodeData::Fragment => json!({"fragment": true}),
+            NodeData::Comment { contents } => json!({"comment": &contents[..]}),
+            NodeData::Doctype {
+                name,
+                public_id,
+                system_id,
+            } => json!({
+                "doctype": &name[..],
+                "publicId": &public_id[..],
+                "systemId": &system_id[..],
+            }),
+            NodeData::ProcessingInstruction { target, contents } => json!({
+                "processingInstruction": &target[..],
+                "data": &contents[..],
+            }),
+        };
+        value.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let object = Map::deserialize(deserializer)?;
+        let string = |key: &str| string_from_map::<D::Error>(&object, key);
+        if object.contains_key("text") {
+            text_from_map(&object).map(NodeData::Text)
+        } else if object.contains_key("type") {
+            element_from_map(&object).map(NodeData::Element)
+        } else if object.get("document") == Some(&Value::Bool(true)) {
+            Ok(NodeData::Document)
+        } else if object.get("fragment") == Some(&Value::Bool(true)) {
+            Ok(NodeData::Fragment)
+        } else if object.contains_key("comment") {
+            Ok(NodeData::Comment {
+                contents: string("comment")?,
+            })
+        } else if object.contains_key("doctype") {
+            Ok(NodeData::Doctype {
+                name: string("doctype")?,
+                public_id: string("publicId")?,
+                system_id: string("systemId")?,
+            })
+        } else if object.contains_key("processingInstruction") {
+            Ok(NodeData::ProcessingInstruction {
+                target: string("processingInstruction")?,
+                contents: string("data")?,
+            })
+        } else {
+            Err(D::Error::custom(
+                "expected a slate text or element, or another kind of node",
+            ))
+        }
+    }
+}
+
+impl Serialize for Text {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut object = slate_map(&self.attrs);
+        object.insert("text".to_string(), Value::String(self.contents.to_string()));
+        object.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Text {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        text_from_map(&Map::deserialize(deserializer)?)
+    }
+}
+
+/// Serializes as a Slate element without `children`, which are not part of `Element`.
+impl Serialize for Element {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut object = slate_map(&self.attrs);
+        object.insert("type".to_string(), Value::String(self.name.to_string()));
+        object.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        element_from_map(&Map::deserialize(deserializer)?)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AttribRepr<T> {
+    name: T,
+    value: Value,
+}
+
+impl Serialize for Attrib {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        AttribRepr {
+            name: &self.name[..],
+            value: self.value.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Attrib {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = AttribRepr::<String>::deserialize(deserializer)?;
+        Ok(Attrib {
+            name: repr.name.into(),
+            value: repr.value,
+        })
+    }
+}
+
+fn text_from_map<E: serde::de::Error>(object: &Map<String, Value>) -> Result<Text, E> {
+    match object.get("text") {
+        Some(Value::String(text)) => Ok(Text::with_attrs(
+            text.as_str(),
+            slate_attrs(object, &["text"]),
+        )),
+        _ => Err(E::custom("slate text is missing a string `text`")),
+    }
+}
+
+/// Returns the string under `key`, empty if there is none.
+fn string_from_map<E: serde::de::Error>(
+    object: &Map<String, Value>,
+    key: &str,
+) -> Result<StrTendril, E> {
+    match object.get(key) {
+        Some(Value::String(value)) => Ok(value.as_str().into()),
+        None => Ok(StrTendril::new()),
+        Some(_) => Err(E::custom(format!("`{}` must be a string", key))),
+    }
+}
+
+fn element_from_map<E: serde::de::Error>(object: &Map<String, Value>) -> Result<Element, E> {
+    match object.get("type") {
+        Some(Value::String(name)) => Ok(Element::with_attrs(
+            name.as_str(),
+            slate_attrs(object, &["type", "children"]),
+        )),
+        _ => Err(E::custom("slate element is missing a string `type`")),
+    }
+}
@@ -29,24 +29,54 @@
 // #![deny(missing_docs)] // TODO: add this back in.
 extern crate html5ever;
 
+mod builder;
 mod css;
+mod diff;
 mod document;
 mod dom_tree;
 mod element;
 mod entities;
+mod history;
+mod index;
+mod json;
 mod manipulation;
+mod markdown;
+mod marks;
 mod matcher;
+mod normalize;
+mod operation;
+mod path;
 mod property;
 mod query;
+mod range;
+mod recorder;
+mod render;
+mod schema;
 mod selection;
+mod span;
+mod text;
+mod transform;
 mod traversal;
+mod web;
 
+pub use diff::Change;
 pub use dom_tree::{Node, NodeRef};
-pub use document::{Document, DocumentTreeSink, Attrib, Element, Text, NodeData};
+pub use document::{Document, DocumentTreeSink, Attrib, Element, Text, NodeData, ParseOptions};
 #[doc(hidden)]
 pub use document::SerializableNodeRef;
 #[doc(hidden)]
 pub use entities::NodeId;
+pub use index::{TextIndex, TextLeaf};
+pub use markdown::MarkdownSerializer;
 pub use matcher::Matcher;
+pub use normalize::{NormalizeError, Normalizer};
+pub use operation::{Affinity, Operation, OperationError, RangeProperties};
+pub use path::Path;
+pub use range::{Point, Range};
+pub use render::HtmlRenderer;
+pub use schema::{ElementSchema, JsonType, Schema, Violation, ViolationKind};
 pub use selection::Selection;
+pub use span::{SourcePosition, SourceSpan};
+pub use text::TextOptions;
+pub use transform::Side;
 pub use traversal::Selections;
@@ -1,38 +1,9 @@
-use html5ever::QualName;
-use html5ever::{
-    tree_builder::{NoQuirks, TreeBuilderOpts},
-    ParseOpts,
-};
-use markup5ever::{local_name, namespace_url, ns};
 use tendril::StrTendril;
-use tendril::TendrilSink;
 
 use crate::dom_tree::Tree;
-use crate::{document::DocumentTreeSink, Selection};
+use crate::{document::parse_fragment, Selection};
 use crate::{Attrib, Node, NodeData, NodeId};
 
-macro_rules! parse_html {
-    ($html: expr) => {
-        html5ever::parse_fragment(
-            DocumentTreeSink::default(),
-            ParseOpts {
-                tokenizer: Default::default(),
-                tree_builder: TreeBuilderOpts {
-                    exact_errors: false,
-                    scripting_enabled: true,
-                    iframe_srcdoc: false,
-                    drop_doctype: true,
-                    ignore_missing_rules: false,
-                    quirks_mode: NoQuirks,
-                },
-            },
-            QualName::new(None, ns!(html), local_name!("")),
-            Vec::new(),
-        )
-        .one($html)
-    };
-}
-
 impl<'a> Selection<'a> {
     /// Removes the set of matched elements from the document.
     pub fn remove(&mut self) {
@@ -62,7 +33,10 @@ impl<'a> Selection<'a> {
     where
         T: Into<StrTendril>,
     {
-        let dom = parse_html!(html);
+        let dom = match self.nodes.first() {
+            Some(node) => parse_fragment(html.into(), &node.tree.options),
+            None => return,
+        };
 
         for (i, node) in self.nodes().iter().enumerate() {
             if i + 1 == self.size() {
@@ -95,7 +69,11 @@ impl<'a> Selection<'a> {
     where
         T: Into<StrTendril>,
     {
-        let dom = parse_html!(html);
+        // The HTML is parsed like the document it goes in.
+        let dom = match self.nodes.first() {
+            Some(node) => parse_fragment(html.into(), &node.tree.options),
+            None => return,
+        };
 
         for (i, node) in self.nodes().iter().enumerate() {
             if i + 1 == self.size() {
@@ -151,7 +129,7 @@ impl<'a> Selection<'a> {
 
     pub fn append_first_child(&mut self, content: impl Into<NodeData>) {
         let content = content.into();
-        for (i, node) in self.nodes().into_iter().enumerate() {
+        for (i, node) in self.nodes().iter().enumerate() {
             if i + 1 == self.size() {
                 node.append_first_child(content);
                 break;
@@ -163,7 +141,7 @@ impl<'a> Selection<'a> {
 
     pub fn append_last_child(&mut self, content: impl Into<NodeData>) {
         let content = content.into();
-        for (i, node) in self.nodes().into_iter().enumerate() {
+        for (i, node) in self.nodes().iter().enumerate() {
             if i + 1 == self.size() {
                 node.append_last_child(content);
                 break;
@@ -175,7 +153,7 @@ impl<'a> Selection<'a> {
 
     pub fn insert_before(&mut self, content: impl Into<NodeData>) {
         let content = content.into();
-        for (i, node) in self.nodes().into_iter().enumerate() {
+        for (i, node) in self.nodes().iter().enumerate() {
             if i + 1 == self.size() {
                 node.insert_before(content);
                 break;
@@ -186,7 +164,7 @@ impl<'a> Selection<'a> {
     }
     pub fn insert_after(&mut self, content: impl Into<NodeData>) {
         let content = content.into();
-        for (i, node) in self.nodes().into_iter().enumerate() {
+        for (i, node) in self.nodes().iter().enumerate() {
             if i + 1 == self.size() {
                 node.insert_after(content);
                 break;
@@ -200,81 +178,114 @@ impl<'a> Selection<'a> {
 impl<'a> Node<'a> {
     #[inline]
     pub fn append_text_contents(&self, text: impl Into<StrTendril>) {
-        self.tree.append_text_contents(&self.id, text)
+        self.tree
+            .record_text(&self.id, || self.tree.append_text_contents(&self.id, text))
     }
     #[inline]
     pub fn set_text_contents(&self, text: impl Into<StrTendril>) {
-        self.tree.set_text_contents(&self.id, text)
+        self.tree
+            .record_text(&self.id, || self.tree.set_text_contents(&self.id, text))
     }
     #[inline]
     pub fn set_text_attrs(&self, attrs: Vec<Attrib>) {
-        self.tree.set_text_attrs(&self.id, attrs)
+        self.tree
+            .record_set_node(&self.id, || self.tree.set_text_attrs(&self.id, attrs))
+    }
+
+    /// Splits the text leaf at `offset`, in UTF-16 code units like `Point` offsets,
+    /// and returns the leaf and the new leaf holding the text after the offset, with
+    /// the same marks. Returns `None` if the node is not a text or the offset is past
+    /// its end.
+    pub fn split_text_at(&self, offset: usize) -> Option<(Node<'a>, Node<'a>)> {
+        if !self.is_text() {
+            return None;
+        }
+        let properties = self.tree.slate_properties(&self.id);
+        let new_id = self.tree.record_split(&self.id, offset, || {
+            self.tree.split_node(&self.id, offset, &properties)
+        })?;
+        Some((self.clone(), Node::new(new_id, self.tree)))
+    }
+
+    /// Appends the text of the leaf to the previous sibling leaf and removes it. The
+    /// marks of the previous leaf are kept. Returns the previous leaf, or `None` if
+    /// either node is not a text.
+    pub fn merge_with_previous(&self) -> Option<Node<'a>> {
+        let prev = self.prev_content_sibling()?;
+        if !self.is_text() || !prev.is_text() {
+            return None;
+        }
+        self.tree.record_merge(&self.id, || {
+            self.tree.merge_into_previous(&self.id, &prev.id)
+        })?;
+        Some(prev)
     }
 
     pub fn append_first_child(&self, content: NodeData) {
         let new_node = self.tree.create_node(content);
-        if let Some(first_child) = self.tree.first_child_of(&self.id) {
-            self.tree.append_prev_sibling_of(&first_child.id, &new_node)
-        } else {
-            self.tree.append_child_of(&self.id, &new_node)
-        }
+        self.tree.record_move(&new_node, || {
+            if let Some(first_child) = self.tree.first_child_of(&self.id) {
+                self.tree.append_prev_sibling_of(&first_child.id, &new_node)
+            } else {
+                self.tree.append_child_of(&self.id, &new_node)
+            }
+        })
     }
 
     pub fn append_last_child(&self, content: NodeData) {
         let new_node = self.tree.create_node(content);
-        self.tree.append_child_of(&self.id, &new_node)
+        self.tree
+            .record_move(&new_node, || self.tree.append_child_of(&self.id, &new_node))
     }
 
     pub fn insert_before(&self, content: NodeData) {
         let new_node = self.tree.create_node(content);
-        self.tree.append_prev_sibling_of(&self.id, &new_node)
+        self.tree.record_move(&new_node, || {
+            self.tree.append_prev_sibling_of(&self.id, &new_node)
+        })
     }
 
     pub fn insert_after(&self, content: NodeData) {
         let new_node = self.tree.create_node(content);
 
-        // get the next sibling of the current node
-        if let Some(next_sibling) = self.tree.next_sibling_of(&self.id) {
-            eprint!("next_sibling {:?}", next_sibling);
-            self.tree
-                .append_prev_sibling_of(&next_sibling.id, &new_node)
-        } else {
-            // if there is no next sibling, append the new node to the parent
-            if let Some(parent) = self.tree.parent_of(&self.id) {
-                eprint!("parent {:?}", parent.id);
-                self.tree.append_child_of(&parent.id, &new_node)
+        self.tree.record_move(&new_node, || {
+            // get the next sibling of the current node
+            if let Some(next_sibling) = self.tree.next_sibling_of(&self.id) {
+                self.tree
+                    .append_prev_sibling_of(&next_sibling.id, &new_node)
             } else {
-                // the parent is the root
-                eprint!("root");
-                self.tree.append_child_of(&self.tree.root_id(), &new_node)
+                // if there is no next sibling, append the new node to the parent
+                if let Some(parent) = self.tree.parent_of(&self.id) {
+                    self.tree.append_child_of(&parent.id, &new_node)
+                } else {
+                    // the parent is the root
+                    self.tree.append_child_of(&self.tree.root_id(), &new_node)
+                }
             }
-        }
+        })
     }
 }
 
 impl Tree<NodeData> {
     fn append_text_contents(&self, id: &NodeId, text: impl Into<StrTendril>) {
-        self.update_node(id, |node| match node.data {
-            NodeData::Text(ref mut text_node) => {
+        self.update_node(id, |node| {
+            if let NodeData::Text(ref mut text_node) = node.data {
                 text_node.contents.push_slice(&text.into());
             }
-            _ => {}
         });
     }
     fn set_text_contents(&self, id: &NodeId, text: impl Into<StrTendril>) {
-        self.update_node(id, |node| match node.data {
-            NodeData::Text(ref mut text_node) => {
+        self.update_node(id, |node| {
+            if let NodeData::Text(ref mut text_node) = node.data {
                 text_node.contents = text.into();
             }
-            _ => {}
         });
     }
     fn set_text_attrs(&self, id: &NodeId, attrs: Vec<Attrib>) {
-        self.update_node(id, |node| match node.data {
-            NodeData::Text(ref mut text_node) => {
+        self.update_node(id, |node| {
+            if let NodeData::Text(ref mut text_node) = node.data {
                 text_node.attrs = attrs;
             }
-            _ => {}
         });
     }
 }
@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+use serde_json::{Map, Value};
+
+use crate::builder::{is_type, SlateBuilder};
+use crate::{Document, Node};
+
+type HandlerFn = Box<dyn Fn(&Node, &str) -> String>;
+
+/// The text marks with a Markdown syntax, in the order they are opened.
+const MARKS: &[(&str, &str)] = &[("bold", "**"), ("italic", "*"), ("strikethrough", "~~")];
+
+/// MarkdownSerializer renders documents to CommonMark, with the GFM tables and
+/// strikethrough, see [`Document::to_markdown_with`].
+///
+/// It knows the Plate element types: `p`, `h1` to `h6`, `blockquote`, `ul` and
+/// `ol` lists of `li`, with `lic` or block children, `code_block` of `code_line`,
+/// `table` of `tr` of `td` or `th`, `hr`, `img` and `a` with a `url`. List items
+/// with a boolean `checked` attribute are task list items. Text leaves can be
+/// `bold`, `italic`, `code` and `strikethrough`. Other elements render their
+/// children.
+///
+/// A handler renders the elements with a name, instead of the built-in rules. It
+/// gets the element and its rendered children, the Markdown blocks separated by
+/// blank lines, or the inline Markdown if the element has text leaves.
+///
+/// ```
+/// use dom_query::{Document, MarkdownSerializer};
+///
+/// let doc = Document::from_slate_json(
+///     r#"[{"type": "h1", "children": [{"text": "Hi "}, {"text": "there", "bold": true}]},
+///         {"type": "p", "children": [
+///             {"text": "ping "},
+///             {"type": "mention", "value": "ann", "children": [{"text": ""}]}
+///         ]}]"#,
+/// )
+/// .unwrap();
+///
+/// let serializer = MarkdownSerializer::new().handler("mention", |node, _| {
+///     format!("@{}", node.attr("value").unwrap().as_str().unwrap())
+/// });
+/// assert_eq!(doc.to_markdown_with(&serializer), "# Hi **there**\n\nping @ann\n");
+/// ```
+#[derive(Default)]
+pub struct MarkdownSerializer {
+    handlers: HashMap<String, HandlerFn>,
+}
+
+impl MarkdownSerializer {
+    /// Creates a serializer with the built-in rules only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the handler of the elements with the name.
+    pub fn handler<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(&Node, &str) -> String + 'static,
+    {
+        self.handlers.insert(name.to_string(), Box::new(f));
+        self
+    }
+
+    /// Renders the children of the node, a block element or the content root.
+    pub fn serialize(&self, node: &Node) -> String {
+        let mut markdown = self.children(node);
+        if !markdown.is_empty() {
+            markdown.push('\n');
+        }
+        markdown
+    }
+
+    fn children(&self, node: &Node) -> String {
+        if has_inline_content(node) {
+            self.inlines(node)
+        } else {
+            self.blocks(node, "\n\n")
+        }
+    }
+
+    fn blocks(&self, node: &Node, separator: &str) -> String {
+        node.children()
+            .iter()
+            .filter(|child| child.is_element())
+            .map(|child| self.block(child))
+            .filter(|block| !block.is_empty())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    fn block(&self, node: &Node) -> String {
+        let name = node.node_name().unwrap_or_default();
+        if let Some(handler) = self.handlers.get(&name[..]) {
+            return handler(node, &self.children(node));
+        }
+
+        match &name[..] {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                format!("{} {}", "#".repeat(level), self.inlines(node))
+            }
+            "blockquote" => prefix_lines(&self.children(node), "> ", "> "),
+            "ul" | "ol" => self.list(node, &name[..] == "ol"),
+            "code_block" => code_block(node),
+            "table" => self.table(node),
+            "hr" => "---".to_string(),
+            "img" => image(node),
+            "a" => self.link(node),
+            _ if has_inline_content(node) => escape_line_start(self.inlines(node)),
+            _ => self.blocks(node, "\n\n"),
+        }
+    }
+
+    fn list(&self, node: &Node, ordered: bool) -> String {
+        let start = node.attr("start").and_then(|v| v.as_u64()).unwrap_or(1);
+        node.children()
+            .iter()
+            .filter(|item| item.is_element())
+            .enumerate()
+            .map(|(i, item)| {
+                let marker = match ordered {
+                    true => format!("{}. ", start + i as u64),
+                    false => "- ".to_string(),
+                };
+                let content = match has_inline_content(item) {
+                    true => escape_line_start(self.inlines(item)),
+                    false => self.item_blocks(item),
+                };
+                let indent = " ".repeat(marker.len());
+                let marker = match item.attr("checked") {
+                    Some(Value::Bool(true)) => format!("{}[x] ", marker),
+                    Some(Value::Bool(false)) => format!("{}[ ] ", marker),
+                    _ => marker,
+                };
+                prefix_lines(&content, &marker, &indent)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the blocks of a list item. The nested lists follow the item content
+    /// on the next line, so the list stays tight.
+    fn item_blocks(&self, item: &Node) -> String {
+        let mut markdown = String::new();
+        for child in item.children().iter().filter(|child| child.is_element()) {
+            let block = self.block(child);
+            if block.is_empty() {
+                continue;
+            }
+            if !markdown.is_empty() {
+                let is_list = matches!(child.node_name().as_deref(), Some("ul") | Some("ol"));
+                markdown.push_str(if is_list { "\n" } else { "\n\n" });
+            }
+            markdown.push_str(&block);
+        }
+        markdown
+    }
+
+    fn table(&self, node: &Node) -> String {
+        let rows: Vec<Vec<String>> = table_rows(node)
+            .iter()
+            .map(|row| {
+                row.children()
+                    .iter()
+                    .filter(|cell| cell.is_element())
+                    .map(|cell| {
+                        let content = match has_inline_content(cell) {
+                            true => self.inlines(cell),
+                            false => self.blocks(cell, " "),
+                        };
+                        content.replace('|', "\\|").replace('\n', " ")
+                    })
+                    .collect()
+            })
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+
+        let line = |cells: &[String]| {
+            let mut line = String::from("|");
+            for i in 0..columns {
+                line.push(' ');
+                line.push_str(cells.get(i).map_or("", |cell| cell.as_str()));
+                line.push_str(" |");
+            }
+            line
+        };
+        let mut lines = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+        lines.extend(rows[1..].iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+
+    fn inlines(&self, node: &Node) -> String {
+        let mut writer = InlineWriter::default();
+        for child in node.children() {
+            if child.is_text() {
+                writer.leaf(&child);
+                continue;
+            }
+            writer.close_all();
+            let name = child.node_name().unwrap_or_default();
+            let markdown = match self.handlers.get(&name[..]) {
+                Some(handler) => handler(&child, &self.children(&child)),
+                None => match &name[..] {
+                    "a" => self.link(&child),
+                    "img" => image(&child),
+                    _ => self.children(&child),
+                },
+            };
+            writer.out.push_str(&markdown);
+        }
+        writer.close_all();
+        writer.out
+    }
+
+    fn link(&self, node: &Node) -> String {
+        format!(
+            "[{}]({})",
+            self.inlines(node),
+            link_destination(&string_attr(node, "url"))
+        )
+    }
+}
+
+impl Document {
+    /// Renders the document content to Markdown with the built-in rules, see
+    /// [`MarkdownSerializer`].
+    pub fn to_markdown(&self) -> String {
+        self.to_markdown_with(&MarkdownSerializer::default())
+    }
+
+    /// Renders the document content to Markdown with the serializer.
+    pub fn to_markdown_with(&self, serializer: &MarkdownSerializer) -> String {
+        let root = self.tree.get_unchecked(&self.tree.content_root_id());
+        serializer.serialize(&root)
+    }
+}
+
+/// Writes text leaves, opening and closing the mark delimiters between them so
+/// adjacent leaves share the delimiters of their common marks.
+#[derive(Default)]
+struct InlineWriter {
+    out: String,
+    open: Vec<&'static str>,
+}
+
+impl InlineWriter {
+    fn leaf(&mut self, leaf: &Node) {
+        let text = leaf.text();
+        if text.is_empty() {
+            return;
+        }
+        if text.trim().is_empty() {
+            self.out.push_str(&text);
+            return;
+        }
+
+        let is_marked = |key: &str| leaf.attr(key).map_or(false, |v| v != Value::Bool(false));
+        let marks: Vec<&'static str> = MARKS
+            .iter()
+            .filter(|(key, _)| is_marked(key))
+            .map(|(_, delimiter)| *delimiter)
+            .collect();
+
+        // Close from the innermost mark up to the first one the leaf does not have.
+        if let Some(i) = self.open.iter().position(|mark| !marks.contains(mark)) {
+            self.close(i);
+        }
+
+        let rest = text.trim_start();
+        self.out.push_str(&text[..text.len() - rest.len()]);
+        for mark in marks {
+            if !self.open.contains(&mark) {
+                self.out.push_str(mark);
+                self.open.push(mark);
+            }
+        }
+        match is_marked("code") {
+            true => self.out.push_str(&code_span(rest)),
+            false => self.out.push_str(&escape(rest)),
+        }
+    }
+
+    fn close_all(&mut self) {
+        self.close(0);
+    }
+
+    /// Closes the open marks from the innermost to the one at `index`. The trailing
+    /// whitespace is moved after the delimiters.
+    fn close(&mut self, index: usize) {
+        if index >= self.open.len() {
+            return;
+        }
+        let trimmed = self.out.trim_end().len();
+        let whitespace = self.out.split_off(trimmed);
+        while self.open.len() > index {
+            let mark = self.open.pop().expect("checked above");
+            self.out.push_str(mark);
+        }
+        self.out.push_str(&whitespace);
+    }
+}
+
+/// Checks if the node has text leaves, or only links, which are rendered inline.
+fn has_inline_content(node: &Node) -> bool {
+    let children = node.children();
+    let is_link = |child: &Node| child.node_name().as_deref() == Some("a");
+    children.iter().any(|child| child.is_text())
+        || (!children.is_empty() && children.iter().all(is_link))
+}
+
+/// Returns the rows of a table, looking into `thead` and `tbody` too.
+fn table_rows<'a>(table: &Node<'a>) -> Vec<Node<'a>> {
+    let mut rows = vec![];
+    for child in table.children() {
+        match child.node_name().as_deref() {
+            Some("thead") | Some("tbody") | Some("tfoot") => rows.extend(child.children()),
+            Some(_) => rows.push(child),
+            None => {}
+        }
+    }
+    rows
+}
+
+fn code_block(node: &Node) -> String {
+    let code = match has_inline_content(node) {
+        true => node.text().to_string(),
+        false => node
+            .children()
+            .iter()
+            .map(|line| line.text().to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    let fence = "`".repeat(longest_run(&code, '`').max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        string_attr(node, "lang"),
+        code,
+        fence
+    )
+}
+
+fn image(node: &Node) -> String {
+    format!(
+        "![{}]({})",
+        escape(&string_attr(node, "alt")),
+        link_destination(&string_attr(node, "url"))
+    )
+}
+
+/// Writes a link or image URL, between `<` and `>` if it has characters that
+/// would end or break a bare destination, like spaces or parentheses.
+fn link_destination(url: &str) -> String {
+    let is_bare = |c: char| !matches!(c, ' ' | '(' | ')' | '<' | '>' | '\\') && !c.is_control();
+    if url.chars().all(is_bare) {
+        return url.to_string();
+    }
+    let mut destination = String::from("<");
+    for c in url.chars() {
+        match c {
+            '<' | '>' | '\\' => {
+                destination.push('\\');
+                destination.push(c);
+            }
+            '\n' => destination.push_str("%0A"),
+            '\r' => destination.push_str("%0D"),
+            c => destination.push(c),
+        }
+    }
+    destination.push('>');
+    destination
+}
+
+fn string_attr(node: &Node, name: &str) -> String {
+    match node.attr(name) {
+        Some(Value::String(s)) => s,
+        _ => String::new(),
+    }
+}
+
+fn code_span(text: &str) -> String {
+    let ticks = "`".repeat(longest_run(text, '`') + 1);
+    let padding = match text.starts_with('`') || text.ends_with('`') {
+        true => " ",
+        false => "",
+    };
+    format!("{}{}{}{}{}", ticks, padding, text, padding, ticks)
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|other| other != c)
+        .map(str::len)
+        .max()
+        .unwrap_or(0)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        // A backslash before a newline is a hard line break.
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '~' | '<' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes the lines of a paragraph that would start with a block marker, like
+/// `#` or `1.`. The lines after hard breaks start blocks too.
+fn escape_line_start(text: String) -> String {
+    text.split("\\\n")
+        .map(|line| {
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let (indent, rest) = line.split_at(indent);
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            match rest[digits..].chars().next() {
+                Some('#') | Some('>') | Some('-') | Some('+') if digits == 0 => {
+                    format!("{}\\{}", indent, rest)
+                }
+                Some('.') | Some(')') if digits > 0 && digits < 10 => {
+                    format!("{}{}\\{}", indent, &rest[..digits], &rest[digits..])
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\\\n")
+}
+
+/// Prefixes the first line with `first` and the other non-empty lines with `rest`.
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| match (i, line.is_empty()) {
+            (0, _) => format!("{}{}", first, line),
+            (_, true) => rest.trim_end().to_string(),
+            (_, false) => format!("{}{}", rest, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Document {
+    /// Parses Markdown, with the GFM tables, task lists and strikethrough, into a
+    /// document with the Plate element types that [`Document::to_markdown`] renders.
+    /// The items of task lists have a boolean `checked` attribute.
+    ///
+    /// ```
+    /// use dom_query::Document;
+    ///
+    /// let doc = Document::from_markdown("# Title\n\nSome **bold** text.");
+    /// assert_eq!(
+    ///     doc.to_slate_json(),
+    ///     r#"[{"children":[{"text":"Title"}],"type":"h1"},{"children":[{"text":"Some "},{"bold":true,"text":"bold"},{"text":" text."}],"type":"p"}]"#
+    /// );
+    /// ```
+    pub fn from_markdown(markdown: &str) -> Document {
+        let options =
+            Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH;
+        let mut parser = MarkdownParser::default();
+        for event in Parser::new_ext(markdown, options) {
+            parser.event(event);
+        }
+        parser.builder.into_document()
+    }
+}
+
+/// Builds the Slate value of a document from Markdown events.
+#[derive(Default)]
+struct MarkdownParser {
+    builder: SlateBuilder,
+    /// The text of the code block or the alt text of the image being parsed.
+    raw: Option<String>,
+    in_table_head: bool,
+}
+
+impl MarkdownParser {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => {
+                self.builder.marks.push("code");
+                self.text(&code);
+                self.builder.marks.pop();
+            }
+            Event::Html(html) => self.text(html.trim_end_matches('\n')),
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.text("\n"),
+            Event::Rule => {
+                self.builder.close_implicit();
+                self.builder.push_element("hr", Map::new(), false);
+                self.builder.close();
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(item) = self
+                    .builder
+                    .stack
+                    .iter_mut()
+                    .rev()
+                    .find(|open| is_type(&open.element, "li"))
+                {
+                    item.element
+                        .insert("checked".to_string(), Value::Bool(checked));
+                }
+            }
+            Event::FootnoteReference(_) => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Emphasis => self.builder.marks.push("italic"),
+            Tag::Strong => self.builder.marks.push("bold"),
+            Tag::Strikethrough => self.builder.marks.push("strikethrough"),
+            Tag::Link(_, url, _) => {
+                self.builder.ensure_inline_container();
+                let mut attrs = Map::new();
+                attrs.insert("url".to_string(), Value::String(url.to_string()));
+                self.builder.push_element("a", attrs, false);
+            }
+            Tag::Image(_, url, _) => {
+                self.builder.ensure_inline_container();
+                let mut attrs = Map::new();
+                attrs.insert("url".to_string(), Value::String(url.to_string()));
+                self.builder.push_element("img", attrs, false);
+                self.raw = Some(String::new());
+            }
+            Tag::CodeBlock(kind) => {
+                self.builder.close_implicit();
+                let mut attrs = Map::new();
+                if let CodeBlockKind::Fenced(lang) = kind {
+                    let lang = lang.split_whitespace().next().unwrap_or_default();
+                    if !lang.is_empty() {
+                        attrs.insert("lang".to_string(), Value::String(lang.to_string()));
+                    }
+                }
+                self.builder.push_element("code_block", attrs, false);
+                self.raw = Some(String::new());
+            }
+            tag => {
+                self.builder.close_implicit();
+                let implicit = matches!(tag, Tag::TableCell);
+                let (name, attrs) = match tag {
+                    Tag::Paragraph if self.builder.top_is("li") => ("lic".to_string(), Map::new()),
+                    Tag::Paragraph => ("p".to_string(), Map::new()),
+                    Tag::Heading(level, _, _) => (format!("h{}", level as usize), Map::new()),
+                    Tag::BlockQuote => ("blockquote".to_string(), Map::new()),
+                    Tag::List(Some(start)) => {
+                        let mut attrs = Map::new();
+                        if start != 1 {
+                            attrs.insert("start".to_string(), start.into());
+                        }
+                        ("ol".to_string(), attrs)
+                    }
+                    Tag::List(None) => ("ul".to_string(), Map::new()),
+                    Tag::Item => ("li".to_string(), Map::new()),
+                    Tag::Table(_) => ("table".to_string(), Map::new()),
+                    Tag::TableHead => {
+                        self.in_table_head = true;
+                        ("tr".to_string(), Map::new())
+                    }
+                    Tag::TableRow => ("tr".to_string(), Map::new()),
+                    Tag::TableCell => {
+                        let name = if self.in_table_head { "th" } else { "td" };
+                        self.builder.push_element(name, Map::new(), false);
+                        // The cell content goes in a paragraph, like in Plate.
+                        ("p".to_string(), Map::new())
+                    }
+                    _ => ("div".to_string(), Map::new()),
+                };
+                self.builder.push_element(&name, attrs, implicit);
+            }
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough => {
+                self.builder.marks.pop();
+            }
+            Tag::Image(..) => {
+                let alt = self.raw.take().unwrap_or_default();
+                if let Some(open) = self.builder.stack.last_mut() {
+                    open.element.insert("alt".to_string(), Value::String(alt));
+                }
+                self.builder.close();
+            }
+            Tag::CodeBlock(_) => {
+                let code = self.raw.take().unwrap_or_default();
+                let code = code.strip_suffix('\n').unwrap_or(&code);
+                for line in code.split('\n') {
+                    self.builder.push_element("code_line", Map::new(), false);
+                    self.builder.push_text(line);
+                    self.builder.close();
+                }
+                self.builder.close();
+            }
+            Tag::TableCell => {
+                self.builder.close();
+                self.builder.close();
+            }
+            Tag::TableHead => {
+                self.in_table_head = false;
+                self.builder.close();
+            }
+            Tag::Paragraph | Tag::Item => {
+                self.builder.close_implicit();
+                if let Tag::Paragraph = tag {
+                    self.builder.close_paragraph();
+                } else {
+                    self.builder.close();
+                }
+            }
+            _ => {
+                self.builder.close_implicit();
+                self.builder.close();
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        match self.raw.as_mut() {
+            Some(raw) => raw.push_str(text),
+            None => self.builder.text(text),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use serde_json::{Map, Value};
+
+use crate::range::text_len;
+use crate::{Affinity, Document, Node, NodeId, Operation, OperationError, Path, Point, Range};
+
+impl Document {
+    /// Sets the mark `key` to `value` on the text covered by the range, like Slate's
+    /// `Editor.addMark`. The text leaves are split at the range edges, and the leaves
+    /// left with equal marks are merged back with their neighbours. Nothing changes if
+    /// the range is collapsed.
+    pub fn add_mark(&self, range: &Range, key: &str, value: Value) -> Result<(), OperationError> {
+        self.set_mark(range, key, Some(value))
+    }
+
+    /// Removes the mark `key` from the text covered by the range, like Slate's
+    /// `Editor.removeMark`.
+    pub fn remove_mark(&self, range: &Range, key: &str) -> Result<(), OperationError> {
+        self.set_mark(range, key, None)
+    }
+
+    /// Removes the boolean mark `key` if all the text covered by the range has it, adds
+    /// it otherwise.
+    pub fn toggle_mark(&self, range: &Range, key: &str) -> Result<(), OperationError> {
+        let (start, end) = range.edges();
+        let active = self
+            .covered_leaves(start, end)
+            .iter()
+            .filter(|(_, leaf)| !leaf.text().is_empty())
+            .all(|(_, leaf)| leaf.attr(key) == Some(Value::Bool(true)));
+        if active {
+            self.remove_mark(range, key)
+        } else {
+            self.add_mark(range, key, Value::Bool(true))
+        }
+    }
+
+    fn set_mark(
+        &self,
+        range: &Range,
+        key: &str,
+        value: Option<Value>,
+    ) -> Result<(), OperationError> {
+        if key == "text" {
+            return Err(OperationError::InvalidOperation(
+                "`text` can not be used as a mark".to_string(),
+            ));
+        }
+        let (start, end) = range.edges();
+        for point in [start, end] {
+            if self.resolve_point(point).is_none() {
+                return Err(match self.node_at_path(&point.path) {
+                    Some(_) => OperationError::InvalidOffset {
+                        path: point.path.clone(),
+                        offset: point.offset,
+                    },
+                    None => OperationError::InvalidPath(point.path.clone()),
+                });
+            }
+        }
+        if range.is_collapsed() {
+            return Ok(());
+        }
+
+        // The end is split first, so the start point is not moved by the split.
+        let (mut start, mut end) = (start.clone(), end.clone());
+        for point in [end.clone(), start.clone()] {
+            let op = match self.split_leaf_op(&point) {
+                Some(op) => op,
+                None => continue,
+            };
+            self.apply(&op)?;
+            start = start
+                .transform(&op, Some(Affinity::Forward))
+                .unwrap_or(start);
+            end = end.transform(&op, Some(Affinity::Backward)).unwrap_or(end);
+        }
+
+        let mut parents: Vec<Path> = vec![];
+        for (path, leaf) in self.covered_leaves(&start, &end) {
+            // The parents are merged even if nothing is set, to undo the splits.
+            let parent = path.parent().unwrap_or_default();
+            if !parents.contains(&parent) {
+                parents.push(parent);
+            }
+            let current = leaf.attr(key);
+            if current == value {
+                continue;
+            }
+            let mut properties = Map::new();
+            let mut new_properties = Map::new();
+            if let Some(current) = current {
+                properties.insert(key.to_string(), current);
+            }
+            if let Some(ref value) = value {
+                new_properties.insert(key.to_string(), value.clone());
+            }
+            self.apply(&Operation::SetNode {
+                path,
+                properties,
+                new_properties,
+            })?;
+        }
+
+        // Merging inside deeper parents does not move the shallower ones.
+        parents.sort_by_key(|path| std::cmp::Reverse(path.len()));
+        for parent in parents {
+            self.merge_equal_leaves(&parent)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the `split_node` that splits the leaf at the point, `None` if the point
+    /// is at an edge of the leaf.
+    fn split_leaf_op(&self, point: &Point) -> Option<Operation> {
+        let leaf = self.node_at_path(&point.path)?;
+        if point.offset == 0 || point.offset >= text_len(&leaf.text()) {
+            return None;
+        }
+        Some(Operation::SplitNode {
+            path: point.path.clone(),
+            position: point.offset,
+            properties: self.tree.slate_properties(&leaf.id),
+        })
+    }
+
+    /// Returns the leaves with at least one character between the points. The empty
+    /// leaves in between are included too.
+    fn covered_leaves(&self, start: &Point, end: &Point) -> Vec<(Path, Node<'_>)> {
+        self.leaves()
+            .into_iter()
+            .filter(|(path, leaf)| {
+                if path.is_before(&start.path) || path.is_after(&end.path) {
+                    return false;
+                }
+                let len = text_len(&leaf.text());
+                match (*path == start.path, *path == end.path) {
+                    (true, true) => start.offset < end.offset,
+                    (true, false) => start.offset < len,
+                    (false, true) => end.offset > 0,
+                    (false, false) => true,
+                }
+            })
+            .collect()
+    }
+
+    /// Merges the adjacent text leaves of the element at `parent` that have the same
+    /// marks.
+    fn merge_equal_leaves(&self, parent: &Path) -> Result<(), OperationError> {
+        let parent = match self.node_at_path(parent) {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+        let parent_path = parent.path().unwrap_or_default();
+        let mut i = parent.content_children().len();
+        while i > 1 {
+            i -= 1;
+            let children = parent.content_children();
+            let (prev, child) = (&children[i - 1], &children[i]);
+            if !prev.is_text() || !child.is_text() || !self.same_marks(&prev.id, &child.id) {
+                continue;
+            }
+            self.apply(&Operation::MergeNode {
+                path: parent_path.child(i),
+                position: text_len(&prev.text()),
+                properties: self.tree.slate_properties(&child.id),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn same_marks(&self, a: &NodeId, b: &NodeId) -> bool {
+        self.tree.slate_properties(a) == self.tree.slate_properties(b)
+    }
+}
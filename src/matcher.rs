@@ -18,7 +18,7 @@ pub struct Matcher {
 
 impl Matcher {
     /// creates a new CSS matcher.
-    pub fn new(sel: &str) -> Result<Self, ParseError<SelectorParseErrorKind>> {
+    pub fn new(sel: &str) -> Result<Self, ParseError<'_, SelectorParseErrorKind<'_>>> {
         let mut input = cssparser::ParserInput::new(sel);
         let mut parser = cssparser::Parser::new(&mut input);
         selectors::parser::SelectorList::parse(
@@ -118,7 +118,7 @@ impl<'a, 'b> Iterator for Matches<'a, NodeRef<'b, NodeData>> {
                 self.nodes.insert(0, node);
             }
 
-            if self.set.contains(&node.id) {
+            if self.set.contains(&node.id) || !(node.is_element() || node.is_text()) {
                 continue;
             }
 
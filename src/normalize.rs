@@ -0,0 +1,310 @@
+use std::{error, fmt};
+
+use serde_json::json;
+
+use crate::{Document, Node, NodeData, NodeId, Operation, OperationError, Path};
+
+type InlineFn = Box<dyn Fn(&Node) -> bool>;
+type RuleFn = Box<dyn Fn(&Document, &Node) -> bool>;
+
+/// Normalizer holds what `Document::normalize_with` needs to know about the editor:
+/// which elements are inline, and the custom normalization rules.
+///
+/// A rule is called for every node of the content, before the default rules. It
+/// returns `true` if it changed the document, and is then called again until the
+/// node is normalized. Rules should change the document with `Document::apply` or
+/// the `Node` and `Selection` methods, so the changes are recorded.
+///
+/// ```
+/// use dom_query::{Document, Normalizer};
+///
+/// let normalizer = Normalizer::new()
+///     .inline(|node| node.node_name().as_deref() == Some("a"))
+///     .rule(|_, node| {
+///         // Paragraphs are always aligned.
+///         if node.node_name().as_deref() == Some("p") && node.attr("align").is_none() {
+///             node.set_attr("align", "left".into());
+///             return true;
+///         }
+///         false
+///     });
+///
+/// let doc = Document::from_slate_json(r#"[{"type": "p", "children": []}]"#).unwrap();
+/// doc.normalize_with(&normalizer).unwrap();
+/// assert_eq!(
+///     doc.to_slate_json(),
+///     r#"[{"align":"left","children":[{"text":""}],"type":"p"}]"#
+/// );
+/// ```
+pub struct Normalizer {
+    inline: InlineFn,
+    rules: Vec<RuleFn>,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self {
+            inline: Box::new(|_| false),
+            rules: vec![],
+        }
+    }
+}
+
+impl Normalizer {
+    /// Creates a normalizer with the default Slate rules, where no element is inline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the function that tells if an element is inline, like Slate's
+    /// `editor.isInline`.
+    pub fn inline<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Node) -> bool + 'static,
+    {
+        self.inline = Box::new(f);
+        self
+    }
+
+    /// Adds a custom normalization rule.
+    pub fn rule<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Document, &Node) -> bool + 'static,
+    {
+        self.rules.push(Box::new(f));
+        self
+    }
+
+    /// Checks if the node is an inline element.
+    pub fn is_inline(&self, node: &Node) -> bool {
+        node.is_element() && (self.inline)(node)
+    }
+}
+
+impl Document {
+    /// Normalizes the document content with the default Slate rules, see
+    /// [`Document::normalize_with`].
+    pub fn normalize(&self) -> Result<(), NormalizeError> {
+        self.normalize_with(&Normalizer::default())
+    }
+
+    /// Normalizes the document content so the Slate editor can load it. Like Slate's
+    /// `Editor.normalizeNode`, it:
+    ///
+    /// * inserts an empty text leaf into elements without children,
+    /// * removes the children of text leaves,
+    /// * removes the inline children of blocks that have block children, and the
+    ///   block children of blocks that start with an inline or a text, and of
+    ///   inline elements. Text leaves are not allowed at the top level,
+    /// * surrounds inline elements with text leaves,
+    /// * merges adjacent text leaves with the same attributes, and removes empty
+    ///   text leaves next to other text leaves.
+    ///
+    /// The changes are made with operations, so they are recorded and can be undone.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NormalizeError::Unfinished`] if the custom rules keep changing the
+    /// document, like Slate throws when it can not completely normalize the editor,
+    /// and [`NormalizeError::Operation`] if a normalization operation can not be
+    /// applied. The changes made until then are kept.
+    pub fn normalize_with(&self, normalizer: &Normalizer) -> Result<(), NormalizeError> {
+        let root_id = self.tree.content_root_id();
+        let mut budget = Budget {
+            iterations: 0,
+            max: 42 * self.tree.nodes.borrow().len(),
+        };
+        while self.normalize_subtree(&root_id, normalizer, &mut budget)? {}
+        Ok(())
+    }
+
+    /// Normalizes the node, then its descendants. Returns `true` if anything changed.
+    fn normalize_subtree(
+        &self,
+        id: &NodeId,
+        normalizer: &Normalizer,
+        budget: &mut Budget,
+    ) -> Result<bool, NormalizeError> {
+        let mut changed = false;
+        while self.normalize_node(id, normalizer)? {
+            changed = true;
+            budget.spend()?;
+        }
+        for child in self.tree.content_children_of(id) {
+            changed |= self.normalize_subtree(&child.id, normalizer, budget)?;
+        }
+        Ok(changed)
+    }
+
+    /// Applies the first rule that changes the node. Returns `false` if the node is
+    /// normalized.
+    fn normalize_node(&self, id: &NodeId, normalizer: &Normalizer) -> Result<bool, NormalizeError> {
+        let node = self.tree.get_unchecked(id);
+        if normalizer.rules.iter().any(|rule| rule(self, &node)) {
+            return Ok(true);
+        }
+
+        // A custom rule may have removed the node from the content.
+        let path = match node.path() {
+            Some(path) => path,
+            None => return Ok(false),
+        };
+        let children = node.content_children();
+
+        if node.is_text() {
+            for child in &children {
+                self.apply(&Operation::RemoveNode {
+                    path: path.child(0),
+                    node: child.to_slate_value(),
+                })?;
+            }
+            return Ok(!children.is_empty());
+        }
+
+        let is_root = *id == self.tree.content_root_id();
+        if !is_root && children.is_empty() {
+            self.insert_empty_text(&path, 0)?;
+            return Ok(true);
+        }
+
+        let should_have_inlines = !is_root
+            && (normalizer.is_inline(&node)
+                || children
+                    .first()
+                    .map_or(true, |first| first.is_text() || normalizer.is_inline(first)));
+
+        let len = children.len();
+        let mut changed = false;
+        let mut n = 0;
+        for i in 0..len {
+            let children = node.content_children();
+            let child = &children[n];
+            let prev = n.checked_sub(1).map(|prev| &children[prev]);
+            let is_inline_or_text = child.is_text() || normalizer.is_inline(child);
+
+            if is_inline_or_text != should_have_inlines {
+                self.remove_child(&path, n, child)?;
+                changed = true;
+                continue;
+            }
+
+            if child.is_element() {
+                // Inline elements are surrounded by text leaves.
+                if normalizer.is_inline(child) {
+                    if !prev.map_or(false, |prev| prev.is_text()) {
+                        self.insert_empty_text(&path, n)?;
+                        changed = true;
+                        n += 1;
+                    } else if i == len - 1 {
+                        self.insert_empty_text(&path, n + 1)?;
+                        changed = true;
+                        n += 1;
+                    }
+                }
+                n += 1;
+                continue;
+            }
+
+            // Adjacent text leaves are merged when their marks match, empty ones removed.
+            match prev.filter(|prev| prev.is_text()) {
+                Some(prev)
+                    if self.tree.slate_properties(&prev.id)
+                        == self.tree.slate_properties(&child.id) =>
+                {
+                    self.apply(&Operation::MergeNode {
+                        path: path.child(n),
+                        position: text_len(prev),
+                        properties: self.tree.slate_properties(&child.id),
+                    })?;
+                }
+                Some(prev) if text_len(prev) == 0 => self.remove_child(&path, n - 1, prev)?,
+                Some(_) if text_len(child) == 0 => self.remove_child(&path, n, child)?,
+                _ => {
+                    n += 1;
+                    continue;
+                }
+            }
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    fn remove_child(&self, path: &Path, index: usize, child: &Node) -> Result<(), OperationError> {
+        self.apply(&Operation::RemoveNode {
+            path: path.child(index),
+            node: child.to_slate_value(),
+        })
+    }
+
+    fn insert_empty_text(&self, path: &Path, index: usize) -> Result<(), OperationError> {
+        self.apply(&Operation::InsertNode {
+            path: path.child(index),
+            node: json!({"text": ""}),
+        })
+    }
+}
+
+/// Error returned when a document can not be normalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NormalizeError {
+    /// The document still changed after the given number of iterations, usually
+    /// because custom rules undo each other or never report the node as normalized.
+    Unfinished { iterations: usize },
+    /// A normalization operation could not be applied, e.g. because a custom rule
+    /// left the tree in a state the default rules can not address.
+    Operation(OperationError),
+}
+
+impl fmt::Display for NormalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NormalizeError::Unfinished { iterations } => write!(
+                f,
+                "could not completely normalize the document after {} iterations",
+                iterations
+            ),
+            NormalizeError::Operation(e) => write!(f, "normalization failed: {}", e),
+        }
+    }
+}
+
+impl error::Error for NormalizeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            NormalizeError::Operation(e) => Some(e),
+            NormalizeError::Unfinished { .. } => None,
+        }
+    }
+}
+
+impl From<OperationError> for NormalizeError {
+    fn from(e: OperationError) -> Self {
+        NormalizeError::Operation(e)
+    }
+}
+
+fn text_len(node: &Node) -> usize {
+    node.query(|node| match node.data {
+        NodeData::Text(ref t) => crate::range::text_len(&t.contents),
+        _ => 0,
+    })
+    .unwrap_or(0)
+}
+
+/// Budget bounds the number of changes a normalization makes.
+struct Budget {
+    iterations: usize,
+    max: usize,
+}
+
+impl Budget {
+    fn spend(&mut self) -> Result<(), NormalizeError> {
+        self.iterations += 1;
+        match self.iterations > self.max {
+            true => Err(NormalizeError::Unfinished {
+                iterations: self.max,
+            }),
+            false => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,797 @@
+use std::error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tendril::StrTendril;
+
+use crate::dom_tree::Tree;
+use crate::range::{byte_offset, text_len};
+use crate::{Document, Element, NodeData, NodeId, Path, Point, Range, Text};
+
+/// Operation is a Slate operation, the unit of change the editor emits. It
+/// deserializes from the JSON the editor produces, e.g.
+/// `{"type": "insert_text", "path": [0, 0], "offset": 2, "text": "a"}`.
+///
+/// Text offsets and positions count UTF-16 code units, like Slate's, see [`Point`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Operation {
+    /// Inserts `text` at `offset` of the text leaf at `path`.
+    InsertText {
+        path: Path,
+        offset: usize,
+        text: String,
+    },
+    /// Removes `text` from `offset` of the text leaf at `path`.
+    RemoveText {
+        path: Path,
+        offset: usize,
+        text: String,
+    },
+    /// Inserts the Slate `node` at `path`.
+    InsertNode { path: Path, node: Value },
+    /// Removes the node at `path`, `node` is the removed Slate node.
+    RemoveNode { path: Path, node: Value },
+    /// Merges the node at `path` into its previous sibling, which had `position`
+    /// UTF-16 code units or children before the merge.
+    MergeNode {
+        path: Path,
+        position: usize,
+        #[serde(default)]
+        properties: Map<String, Value>,
+    },
+    /// Splits the node at `path` at `position`. The second half is inserted after the
+    /// node, with `properties`.
+    SplitNode {
+        path: Path,
+        position: usize,
+        #[serde(default)]
+        properties: Map<String, Value>,
+    },
+    /// Moves the node at `path` to `new_path`.
+    MoveNode {
+        path: Path,
+        #[serde(rename = "newPath")]
+        new_path: Path,
+    },
+    /// Sets the properties of the node at `path`. `properties` are the previous
+    /// values, a `null` new value removes the property.
+    SetNode {
+        path: Path,
+        #[serde(default)]
+        properties: Map<String, Value>,
+        #[serde(rename = "newProperties", default)]
+        new_properties: Map<String, Value>,
+    },
+    /// Sets the selection. `None` new properties clears the selection.
+    SetSelection {
+        properties: Option<RangeProperties>,
+        #[serde(rename = "newProperties")]
+        new_properties: Option<RangeProperties>,
+    },
+}
+
+/// A partial range, used by `set_selection` operations.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RangeProperties {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<Point>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus: Option<Point>,
+}
+
+impl From<Range> for RangeProperties {
+    fn from(range: Range) -> Self {
+        Self {
+            anchor: Some(range.anchor),
+            focus: Some(range.focus),
+        }
+    }
+}
+
+impl Operation {
+    /// Returns the path the operation applies to, `None` for `set_selection`.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Operation::InsertText { path, .. }
+            | Operation::RemoveText { path, .. }
+            | Operation::InsertNode { path, .. }
+            | Operation::RemoveNode { path, .. }
+            | Operation::MergeNode { path, .. }
+            | Operation::SplitNode { path, .. }
+            | Operation::MoveNode { path, .. }
+            | Operation::SetNode { path, .. } => Some(path),
+            Operation::SetSelection { .. } => None,
+        }
+    }
+
+    /// Returns the operation that undoes this one, like Slate's `Operation.inverse`.
+    /// `remove_node` and `remove_text` must carry the removed node and text, and
+    /// `merge_node` the properties of the merged node, as the recorded operations do.
+    /// The `null` node of a removal made by [`Operation::transform`] is not enough.
+    pub fn inverse(&self) -> Operation {
+        match self.clone() {
+            Operation::InsertText { path, offset, text } => {
+                Operation::RemoveText { path, offset, text }
+            }
+            Operation::RemoveText { path, offset, text } => {
+                Operation::InsertText { path, offset, text }
+            }
+            Operation::InsertNode { path, node } => Operation::RemoveNode { path, node },
+            Operation::RemoveNode { path, node } => Operation::InsertNode { path, node },
+            Operation::MergeNode {
+                path,
+                position,
+                properties,
+            } => Operation::SplitNode {
+                // A merge of a first child is invalid and can not be applied anyway.
+                path: path.previous().unwrap_or(path),
+                position,
+                properties,
+            },
+            Operation::SplitNode {
+                path,
+                position,
+                properties,
+            } => Operation::MergeNode {
+                path: path.next().unwrap_or(path),
+                position,
+                properties,
+            },
+            Operation::MoveNode { path, new_path } => {
+                if path == new_path {
+                    return self.clone();
+                }
+                if path.is_sibling(&new_path) {
+                    return Operation::MoveNode {
+                        path: new_path,
+                        new_path: path,
+                    };
+                }
+                // The node is now at the transformed path, and goes back to where its
+                // next sibling is after the move.
+                let inverse_path = path.transform(self, None).unwrap_or_else(|| path.clone());
+                let inverse_new_path = path
+                    .next()
+                    .and_then(|next| next.transform(self, None))
+                    .unwrap_or_else(|| path.clone());
+                Operation::MoveNode {
+                    path: inverse_path,
+                    new_path: inverse_new_path,
+                }
+            }
+            Operation::SetNode {
+                path,
+                properties,
+                new_properties,
+            } => Operation::SetNode {
+                path,
+                properties: new_properties,
+                new_properties: properties,
+            },
+            Operation::SetSelection {
+                properties,
+                new_properties,
+            } => Operation::SetSelection {
+                properties: new_properties,
+                new_properties: properties,
+            },
+        }
+    }
+}
+
+/// Error returned when an operation can not be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperationError {
+    /// There is no node at the path, or the operation can not address it.
+    InvalidPath(Path),
+    /// The offset or position is outside of the node at the path.
+    InvalidOffset { path: Path, offset: usize },
+    /// The node at the path is not of the kind the operation needs.
+    InvalidNode(Path),
+    /// The operation itself is malformed, e.g. it carries an invalid Slate node.
+    InvalidOperation(String),
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OperationError::InvalidPath(path) => write!(f, "no node at path {}", path),
+            OperationError::InvalidOffset { path, offset } => {
+                write!(
+                    f,
+                    "offset {} is out of range for the node at {}",
+                    offset, path
+                )
+            }
+            OperationError::InvalidNode(path) => {
+                write!(f, "the node at {} does not support the operation", path)
+            }
+            OperationError::InvalidOperation(msg) => write!(f, "invalid operation: {}", msg),
+        }
+    }
+}
+
+impl error::Error for OperationError {}
+
+/// Which side a position sticks to when content is inserted or split exactly at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    Forward,
+    Backward,
+}
+
+impl Document {
+    /// Applies a Slate operation to the document. The operation is validated before
+    /// the tree is changed, so a failed operation leaves the document untouched.
+    /// The selection is moved along with the content, like Slate does. The operation
+    /// is recorded if the document is recording, and added to the undo history.
+    pub fn apply(&self, op: &Operation) -> Result<(), OperationError> {
+        if let Operation::SetSelection { new_properties, .. } = op {
+            let previous = self.selection.borrow().clone();
+            let selection = apply_selection(previous.clone(), new_properties.as_ref())?;
+            *self.selection.borrow_mut() = selection;
+            self.tree.record(Operation::SetSelection {
+                properties: previous.map(Into::into),
+                new_properties: new_properties.clone(),
+            });
+            return Ok(());
+        }
+
+        let recorded = self
+            .tree
+            .is_recording()
+            .then(|| self.tree.complete_operation(op));
+        self.tree.apply_operation(op)?;
+        if let Some(recorded) = recorded {
+            self.tree.record(recorded);
+        }
+
+        let selection = self.selection.borrow().clone();
+        if let Some(selection) = selection {
+            let selection = self.transform_selection(selection, op);
+            *self.selection.borrow_mut() = selection;
+        }
+        Ok(())
+    }
+
+    /// Returns the current Slate selection.
+    pub fn selection(&self) -> Option<Range> {
+        self.selection.borrow().clone()
+    }
+
+    /// Sets the selection by applying a `set_selection` operation.
+    pub fn set_selection(&self, range: Option<Range>) -> Result<(), OperationError> {
+        self.apply(&Operation::SetSelection {
+            properties: self.selection().map(Into::into),
+            new_properties: range.map(Into::into),
+        })
+    }
+
+    fn transform_selection(&self, selection: Range, op: &Operation) -> Option<Range> {
+        let mut points = vec![];
+        for point in [&selection.anchor, &selection.focus] {
+            match point.transform(op, Some(Affinity::Forward)) {
+                Some(point) => points.push(point),
+                // The point was inside a removed node, move it to the closest leaf.
+                None => points.push(self.closest_point(&point.path)?),
+            }
+        }
+        let focus = points.pop()?;
+        let anchor = points.pop()?;
+        Some(Range::new(anchor, focus))
+    }
+
+    fn closest_point(&self, path: &Path) -> Option<Point> {
+        let leaves = self.leaves();
+        let prev = leaves.iter().rev().find(|(p, _)| p.is_before(path));
+        let next = leaves.iter().find(|(p, _)| !p.is_before(path));
+        match (prev, next) {
+            (Some((p, node)), _) => Some(Point::new(p.clone(), text_len(&node.text()))),
+            (None, Some((p, _))) => Some(Point::new(p.clone(), 0)),
+            (None, None) => None,
+        }
+    }
+}
+
+fn apply_selection(
+    selection: Option<Range>,
+    new_properties: Option<&RangeProperties>,
+) -> Result<Option<Range>, OperationError> {
+    let new_properties = match new_properties {
+        Some(props) => props,
+        None => return Ok(None),
+    };
+    let anchor = new_properties
+        .anchor
+        .clone()
+        .or_else(|| selection.as_ref().map(|s| s.anchor.clone()));
+    let focus = new_properties
+        .focus
+        .clone()
+        .or_else(|| selection.as_ref().map(|s| s.focus.clone()));
+    match (anchor, focus) {
+        (Some(anchor), Some(focus)) => Ok(Some(Range::new(anchor, focus))),
+        _ => Err(OperationError::InvalidOperation(
+            "cannot set a partial selection when there is no selection".to_string(),
+        )),
+    }
+}
+
+impl Tree<NodeData> {
+    pub(crate) fn apply_operation(&self, op: &Operation) -> Result<(), OperationError> {
+        match op {
+            Operation::InsertText { path, offset, text } => {
+                let id = self.text_id_at(path)?;
+                self.update_text(&id, |contents| {
+                    let i = byte_offset(contents, *offset)
+                        .ok_or_else(|| invalid_offset(path, *offset))?;
+                    let mut s = contents.to_string();
+                    s.insert_str(i, text);
+                    Ok(s)
+                })
+            }
+            Operation::RemoveText { path, offset, text } => {
+                let id = self.text_id_at(path)?;
+                self.update_text(&id, |contents| {
+                    let end = offset + text_len(text);
+                    let from = byte_offset(contents, *offset)
+                        .ok_or_else(|| invalid_offset(path, *offset))?;
+                    let to = byte_offset(contents, end).ok_or_else(|| invalid_offset(path, end))?;
+                    let mut s = contents.to_string();
+                    s.replace_range(from..to, "");
+                    Ok(s)
+                })
+            }
+            Operation::InsertNode { path, node } => {
+                let (parent_id, index) = self.insert_position(path)?;
+                let id = self
+                    .create_slate_node(node)
+                    .map_err(|e| OperationError::InvalidOperation(e.to_string()))?;
+                self.insert_child_at(&parent_id, index, &id);
+                Ok(())
+            }
+            Operation::RemoveNode { path, .. } => {
+                let id = self.node_id_at(path)?;
+                self.remove_from_parent(&id);
+                Ok(())
+            }
+            Operation::MergeNode { path, .. } => {
+                let id = self.node_id_at(path)?;
+                let prev_id = path
+                    .previous()
+                    .and_then(|prev| self.node_id_at_path(&prev))
+                    .ok_or_else(|| OperationError::InvalidPath(path.clone()))?;
+                self.merge_into_previous(&id, &prev_id)
+                    .ok_or_else(|| OperationError::InvalidNode(path.clone()))
+            }
+            Operation::SplitNode {
+                path,
+                position,
+                properties,
+            } => {
+                let id = self.node_id_at(path)?;
+                self.split_node(&id, *position, properties)
+                    .ok_or_else(|| invalid_offset(path, *position))?;
+                Ok(())
+            }
+            Operation::MoveNode { path, new_path } => {
+                // Nothing can take the place of the root.
+                if new_path.is_empty() {
+                    return Err(OperationError::InvalidPath(new_path.clone()));
+                }
+                if path == new_path {
+                    return Ok(());
+                }
+                let id = self.node_id_at(path)?;
+                if path.is_ancestor(new_path) {
+                    return Err(OperationError::InvalidPath(new_path.clone()));
+                }
+                let true_path = path
+                    .transform(op, Some(Affinity::Forward))
+                    .expect("a moved path always transforms");
+                // `true_path` addresses the tree without the node, so check the
+                // destination against the node's position before detaching it.
+                let invalid = || OperationError::InvalidPath(true_path.clone());
+                let (index, parent) = true_path.split_last().ok_or_else(invalid)?;
+                let parent_id = self
+                    .node_id_at_path(&path_before_removal(parent, path))
+                    .ok_or_else(invalid)?;
+                let mut len = self.content_children_of(&parent_id).len();
+                if self.parent_of(&id).map(|parent| parent.id) == Some(parent_id) {
+                    len -= 1;
+                }
+                if self.query_node(&parent_id, |node| node.is_text()) != Some(false)
+                    || *index > len
+                {
+                    return Err(invalid());
+                }
+                self.remove_from_parent(&id);
+                self.insert_child_at(&parent_id, *index, &id);
+                Ok(())
+            }
+            Operation::SetNode {
+                path,
+                properties,
+                new_properties,
+            } => {
+                let id = self.node_id_at(path)?;
+                for key in new_properties.keys().chain(properties.keys()) {
+                    if key == "children" || key == "text" {
+                        return Err(OperationError::InvalidOperation(format!(
+                            "cannot set the `{}` property",
+                            key
+                        )));
+                    }
+                }
+                self.update_node(&id, |node| {
+                    set_properties(&mut node.data, properties, new_properties)
+                });
+                Ok(())
+            }
+            Operation::SetSelection { .. } => Ok(()),
+        }
+    }
+
+    /// Returns the id of the node at `path`. The content root itself can not be
+    /// addressed by operations.
+    pub(crate) fn node_id_at(&self, path: &Path) -> Result<NodeId, OperationError> {
+        if path.is_empty() {
+            return Err(OperationError::InvalidPath(path.clone()));
+        }
+        self.node_id_at_path(path)
+            .ok_or_else(|| OperationError::InvalidPath(path.clone()))
+    }
+
+    fn text_id_at(&self, path: &Path) -> Result<NodeId, OperationError> {
+        let id = self.node_id_at(path)?;
+        match self.query_node(&id, |node| node.is_text()) {
+            Some(true) => Ok(id),
+            _ => Err(OperationError::InvalidNode(path.clone())),
+        }
+    }
+
+    /// Returns the parent and the child index a node inserted at `path` goes to.
+    fn insert_position(&self, path: &Path) -> Result<(NodeId, usize), OperationError> {
+        let invalid = || OperationError::InvalidPath(path.clone());
+        let (index, parent) = path.split_last().ok_or_else(invalid)?;
+        let parent_id = self.node_id_at_path(parent).ok_or_else(invalid)?;
+        if self.query_node(&parent_id, |node| node.is_text()) != Some(false)
+            || *index > self.content_children_of(&parent_id).len()
+        {
+            return Err(invalid());
+        }
+        Ok((parent_id, *index))
+    }
+
+    pub(crate) fn insert_child_at(&self, parent_id: &NodeId, index: usize, id: &NodeId) {
+        match self.content_children_of(parent_id).get(index) {
+            Some(sibling) => self.append_prev_sibling_of(&sibling.id, id),
+            None => self.append_child_of(parent_id, id),
+        }
+    }
+
+    fn update_text<F>(&self, id: &NodeId, f: F) -> Result<(), OperationError>
+    where
+        F: FnOnce(&StrTendril) -> Result<String, OperationError>,
+    {
+        self.update_node(id, |node| match node.data {
+            NodeData::Text(ref mut t) => {
+                t.contents = f(&t.contents)?.into();
+                Ok(())
+            }
+            _ => Ok(()),
+        })
+        .unwrap_or(Ok(()))
+    }
+
+    /// Splits the node at `position` and inserts the part after it as the next
+    /// sibling, with `properties`. Returns the id of the new sibling.
+    pub(crate) fn split_node(
+        &self,
+        id: &NodeId,
+        position: usize,
+        properties: &Map<String, Value>,
+    ) -> Option<NodeId> {
+        let parent = self.parent_of(id)?;
+        let new_id = self.split_off(id, position, properties)?;
+        match self.next_sibling_of(id) {
+            Some(next) => self.append_prev_sibling_of(&next.id, &new_id),
+            None => self.append_child_of(&parent.id, &new_id),
+        }
+        Some(new_id)
+    }
+
+    /// Merges the node into its previous sibling and removes it. Texts are
+    /// concatenated, the children of elements are moved. Returns `None` if the
+    /// nodes are not of the same kind.
+    pub(crate) fn merge_into_previous(&self, id: &NodeId, prev_id: &NodeId) -> Option<()> {
+        // `Some(contents)` for texts, `None` for elements.
+        let kind = |id: &NodeId| {
+            self.query_node(id, |node| match node.data {
+                NodeData::Text(ref t) => Some(Some(t.contents.clone())),
+                NodeData::Element(_) => Some(None),
+                _ => None,
+            })
+            .flatten()
+        };
+
+        match (kind(id)?, kind(prev_id)?) {
+            (Some(contents), Some(_)) => {
+                self.update_node(prev_id, |node| {
+                    if let NodeData::Text(ref mut t) = node.data {
+                        t.contents.push_tendril(&contents);
+                    }
+                });
+            }
+            (None, None) => {
+                for child in self.children_of(id) {
+                    self.remove_from_parent(&child.id);
+                    self.append_child_of(prev_id, &child.id);
+                }
+            }
+            _ => return None,
+        }
+        self.remove_from_parent(id);
+        Some(())
+    }
+
+    /// Creates a detached node holding everything of the node after `position`,
+    /// UTF-16 code units for texts and children for elements, with `properties`.
+    fn split_off(
+        &self,
+        id: &NodeId,
+        position: usize,
+        properties: &Map<String, Value>,
+    ) -> Option<NodeId> {
+        let attrs = |reserved: &[&str]| {
+            properties
+                .iter()
+                .filter(|(k, _)| !reserved.contains(&k.as_str()))
+                .map(|(k, v)| crate::Attrib {
+                    name: k.as_str().into(),
+                    value: v.clone(),
+                })
+                .collect()
+        };
+
+        let split = self.update_node(id, |node| match node.data {
+            NodeData::Text(ref mut t) => {
+                let i = byte_offset(&t.contents, position)?;
+                let after = StrTendril::from(&t.contents[i..]);
+                t.contents.pop_back((t.contents.len() - i) as u32);
+                Some(NodeData::Text(Text::with_attrs(after, attrs(&["text"]))))
+            }
+            NodeData::Element(ref e) => {
+                let name = match properties.get("type") {
+                    Some(Value::String(name)) => StrTendril::from(name.as_str()),
+                    _ => e.name.clone(),
+                };
+                Some(NodeData::Element(Element::with_attrs(
+                    name,
+                    attrs(&["type", "children"]),
+                )))
+            }
+            _ => None,
+        })??;
+
+        // The children move from the one at `position` on, with the comments and
+        // the like between them.
+        let first = match split {
+            NodeData::Element(_) => match self.content_children_of(id) {
+                children if position > children.len() => return None,
+                children => children.get(position).map(|child| child.id),
+            },
+            _ => None,
+        };
+        let new_id = self.create_node(split);
+        let moved = self
+            .children_of(id)
+            .into_iter()
+            .skip_while(|child| Some(child.id) != first);
+        for child in moved {
+            self.remove_from_parent(&child.id);
+            self.append_child_of(&new_id, &child.id);
+        }
+        Some(new_id)
+    }
+}
+
+/// Maps a path in the tree without the node at `removed` to the same node in
+/// the tree that still has it.
+fn path_before_removal(path: &[usize], removed: &Path) -> Vec<usize> {
+    let mut path = path.to_vec();
+    let level = removed.len() - 1;
+    if path.len() > level && path[..level] == removed[..level] && path[level] >= removed[level] {
+        path[level] += 1;
+    }
+    path
+}
+
+fn invalid_offset(path: &Path, offset: usize) -> OperationError {
+    OperationError::InvalidOffset {
+        path: path.clone(),
+        offset,
+    }
+}
+
+fn set_properties(
+    data: &mut NodeData,
+    properties: &Map<String, Value>,
+    new_properties: &Map<String, Value>,
+) {
+    let (mut name, attrs) = match data {
+        NodeData::Element(ref mut e) => (Some(&mut e.name), &mut e.attrs),
+        NodeData::Text(ref mut t) => (None, &mut t.attrs),
+        _ => return,
+    };
+
+    for (key, value) in new_properties {
+        match (key.as_str(), value, name.as_mut()) {
+            ("type", Value::String(new_name), Some(name)) => **name = new_name.as_str().into(),
+            (_, Value::Null, _) => attrs.retain(|a| &a.name[..] != key),
+            _ => match attrs.iter_mut().find(|a| &a.name[..] == key) {
+                Some(attr) => attr.value = value.clone(),
+                None => attrs.push(crate::Attrib {
+                    name: key.as_str().into(),
+                    value: value.clone(),
+                }),
+            },
+        }
+    }
+    // Properties that had a value but are not part of the new properties are removed.
+    for key in properties.keys() {
+        if key != "type" && !new_properties.contains_key(key) {
+            attrs.retain(|a| &a.name[..] != key);
+        }
+    }
+}
+
+impl Path {
+    /// Transforms the path to where it ends up after `op` is applied, the way Slate's
+    /// `Path.transform` does. Returns `None` if the node is removed by the operation,
+    /// or if it is split and `affinity` is `None`.
+    pub fn transform(&self, op: &Operation, affinity: Option<Affinity>) -> Option<Path> {
+        let op_paths_empty = match op {
+            Operation::MoveNode { path, new_path } => path.is_empty() || new_path.is_empty(),
+            _ => op.path().map_or(true, |path| path.is_empty()),
+        };
+        // Operations can not address the content root, so they never move it.
+        if self.is_empty() || op_paths_empty {
+            return Some(self.clone());
+        }
+        let mut p = self.to_vec();
+
+        match op {
+            Operation::InsertNode { path: op, .. } => {
+                if op == self || op.ends_before(self) || op.is_ancestor(self) {
+                    p[op.len() - 1] += 1;
+                }
+            }
+            Operation::RemoveNode { path: op, .. } => {
+                if op == self || op.is_ancestor(self) {
+                    return None;
+                } else if op.ends_before(self) {
+                    p[op.len() - 1] -= 1;
+                }
+            }
+            Operation::MergeNode {
+                path: op, position, ..
+            } => {
+                if op == self || op.ends_before(self) {
+                    p[op.len() - 1] -= 1;
+                } else if op.is_ancestor(self) {
+                    p[op.len() - 1] -= 1;
+                    p[op.len()] += position;
+                }
+            }
+            Operation::SplitNode {
+                path: op, position, ..
+            } => {
+                if op == self {
+                    match affinity {
+                        Some(Affinity::Forward) => *p.last_mut()? += 1,
+                        Some(Affinity::Backward) => {}
+                        None => return None,
+                    }
+                } else if op.ends_before(self) {
+                    p[op.len() - 1] += 1;
+                } else if op.is_ancestor(self) && self[op.len()] >= *position {
+                    p[op.len() - 1] += 1;
+                    p[op.len()] -= position;
+                }
+            }
+            Operation::MoveNode {
+                path: op,
+                new_path: onp,
+            } => {
+                if op == onp {
+                    return Some(self.clone());
+                }
+                if op.is_ancestor(self) || op == self {
+                    let mut copy = onp.to_vec();
+                    if op.ends_before(onp) && op.len() < onp.len() {
+                        copy[op.len() - 1] -= 1;
+                    }
+                    copy.extend_from_slice(&self[op.len()..]);
+                    return Some(Path::new(copy));
+                } else if op.is_sibling(onp) && (onp.is_ancestor(self) || onp == self) {
+                    if op.ends_before(self) {
+                        p[onp.len() - 1] -= 1;
+                    } else {
+                        p[onp.len() - 1] += 1;
+                    }
+                } else if onp.ends_before(self) || onp == self || onp.is_ancestor(self) {
+                    if op.ends_before(self) {
+                        p[op.len() - 1] -= 1;
+                    }
+                    p[onp.len() - 1] += 1;
+                } else if op.ends_before(self) {
+                    if onp == self {
+                        p[onp.len() - 1] += 1;
+                    }
+                    p[op.len() - 1] -= 1;
+                }
+            }
+            Operation::InsertText { .. }
+            | Operation::RemoveText { .. }
+            | Operation::SetNode { .. }
+            | Operation::SetSelection { .. } => {}
+        }
+
+        Some(Path::new(p))
+    }
+}
+
+impl Point {
+    /// Transforms the point to where it ends up after `op` is applied, the way Slate's
+    /// `Point.transform` does. Returns `None` if the point's leaf is removed, or if it
+    /// is split exactly at the point and `affinity` is `None`.
+    pub fn transform(&self, op: &Operation, affinity: Option<Affinity>) -> Option<Point> {
+        let mut point = self.clone();
+        match op {
+            Operation::InsertNode { .. } | Operation::MoveNode { .. } => {
+                point.path = self.path.transform(op, affinity)?;
+            }
+            Operation::InsertText { path, offset, text } => {
+                if *path == self.path
+                    && (*offset < self.offset
+                        || (*offset == self.offset && affinity == Some(Affinity::Forward)))
+                {
+                    point.offset += text_len(text);
+                }
+            }
+            Operation::MergeNode { path, position, .. } => {
+                if *path == self.path {
+                    point.offset += position;
+                }
+                point.path = self.path.transform(op, affinity)?;
+            }
+            Operation::RemoveText { path, offset, text } => {
+                if *path == self.path && *offset <= self.offset {
+                    point.offset -= (self.offset - offset).min(text_len(text));
+                }
+            }
+            Operation::RemoveNode { .. } => {
+                point.path = self.path.transform(op, affinity)?;
+            }
+            Operation::SplitNode { path, position, .. } => {
+                if *path == self.path {
+                    if *position == self.offset && affinity.is_none() {
+                        return None;
+                    } else if *position < self.offset
+                        || (*position == self.offset && affinity == Some(Affinity::Forward))
+                    {
+                        point.offset -= position;
+                        point.path = self.path.transform(op, Some(Affinity::Forward))?;
+                    }
+                } else {
+                    point.path = self.path.transform(op, affinity)?;
+                }
+            }
+            Operation::SetNode { .. } | Operation::SetSelection { .. } => {}
+        }
+        Some(point)
+    }
+}
@@ -0,0 +1,241 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dom_tree::Tree;
+use crate::{Document, Node, NodeData, NodeId, Selection};
+
+/// Path is the list of child indexes that leads from the document content to a node,
+/// the way Slate addresses nodes. The top level nodes have paths `[0]`, `[1]`, ... and
+/// the content root itself has the empty path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Path(Vec<usize>);
+
+impl Path {
+    /// Creates a path from child indexes.
+    pub fn new(indexes: Vec<usize>) -> Self {
+        Path(indexes)
+    }
+
+    /// Returns the path of the parent node, `None` for the empty path.
+    pub fn parent(&self) -> Option<Path> {
+        let (_, parent) = self.0.split_last()?;
+        Some(Path(parent.to_vec()))
+    }
+
+    /// Returns the path of the `index`th child of this path.
+    pub fn child(&self, index: usize) -> Path {
+        let mut indexes = self.0.clone();
+        indexes.push(index);
+        Path(indexes)
+    }
+
+    /// Returns the path of the next sibling, `None` for the empty path.
+    pub fn next(&self) -> Option<Path> {
+        let (last, parent) = self.0.split_last()?;
+        Some(Path(parent.to_vec()).child(last + 1))
+    }
+
+    /// Returns the path of the previous sibling, `None` if this is a first child.
+    pub fn previous(&self) -> Option<Path> {
+        let (last, parent) = self.0.split_last()?;
+        let index = last.checked_sub(1)?;
+        Some(Path(parent.to_vec()).child(index))
+    }
+
+    /// Returns the paths of all the ancestors, from the content root down to the parent.
+    pub fn ancestors(&self) -> Vec<Path> {
+        (0..self.0.len()).map(|len| Path(self.0[..len].to_vec())).collect()
+    }
+
+    /// Returns the longest path that is an ancestor of (or equal to) both paths.
+    pub fn common(&self, other: &Path) -> Path {
+        Path(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| *a)
+                .collect(),
+        )
+    }
+
+    /// Compares the paths in document order. Like Slate, only the common length is
+    /// compared, so a path is `Equal` to its ancestors and descendants.
+    pub fn compare(&self, other: &Path) -> Ordering {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| a.cmp(b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// Checks if this path is an ancestor of `other`.
+    pub fn is_ancestor(&self, other: &Path) -> bool {
+        self.0.len() < other.0.len() && other.0.starts_with(&self.0)
+    }
+
+    /// Checks if this path is a descendant of `other`.
+    pub fn is_descendant(&self, other: &Path) -> bool {
+        other.is_ancestor(self)
+    }
+
+    /// Checks if this path is the parent of `other`.
+    pub fn is_parent(&self, other: &Path) -> bool {
+        self.0.len() + 1 == other.0.len() && other.0.starts_with(&self.0)
+    }
+
+    /// Checks if both paths have the same parent.
+    pub fn is_sibling(&self, other: &Path) -> bool {
+        !self.0.is_empty() && self != other && self.parent() == other.parent()
+    }
+
+    /// Checks if this path comes before `other` in document order and is not its ancestor.
+    pub fn is_before(&self, other: &Path) -> bool {
+        self.compare(other) == Ordering::Less
+    }
+
+    /// Checks if this path comes after `other` in document order and is not its descendant.
+    pub fn is_after(&self, other: &Path) -> bool {
+        self.compare(other) == Ordering::Greater
+    }
+
+    /// Checks if the last index of this path is before the index of `other` at the
+    /// same level, under the same ancestors. Sibling paths and the ancestors of later
+    /// siblings end before.
+    pub fn ends_before(&self, other: &Path) -> bool {
+        match self.0.split_last() {
+            Some((last, parent)) => {
+                other.0.len() > parent.len()
+                    && other.0.starts_with(parent)
+                    && *last < other.0[parent.len()]
+            }
+            None => false,
+        }
+    }
+
+    /// Checks if the last index of this path is after the index of `other` at the
+    /// same level, under the same ancestors.
+    pub fn ends_after(&self, other: &Path) -> bool {
+        match self.0.split_last() {
+            Some((last, parent)) => {
+                other.0.len() > parent.len()
+                    && other.0.starts_with(parent)
+                    && *last > other.0[parent.len()]
+            }
+            None => false,
+        }
+    }
+}
+
+impl Deref for Path {
+    type Target = [usize];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<usize>> for Path {
+    fn from(indexes: Vec<usize>) -> Self {
+        Path(indexes)
+    }
+}
+
+impl From<&[usize]> for Path {
+    fn from(indexes: &[usize]) -> Self {
+        Path(indexes.to_vec())
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl Document {
+    /// Returns the node at the given Slate path, if there is one.
+    pub fn node_at_path(&self, path: &Path) -> Option<Node<'_>> {
+        let id = self.tree.node_id_at_path(path)?;
+        self.tree.get(&id)
+    }
+}
+
+impl Tree<NodeData> {
+    pub(crate) fn node_id_at_path(&self, path: &[usize]) -> Option<NodeId> {
+        let mut id = self.content_root_id();
+        for index in path {
+            id = self.content_children_of(&id).get(*index)?.id;
+        }
+        Some(id)
+    }
+
+    /// Returns the children that are part of the Slate content, the elements and
+    /// text leaves. The comments, doctype and processing instructions kept by
+    /// `ParseOptions` have no path and are skipped.
+    pub(crate) fn content_children_of(&self, id: &NodeId) -> Vec<Node<'_>> {
+        self.children_of(id)
+            .into_iter()
+            .filter(|child| child.is_element() || child.is_text())
+            .collect()
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Returns the Slate path of the node. Returns `None` for nodes that are outside
+    /// of the document content, like the `head` element or a removed node, and for
+    /// comments, doctypes and processing instructions.
+    pub fn path(&self) -> Option<Path> {
+        let content_id = self.tree.content_root_id();
+        let mut indexes = vec![];
+        let mut id = self.id;
+
+        while id != content_id {
+            let node = self.tree.get_unchecked(&id);
+            if !node.is_element() && !node.is_text() {
+                return None;
+            }
+            let mut index = 0;
+            let mut sibling = node.prev_content_sibling();
+            while let Some(node) = sibling {
+                index += 1;
+                sibling = node.prev_content_sibling();
+            }
+            indexes.push(index);
+            id = self.tree.parent_of(&id)?.id;
+        }
+
+        indexes.reverse();
+        Some(Path(indexes))
+    }
+
+    /// Returns the children that are part of the Slate content, see
+    /// [`Node::path`].
+    pub(crate) fn content_children(&self) -> Vec<Node<'a>> {
+        self.tree.content_children_of(&self.id)
+    }
+
+    /// Returns the previous sibling that is part of the Slate content.
+    pub(crate) fn prev_content_sibling(&self) -> Option<Node<'a>> {
+        let mut sibling = self.tree.prev_sibling_of(&self.id);
+        while let Some(node) = sibling {
+            if node.is_element() || node.is_text() {
+                return Some(node);
+            }
+            sibling = self.tree.prev_sibling_of(&node.id);
+        }
+        None
+    }
+}
+
+impl<'a> Selection<'a> {
+    /// Returns the Slate paths of the matched nodes that are part of the document content.
+    pub fn paths(&self) -> Vec<Path> {
+        self.nodes().iter().filter_map(|node| node.path()).collect()
+    }
+}
@@ -25,7 +25,7 @@ impl<'a> Selection<'a> {
 
     /// Works like `attr` but returns default value if attribute is not present.
     pub fn attr_or(&self, name: &str, default: serde_json::Value) -> serde_json::Value {
-        self.attr(name).unwrap_or_else(|| default)
+        self.attr(name).unwrap_or(default)
     }
 
     pub fn attrs(&self) -> Option<Vec<Attrib>> {
@@ -0,0 +1,180 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Document, Node, NodeData, Path};
+
+/// Point is a position inside a text leaf, like Slate's `Point`. The offset counts
+/// UTF-16 code units of the leaf contents, like JavaScript strings, so an emoji
+/// outside of the Basic Multilingual Plane counts 2.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Point {
+    pub path: Path,
+    pub offset: usize,
+}
+
+impl Point {
+    pub fn new(path: impl Into<Path>, offset: usize) -> Self {
+        Self {
+            path: path.into(),
+            offset,
+        }
+    }
+
+    /// Compares the points in document order.
+    pub fn compare(&self, other: &Point) -> Ordering {
+        self.path
+            .compare(&other.path)
+            .then(self.offset.cmp(&other.offset))
+    }
+
+    pub fn is_before(&self, other: &Point) -> bool {
+        self.compare(other) == Ordering::Less
+    }
+
+    pub fn is_after(&self, other: &Point) -> bool {
+        self.compare(other) == Ordering::Greater
+    }
+}
+
+/// Range is the span between two points, like Slate's `Range`. The anchor is where
+/// the range starts from and the focus where it ends, so the focus may come first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Range {
+    pub anchor: Point,
+    pub focus: Point,
+}
+
+impl Range {
+    pub fn new(anchor: Point, focus: Point) -> Self {
+        Self { anchor, focus }
+    }
+
+    /// Creates a collapsed range at the point.
+    pub fn collapsed(point: Point) -> Self {
+        Self {
+            anchor: point.clone(),
+            focus: point,
+        }
+    }
+
+    /// Checks if the anchor and the focus are the same point.
+    pub fn is_collapsed(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    /// Checks if the focus comes before the anchor.
+    pub fn is_backward(&self) -> bool {
+        self.anchor.is_after(&self.focus)
+    }
+
+    /// Returns the start and the end points of the range, in document order.
+    pub fn edges(&self) -> (&Point, &Point) {
+        if self.is_backward() {
+            (&self.focus, &self.anchor)
+        } else {
+            (&self.anchor, &self.focus)
+        }
+    }
+
+    pub fn start(&self) -> &Point {
+        self.edges().0
+    }
+
+    pub fn end(&self) -> &Point {
+        self.edges().1
+    }
+
+    /// Checks if the point is inside the range, edges included.
+    pub fn includes(&self, point: &Point) -> bool {
+        let (start, end) = self.edges();
+        !point.is_before(start) && !point.is_after(end)
+    }
+}
+
+impl Document {
+    /// Returns the text leaf the point is in, or `None` if the point does not address
+    /// a text leaf or the offset is past the end of its contents.
+    pub fn resolve_point(&self, point: &Point) -> Option<Node<'_>> {
+        let node = self.node_at_path(&point.path)?;
+        let len = node.query(|node| match node.data {
+            NodeData::Text(ref t) => Some(text_len(&t.contents)),
+            _ => None,
+        })??;
+        (point.offset <= len).then_some(node)
+    }
+
+    /// Returns the text leaves the range touches, in document order. Leaves that the
+    /// range only touches at their edges are included.
+    pub fn range_leaves(&self, range: &Range) -> Vec<Node<'_>> {
+        let (start, end) = range.edges();
+        self.leaves()
+            .into_iter()
+            .filter(|(path, _)| !path.is_before(&start.path) && !path.is_after(&end.path))
+            .map(|(_, node)| node)
+            .collect()
+    }
+
+    /// Returns the text covered by the range. The contents of the leaves are
+    /// concatenated without separators, like Slate's `Editor.string`.
+    /// Returns `None` if one of the points can not be resolved.
+    pub fn range_text(&self, range: &Range) -> Option<String> {
+        let (start, end) = range.edges();
+        self.resolve_point(start)?;
+        self.resolve_point(end)?;
+
+        let mut text = String::new();
+        for (path, node) in self.leaves() {
+            if path.is_before(&start.path) || path.is_after(&end.path) {
+                continue;
+            }
+            let contents = node.text();
+            let from = if path == start.path { start.offset } else { 0 };
+            let to = if path == end.path {
+                end.offset
+            } else {
+                text_len(&contents)
+            };
+            let from = byte_offset(&contents, from)?;
+            let to = byte_offset(&contents, to)?.max(from);
+            text.push_str(&contents[from..to]);
+        }
+        Some(text)
+    }
+
+    /// Returns all the text leaves of the document content with their paths, in
+    /// document order.
+    pub(crate) fn leaves(&self) -> Vec<(Path, Node<'_>)> {
+        let mut leaves = vec![];
+        let mut ops = vec![(Path::default(), self.tree.content_root_id())];
+        while let Some((path, id)) = ops.pop() {
+            let node = self.tree.get_unchecked(&id);
+            if node.is_text() {
+                leaves.push((path, node));
+                continue;
+            }
+            for (i, child) in node.content_children().into_iter().enumerate().rev() {
+                ops.push((path.child(i), child.id));
+            }
+        }
+        leaves
+    }
+}
+
+/// Returns the length of `s` in UTF-16 code units, the unit of Slate offsets.
+pub(crate) fn text_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// Converts an offset in UTF-16 code units into a byte offset of `s`. Returns `None`
+/// if the offset is past the end or splits a surrogate pair.
+pub(crate) fn byte_offset(s: &str, offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (i, c) in s.char_indices() {
+        if units >= offset {
+            return (units == offset).then_some(i);
+        }
+        units += c.len_utf16();
+    }
+    (units == offset).then_some(s.len())
+}
@@ -0,0 +1,398 @@
+use serde_json::{Map, Value};
+
+use crate::dom_tree::Tree;
+use crate::history::History;
+use crate::range::{byte_offset, text_len};
+use crate::{Document, NodeData, NodeId, Operation, Path};
+
+/// Recorder holds where the mutations of a tree go: the operation log of
+/// `Document::start_recording` and the undo history.
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    pub(crate) log: Option<Vec<Operation>>,
+    pub(crate) history: Option<History>,
+}
+
+impl Recorder {
+    fn record(&mut self, op: Operation) {
+        if let Some(history) = self.history.as_mut() {
+            history.record(op.clone());
+        }
+        if let Some(log) = self.log.as_mut() {
+            log.push(op);
+        }
+    }
+}
+
+impl Document {
+    /// Starts recording the changes made through `Node` and `Selection` methods, and
+    /// the applied operations, as Slate operations. The recorded operations can be
+    /// sent to connected editors, which end up with the same value after applying
+    /// them. Changes outside of the document content, e.g. in `head`, or made with
+    /// `Node::update`, are not recorded.
+    pub fn start_recording(&self) {
+        let mut recorder = self.tree.recorder.borrow_mut();
+        if recorder.log.is_none() {
+            recorder.log = Some(vec![]);
+        }
+    }
+
+    /// Stops recording and returns the operations recorded since the last call to
+    /// `take_operations`.
+    pub fn stop_recording(&self) -> Vec<Operation> {
+        self.tree
+            .recorder
+            .borrow_mut()
+            .log
+            .take()
+            .unwrap_or_default()
+    }
+
+    /// Returns the operations recorded so far, and keeps recording.
+    pub fn take_operations(&self) -> Vec<Operation> {
+        self.tree
+            .recorder
+            .borrow_mut()
+            .log
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.tree.recorder.borrow().log.is_some()
+    }
+}
+
+impl Tree<NodeData> {
+    /// Checks if the mutations are recorded, to the log or to the history.
+    pub(crate) fn is_recording(&self) -> bool {
+        let recorder = self.recorder.borrow();
+        recorder.log.is_some() || recorder.history.is_some()
+    }
+
+    pub(crate) fn record(&self, op: Operation) {
+        self.recorder.borrow_mut().record(op);
+    }
+
+    /// Fills in what an operation from the outside may leave out or get wrong, the
+    /// removed node and text, and the previous properties, from the current tree.
+    /// The recorded operation can then be inverted.
+    pub(crate) fn complete_operation(&self, op: &Operation) -> Operation {
+        let mut op = op.clone();
+        let id = match op.path().and_then(|path| self.node_id_at(path).ok()) {
+            Some(id) => id,
+            None => return op,
+        };
+
+        match op {
+            Operation::RemoveNode { ref mut node, .. } => {
+                *node = self.get_unchecked(&id).to_slate_value();
+            }
+            Operation::RemoveText {
+                offset,
+                ref mut text,
+                ..
+            } => {
+                let contents = self.get_unchecked(&id).text();
+                let from = byte_offset(&contents, offset);
+                let to = byte_offset(&contents, offset + text_len(text));
+                if let (Some(from), Some(to)) = (from, to) {
+                    *text = contents[from..to].to_string();
+                }
+            }
+            Operation::MergeNode {
+                ref mut properties, ..
+            } => {
+                *properties = self.slate_properties(&id);
+            }
+            Operation::SetNode {
+                ref mut properties,
+                ref new_properties,
+                ..
+            } => {
+                let current = self.slate_properties(&id);
+                let keys: Vec<_> = properties
+                    .keys()
+                    .chain(new_properties.keys())
+                    .cloned()
+                    .collect();
+                *properties = keys
+                    .into_iter()
+                    .filter_map(|key| current.get(&key).map(|value| (key, value.clone())))
+                    .collect();
+            }
+            _ => {}
+        }
+        op
+    }
+
+    /// Runs `f`, which moves the node, and records it as `insert_node`, `remove_node`
+    /// or `move_node` depending on whether the node was part of the content before
+    /// and after the move.
+    pub(crate) fn record_move<F: FnOnce()>(&self, id: &NodeId, f: F) {
+        if !self.is_recording() {
+            return f();
+        }
+
+        let before = self.slate_path(id);
+        let removed = before
+            .as_ref()
+            .map(|_| self.get_unchecked(id).to_slate_value());
+        f();
+        let after = self.slate_path(id);
+
+        match (before, after, removed) {
+            (Some(path), None, Some(node)) => self.record(Operation::RemoveNode { path, node }),
+            (None, Some(path), _) => {
+                let node = self.get_unchecked(id).to_slate_value();
+                self.record(Operation::InsertNode { path, node })
+            }
+            (Some(path), Some(new_path), _) if path != new_path => {
+                let new_path = move_target(&path, &new_path);
+                self.record(Operation::MoveNode { path, new_path })
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs `f`, which removes all the children of the node, and records a
+    /// `remove_node` for each child.
+    pub(crate) fn record_remove_children<F: FnOnce()>(&self, id: &NodeId, f: F) {
+        let path = match self.content_path(id) {
+            Some(path) if self.is_recording() => path,
+            _ => return f(),
+        };
+
+        let ops: Vec<_> = self
+            .content_children_of(id)
+            .iter()
+            .map(|child| Operation::RemoveNode {
+                path: path.child(0),
+                node: child.to_slate_value(),
+            })
+            .collect();
+        f();
+        ops.into_iter().for_each(|op| self.record(op));
+    }
+
+    /// Runs `f`, which appends children to the node, and records an `insert_node`
+    /// for each new child.
+    pub(crate) fn record_insert_children<F: FnOnce()>(&self, id: &NodeId, f: F) {
+        let path = match self.content_path(id) {
+            Some(path) if self.is_recording() => path,
+            _ => return f(),
+        };
+
+        let len = self.content_children_of(id).len();
+        f();
+        for (i, child) in self.content_children_of(id).iter().enumerate().skip(len) {
+            self.record(Operation::InsertNode {
+                path: path.child(i),
+                node: child.to_slate_value(),
+            });
+        }
+    }
+
+    /// Runs `f`, which inserts siblings before the node, and records an `insert_node`
+    /// for each new sibling.
+    pub(crate) fn record_insert_prev_siblings<F: FnOnce()>(&self, id: &NodeId, f: F) {
+        let before = match self.slate_path(id) {
+            Some(path) if self.is_recording() => path,
+            _ => return f(),
+        };
+
+        f();
+        let after = match self.slate_path(id) {
+            Some(path) => path,
+            None => return,
+        };
+        let parent_id = self
+            .parent_of(id)
+            .expect("nodes with a path have a parent")
+            .id;
+        let (start, end) = (before[before.len() - 1], after[after.len() - 1]);
+        let parent = before.parent().unwrap_or_default();
+        for (i, sibling) in self.content_children_of(&parent_id).iter().enumerate() {
+            if (start..end).contains(&i) {
+                self.record(Operation::InsertNode {
+                    path: parent.child(i),
+                    node: sibling.to_slate_value(),
+                });
+            }
+        }
+    }
+
+    /// Runs `f`, which changes the attributes or the name of the node, and records
+    /// the change as a `set_node`.
+    pub(crate) fn record_set_node<F: FnOnce()>(&self, id: &NodeId, f: F) {
+        let path = match self.slate_path(id) {
+            Some(path) if self.is_recording() => path,
+            _ => return f(),
+        };
+
+        let before = self.slate_properties(id);
+        f();
+        let after = self.slate_properties(id);
+
+        let properties: Map<_, _> = before
+            .iter()
+            .filter(|(key, value)| after.get(*key) != Some(value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let new_properties: Map<_, _> = after
+            .iter()
+            .filter(|(key, value)| before.get(*key) != Some(value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        if !properties.is_empty() || !new_properties.is_empty() {
+            self.record(Operation::SetNode {
+                path,
+                properties,
+                new_properties,
+            });
+        }
+    }
+
+    /// Runs `f`, which changes the contents of a text leaf, and records the change
+    /// as a `remove_text` of the replaced characters followed by an `insert_text`.
+    pub(crate) fn record_text<F: FnOnce()>(&self, id: &NodeId, f: F) {
+        let path = match self.slate_path(id) {
+            Some(path) if self.is_recording() => path,
+            _ => return f(),
+        };
+
+        let contents = || {
+            self.query_node(id, |node| match node.data {
+                NodeData::Text(ref t) => t.contents.chars().collect::<Vec<_>>(),
+                _ => vec![],
+            })
+            .unwrap_or_default()
+        };
+        let before = contents();
+        f();
+        let after = contents();
+
+        let prefix = before
+            .iter()
+            .zip(after.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let removed: String = before[prefix..before.len() - suffix].iter().collect();
+        let inserted: String = after[prefix..after.len() - suffix].iter().collect();
+        let prefix = before[..prefix].iter().map(|c| c.len_utf16()).sum();
+
+        if !removed.is_empty() {
+            self.record(Operation::RemoveText {
+                path: path.clone(),
+                offset: prefix,
+                text: removed,
+            });
+        }
+        if !inserted.is_empty() {
+            self.record(Operation::InsertText {
+                path,
+                offset: prefix,
+                text: inserted,
+            });
+        }
+    }
+
+    /// Runs `f`, which splits the node at `position`, and records a `split_node` if
+    /// it returns the new sibling.
+    pub(crate) fn record_split<F>(&self, id: &NodeId, position: usize, f: F) -> Option<NodeId>
+    where
+        F: FnOnce() -> Option<NodeId>,
+    {
+        let path = self.slate_path(id).filter(|_| self.is_recording());
+        let new_id = f()?;
+        if let Some(path) = path {
+            self.record(Operation::SplitNode {
+                path,
+                position,
+                properties: self.slate_properties(&new_id),
+            });
+        }
+        Some(new_id)
+    }
+
+    /// Runs `f`, which merges the node into its previous sibling, and records a
+    /// `merge_node` if it succeeds.
+    pub(crate) fn record_merge<F>(&self, id: &NodeId, f: F) -> Option<()>
+    where
+        F: FnOnce() -> Option<()>,
+    {
+        let path = match self.slate_path(id) {
+            Some(path) if self.is_recording() => path,
+            _ => return f(),
+        };
+
+        let properties = self.slate_properties(id);
+        let position = self
+            .get_unchecked(id)
+            .prev_content_sibling()
+            .map_or(0, |prev| {
+                if prev.is_text() {
+                    text_len(&prev.text())
+                } else {
+                    prev.content_children().len()
+                }
+            });
+        f()?;
+        self.record(Operation::MergeNode {
+            path,
+            position,
+            properties,
+        });
+        Some(())
+    }
+
+    /// Returns the path of the node if it is part of the content, including the
+    /// content root.
+    fn content_path(&self, id: &NodeId) -> Option<Path> {
+        self.get(id)?.path()
+    }
+
+    /// Returns the path of the node if it can be addressed by operations, which
+    /// excludes the content root.
+    fn slate_path(&self, id: &NodeId) -> Option<Path> {
+        self.content_path(id).filter(|path| !path.is_empty())
+    }
+
+    /// Returns the Slate properties of the node, the attributes and the `type` of
+    /// elements.
+    pub(crate) fn slate_properties(&self, id: &NodeId) -> Map<String, Value> {
+        let node = self.get_unchecked(id);
+        let mut properties: Map<_, _> = node
+            .attrs()
+            .into_iter()
+            .map(|attr| (attr.name.to_string(), attr.value))
+            .collect();
+        if let Some(name) = node.node_name() {
+            properties.insert("type".to_string(), Value::String(name.to_string()));
+        }
+        properties
+    }
+}
+
+/// Returns the `newPath` of a `move_node` that moves the node at `path` to `target`,
+/// the path it has after the move. Slate resolves `newPath` before the node is
+/// removed, so a target under a later sibling is one index further.
+fn move_target(path: &Path, target: &Path) -> Path {
+    let mut new_path = target.to_vec();
+    let level = path.len() - 1;
+    if path.len() < target.len()
+        && target.starts_with(&path[..level])
+        && target[level] >= path[level]
+    {
+        new_path[level] += 1;
+    }
+    Path::new(new_path)
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{Document, Node};
+
+type HandlerFn = Box<dyn Fn(&Node, &str) -> String>;
+
+/// The text marks and their tags, from the outermost to the innermost.
+const MARKS: &[(&str, &str)] = &[
+    ("bold", "strong"),
+    ("italic", "em"),
+    ("underline", "u"),
+    ("strikethrough", "s"),
+    ("superscript", "sup"),
+    ("subscript", "sub"),
+    ("code", "code"),
+];
+
+/// HtmlRenderer renders documents to standard HTML that browsers and email clients
+/// can display, see [`Document::render_html_with`]. Unlike `Node::outer_html`, text
+/// leaves become plain text with their marks as tags, like `<strong>` for `bold`.
+///
+/// It knows the Plate element types: `p`, `h1` to `h6`, `blockquote`, `ul` and
+/// `ol` lists of `li`, `code_block` of `code_line`, tables, `hr`, `img` and `a`
+/// with a `url`. `lic` renders its children only. Other elements become a `div`,
+/// or a `span` if they have text leaves, unless they are mapped to a tag.
+///
+/// Text and attribute values are escaped, and links with a `javascript:` URL lose
+/// their `href`.
+///
+/// ```
+/// use dom_query::{Document, HtmlRenderer};
+///
+/// let doc = Document::from_slate_json(
+///     r#"[{"type": "p", "children": [{"text": "a < "}, {"text": "b", "bold": true}]},
+///         {"type": "callout", "children": [{"type": "p", "children": [{"text": "note"}]}]},
+///         {"type": "embed", "url": "https://example.com", "children": [{"text": ""}]}]"#,
+/// )
+/// .unwrap();
+///
+/// let renderer = HtmlRenderer::new()
+///     .tag("callout", "aside")
+///     .handler("embed", |node, _| {
+///         let url = node.attr("url").unwrap();
+///         format!(r#"<iframe src="{}"></iframe>"#, url.as_str().unwrap())
+///     });
+/// assert_eq!(
+///     doc.render_html_with(&renderer),
+///     r#"<p>a &lt; <strong>b</strong></p><aside><p>note</p></aside><iframe src="https://example.com"></iframe>"#
+/// );
+/// ```
+#[derive(Default)]
+pub struct HtmlRenderer {
+    tags: HashMap<String, String>,
+    handlers: HashMap<String, HandlerFn>,
+}
+
+impl HtmlRenderer {
+    /// Creates a renderer with the built-in rules only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders the elements with the name as the tag, with their children.
+    pub fn tag(mut self, name: &str, tag: &str) -> Self {
+        self.tags.insert(name.to_string(), tag.to_string());
+        self
+    }
+
+    /// Sets the handler of the elements with the name, instead of the built-in rules.
+    /// It gets the element and the HTML of its children.
+    pub fn handler<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(&Node, &str) -> String + 'static,
+    {
+        self.handlers.insert(name.to_string(), Box::new(f));
+        self
+    }
+
+    /// Renders the children of the node.
+    pub fn render(&self, node: &Node) -> String {
+        let mut html = String::new();
+        for child in node.children() {
+            self.render_node(&child, &mut html);
+        }
+        html
+    }
+
+    fn render_node(&self, node: &Node, html: &mut String) {
+        if node.is_text() {
+            return render_leaf(node, html);
+        }
+        let name = match node.node_name() {
+            Some(name) => name,
+            None => return,
+        };
+        if let Some(handler) = self.handlers.get(&name[..]) {
+            let children = self.render(node);
+            return html.push_str(&handler(node, &children));
+        }
+        if let Some(tag) = self.tags.get(&name[..]) {
+            return self.element(tag, &[], node, html);
+        }
+
+        match &name[..] {
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "ul" | "table"
+            | "thead" | "tbody" | "tr" | "td" | "th" => self.element(&name, &[], node, html),
+            "ol" => {
+                let start = node.attr("start").and_then(|v| v.as_u64());
+                let attrs: Vec<_> = start
+                    .filter(|start| *start != 1)
+                    .map(|start| ("start", start.to_string()))
+                    .into_iter()
+                    .collect();
+                self.element("ol", &attrs, node, html)
+            }
+            "li" => {
+                html.push_str("<li>");
+                if let Some(Value::Bool(checked)) = node.attr("checked") {
+                    html.push_str(match checked {
+                        true => r#"<input type="checkbox" disabled checked> "#,
+                        false => r#"<input type="checkbox" disabled> "#,
+                    });
+                }
+                html.push_str(&self.render(node));
+                html.push_str("</li>");
+            }
+            "lic" => html.push_str(&self.render(node)),
+            "a" => {
+                let url = string_attr(node, "url");
+                let attrs: Vec<_> = Some(("href", url))
+                    .filter(|(_, url)| is_safe_url(url))
+                    .into_iter()
+                    .collect();
+                self.element("a", &attrs, node, html)
+            }
+            "img" => {
+                html.push_str("<img");
+                push_attrs(
+                    html,
+                    &[
+                        ("src", string_attr(node, "url")),
+                        ("alt", string_attr(node, "alt")),
+                    ],
+                );
+                html.push('>');
+            }
+            "hr" => html.push_str("<hr>"),
+            "code_block" => {
+                let lines: Vec<String> = match node.children().iter().any(|c| c.is_text()) {
+                    true => vec![node.text().to_string()],
+                    false => node
+                        .children()
+                        .iter()
+                        .map(|line| line.text().to_string())
+                        .collect(),
+                };
+                let lang = string_attr(node, "lang");
+                html.push_str("<pre><code");
+                if !lang.is_empty() {
+                    push_attrs(html, &[("class", format!("language-{}", lang))]);
+                }
+                html.push('>');
+                html.push_str(&escape(&lines.join("\n"), false));
+                html.push_str("</code></pre>");
+            }
+            _ if node.children().iter().any(|c| c.is_text()) => {
+                self.element("span", &[], node, html)
+            }
+            _ => self.element("div", &[], node, html),
+        }
+    }
+
+    fn element(&self, tag: &str, attrs: &[(&str, String)], node: &Node, html: &mut String) {
+        html.push('<');
+        html.push_str(tag);
+        push_attrs(html, attrs);
+        html.push('>');
+        html.push_str(&self.render(node));
+        html.push_str("</");
+        html.push_str(tag);
+        html.push('>');
+    }
+}
+
+impl Document {
+    /// Renders the document content to HTML with the built-in rules, see
+    /// [`HtmlRenderer`].
+    pub fn render_html(&self) -> String {
+        self.render_html_with(&HtmlRenderer::default())
+    }
+
+    /// Renders the document content to HTML with the renderer.
+    pub fn render_html_with(&self, renderer: &HtmlRenderer) -> String {
+        let root = self.tree.get_unchecked(&self.tree.content_root_id());
+        renderer.render(&root)
+    }
+}
+
+fn render_leaf(leaf: &Node, html: &mut String) {
+    let text = leaf.text();
+    if text.is_empty() {
+        return;
+    }
+    let is_marked = |key: &str| leaf.attr(key).map_or(false, |v| v != Value::Bool(false));
+    let tags: Vec<&str> = MARKS
+        .iter()
+        .filter(|(key, _)| is_marked(key))
+        .map(|(_, tag)| *tag)
+        .collect();
+
+    for tag in &tags {
+        html.push('<');
+        html.push_str(tag);
+        html.push('>');
+    }
+    html.push_str(&escape(&text, false).replace('\n', "<br>"));
+    for tag in tags.iter().rev() {
+        html.push_str("</");
+        html.push_str(tag);
+        html.push('>');
+    }
+}
+
+fn push_attrs(html: &mut String, attrs: &[(&str, String)]) {
+    for (name, value) in attrs {
+        html.push(' ');
+        html.push_str(name);
+        html.push_str("=\"");
+        html.push_str(&escape(value, true));
+        html.push('"');
+    }
+}
+
+fn string_attr(node: &Node, name: &str) -> String {
+    match node.attr(name) {
+        Some(Value::String(s)) => s,
+        _ => String::new(),
+    }
+}
+
+/// Checks that following the URL does not run a script.
+fn is_safe_url(url: &str) -> bool {
+    let scheme: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .take(11)
+        .collect();
+    !scheme.to_ascii_lowercase().starts_with("javascript:")
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{Document, Node, Normalizer, Path};
+
+/// Schema declares the element types an editor knows, by `Element::name`, and what
+/// they may contain. [`Document::validate`] checks a document against it.
+///
+/// ```
+/// use dom_query::{Document, ElementSchema, JsonType, Schema};
+///
+/// let schema = Schema::new()
+///     .element("p", ElementSchema::block())
+///     .element("a", ElementSchema::inline().attr("url", JsonType::String))
+///     .element("img", ElementSchema::block().void().attr("url", JsonType::String))
+///     .element("ul", ElementSchema::block().children(["li"]))
+///     .element("li", ElementSchema::block());
+///
+/// let doc = Document::from_slate_json(
+///     r#"[{"type": "ul", "children": [{"type": "p", "children": [{"text": ""}]}]}]"#,
+/// )
+/// .unwrap();
+/// let violations = doc.validate(&schema);
+/// assert_eq!(violations[0].to_string(), "[0, 0]: `p` is not allowed in `ul`");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    elements: HashMap<String, ElementSchema>,
+    allow_unknown: bool,
+}
+
+/// ElementSchema declares the traits of an element type.
+#[derive(Debug, Clone, Default)]
+pub struct ElementSchema {
+    inline: bool,
+    void: bool,
+    /// The allowed child element types, `None` if any element is allowed.
+    children: Option<HashSet<String>>,
+    allow_text: bool,
+    attrs: Vec<(String, JsonType)>,
+}
+
+/// The type of a JSON value, used to declare the type of required attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonType {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+/// A node that does not follow the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The path of the offending node.
+    pub path: Path,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The element type is not declared in the schema.
+    UnknownElement(String),
+    /// A required attribute is missing.
+    MissingAttribute { element: String, attr: String },
+    /// A required attribute has a value of the wrong JSON type.
+    InvalidAttribute {
+        element: String,
+        attr: String,
+        expected: JsonType,
+    },
+    /// The node is not allowed in its parent element. Text leaves are named `text`.
+    ChildNotAllowed { parent: String, child: String },
+    /// A void element does not have exactly one empty text leaf.
+    InvalidVoid(String),
+    /// An inline element or a text leaf is among blocks, or at the top level.
+    InlineAmongBlocks,
+    /// A block element is in an inline element, or among inlines and text leaves.
+    BlockAmongInlines,
+}
+
+impl Schema {
+    /// Creates an empty schema. Elements that are not declared are reported, see
+    /// [`Schema::allow_unknown`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares an element type.
+    pub fn element(mut self, name: &str, element: ElementSchema) -> Self {
+        self.elements.insert(name.to_string(), element);
+        self
+    }
+
+    /// Accepts the elements that are not declared, as blocks with any children.
+    pub fn allow_unknown(mut self) -> Self {
+        self.allow_unknown = true;
+        self
+    }
+
+    /// Returns the declaration of an element type.
+    pub fn get(&self, name: &str) -> Option<&ElementSchema> {
+        self.elements.get(name)
+    }
+
+    /// Checks if the node is an element declared inline.
+    pub fn is_inline(&self, node: &Node) -> bool {
+        self.element_schema(node).map_or(false, |e| e.inline)
+    }
+
+    /// Checks if the node is an element declared void.
+    pub fn is_void(&self, node: &Node) -> bool {
+        self.element_schema(node).map_or(false, |e| e.void)
+    }
+
+    /// Returns a normalizer that knows the inline elements of the schema.
+    pub fn normalizer(&self) -> Normalizer {
+        let inline: HashSet<String> = self
+            .elements
+            .iter()
+            .filter(|(_, element)| element.inline)
+            .map(|(name, _)| name.clone())
+            .collect();
+        Normalizer::new().inline(move |node| {
+            node.node_name()
+                .map_or(false, |name| inline.contains(&name[..]))
+        })
+    }
+
+    fn element_schema(&self, node: &Node) -> Option<&ElementSchema> {
+        self.elements.get(&node.node_name()?[..])
+    }
+}
+
+impl ElementSchema {
+    /// Declares a block element.
+    pub fn block() -> Self {
+        Self::default()
+    }
+
+    /// Declares an inline element, like a link.
+    pub fn inline() -> Self {
+        Self {
+            inline: true,
+            ..Self::default()
+        }
+    }
+
+    /// Makes the element void, like an image or a mention. Void elements have a single
+    /// empty text leaf, as Slate requires.
+    pub fn void(mut self) -> Self {
+        self.void = true;
+        self
+    }
+
+    /// Restricts the child elements to the given types. Text leaves are not allowed
+    /// unless [`ElementSchema::text`] is set.
+    pub fn children<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.children = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Allows text leaves in an element with restricted children.
+    pub fn text(mut self) -> Self {
+        self.allow_text = true;
+        self
+    }
+
+    /// Requires an attribute with a value of the given JSON type.
+    pub fn attr(mut self, name: &str, ty: JsonType) -> Self {
+        self.attrs.push((name.to_string(), ty));
+        self
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.inline
+    }
+
+    pub fn is_void(&self) -> bool {
+        self.void
+    }
+
+    fn allows(&self, child: &Node) -> bool {
+        match (&self.children, child.node_name()) {
+            (None, _) => true,
+            (Some(names), Some(name)) => names.contains(&name[..]),
+            (Some(_), None) => self.allow_text,
+        }
+    }
+}
+
+impl JsonType {
+    /// Returns the type of the value.
+    pub fn of(value: &Value) -> JsonType {
+        match value {
+            Value::Null => JsonType::Null,
+            Value::Bool(_) => JsonType::Bool,
+            Value::Number(_) => JsonType::Number,
+            Value::String(_) => JsonType::String,
+            Value::Array(_) => JsonType::Array,
+            Value::Object(_) => JsonType::Object,
+        }
+    }
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            JsonType::Null => "null",
+            JsonType::Bool => "boolean",
+            JsonType::Number => "number",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            ViolationKind::UnknownElement(name) => write!(f, "unknown element `{}`", name),
+            ViolationKind::MissingAttribute { element, attr } => {
+                write!(f, "`{}` is missing the `{}` attribute", element, attr)
+            }
+            ViolationKind::InvalidAttribute {
+                element,
+                attr,
+                expected,
+            } => write!(
+                f,
+                "the `{}` attribute of `{}` must be a {}",
+                attr, element, expected
+            ),
+            ViolationKind::ChildNotAllowed { parent, child } => {
+                write!(f, "`{}` is not allowed in `{}`", child, parent)
+            }
+            ViolationKind::InvalidVoid(name) => write!(
+                f,
+                "void element `{}` must have a single empty text child",
+                name
+            ),
+            ViolationKind::InlineAmongBlocks => write!(f, "inline node among blocks"),
+            ViolationKind::BlockAmongInlines => write!(f, "block among inline nodes"),
+        }
+    }
+}
+
+impl Document {
+    /// Checks the document content against the schema, and returns the violations in
+    /// document order.
+    pub fn validate(&self, schema: &Schema) -> Vec<Violation> {
+        let mut violations = vec![];
+        let root = self.tree.get_unchecked(&self.tree.content_root_id());
+        validate_children(schema, &root, &Path::default(), None, &mut violations);
+        violations
+    }
+}
+
+fn validate_children(
+    schema: &Schema,
+    parent: &Node,
+    path: &Path,
+    parent_schema: Option<&ElementSchema>,
+    violations: &mut Vec<Violation>,
+) {
+    let parent_name = parent.node_name().unwrap_or_default();
+    let children = parent.content_children();
+    let is_root = path.is_empty();
+    // Like the normalization, the first child decides between blocks and inlines.
+    let inline_context = !is_root
+        && (parent_schema.map_or(false, |e| e.inline)
+            || children
+                .first()
+                .map_or(true, |first| first.is_text() || schema.is_inline(first)));
+
+    for (i, child) in children.iter().enumerate() {
+        let child_path = path.child(i);
+        let mut report = |kind| {
+            violations.push(Violation {
+                path: child_path.clone(),
+                kind,
+            })
+        };
+
+        if let Some(parent_schema) = parent_schema {
+            if !parent_schema.allows(child) {
+                report(ViolationKind::ChildNotAllowed {
+                    parent: parent_name.to_string(),
+                    child: child
+                        .node_name()
+                        .map_or("text".to_string(), |name| name.to_string()),
+                });
+            }
+        }
+
+        let is_inline = child.is_text() || schema.is_inline(child);
+        if is_inline && !inline_context {
+            report(ViolationKind::InlineAmongBlocks);
+        } else if !is_inline && inline_context {
+            report(ViolationKind::BlockAmongInlines);
+        }
+
+        if child.is_text() {
+            continue;
+        }
+        let name = child.node_name().unwrap_or_default();
+        let element = schema.get(&name);
+        match element {
+            Some(element) => validate_element(element, child, &name, &mut report),
+            None if !schema.allow_unknown => {
+                report(ViolationKind::UnknownElement(name.to_string()))
+            }
+            None => {}
+        }
+        validate_children(schema, child, &child_path, element, violations);
+    }
+}
+
+fn validate_element<F>(element: &ElementSchema, node: &Node, name: &str, report: &mut F)
+where
+    F: FnMut(ViolationKind),
+{
+    for (attr, ty) in &element.attrs {
+        match node.attr(attr) {
+            None => report(ViolationKind::MissingAttribute {
+                element: name.to_string(),
+                attr: attr.clone(),
+            }),
+            Some(value) if JsonType::of(&value) != *ty => report(ViolationKind::InvalidAttribute {
+                element: name.to_string(),
+                attr: attr.clone(),
+                expected: *ty,
+            }),
+            Some(_) => {}
+        }
+    }
+
+    if element.void {
+        let children = node.content_children();
+        let is_empty_text = |child: &Node| child.is_text() && child.text().is_empty();
+        if children.len() != 1 || !is_empty_text(&children[0]) {
+            report(ViolationKind::InvalidVoid(name.to_string()));
+        }
+    }
+}
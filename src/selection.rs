@@ -9,7 +9,7 @@ pub struct Selection<'a> {
 }
 
 impl<'a> From<Node<'a>> for Selection<'a> {
-    fn from(node: Node<'a>) -> Selection {
+    fn from(node: Node<'a>) -> Selection<'a> {
         Self { nodes: vec![node] }
     }
 }
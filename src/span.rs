@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+
+use html5ever::tokenizer::{
+    self as html, BufferQueue, TokenSinkResult, Tokenizer, TokenizerOpts, TokenizerResult,
+};
+use html5ever::tree_builder::{create_element, TreeBuilder};
+use html5ever::{ParseOpts, QualName};
+use markup5ever::interface::TreeSink;
+use markup5ever::LocalName;
+use tendril::StrTendril;
+use xml5ever::tokenizer::{self as xml, XmlTokenizer};
+use xml5ever::tree_builder::XmlTreeBuilder;
+
+use crate::dom_tree::Tree;
+use crate::entities::NodeId;
+use crate::{Document, DocumentTreeSink, Node, NodeData};
+
+/// A position in the parsed source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourcePosition {
+    /// The byte offset.
+    pub offset: usize,
+    /// The line, starting at 1.
+    pub line: usize,
+    /// The column in characters, starting at 1.
+    pub column: usize,
+}
+
+/// SourceSpan is where a node came from in the parsed source, see
+/// [`ParseOptions::source_spans`](crate::ParseOptions::source_spans). An element
+/// spans from the start of its start tag to the end of its end tag, a text leaf
+/// without tags spans its text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceSpan {
+    pub start: SourcePosition,
+    /// The position right after the node.
+    pub end: SourcePosition,
+}
+
+impl<'a> Node<'a> {
+    /// Returns where the node came from in the parsed source. Returns `None` if the
+    /// spans were not recorded, and for the nodes that have no tags in the source,
+    /// like the implied `body` or nodes added after parsing.
+    pub fn source_span(&self) -> Option<SourceSpan> {
+        self.query(|node| node.source_span).flatten()
+    }
+}
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// The start tag of a created element, as consumed by the tree builder.
+pub(crate) struct StartTag {
+    start: usize,
+    end: usize,
+    /// The name of the token, lowercase in HTML.
+    token_name: LocalName,
+    has_contents: bool,
+    /// The name as written in the source.
+    pub(crate) name: String,
+    /// The attribute names as written in the source.
+    pub(crate) attrs: Vec<String>,
+}
+
+impl StartTag {
+    /// Whether the tag is the start tag of an element named `name`.
+    fn starts(&self, name: &LocalName) -> bool {
+        // HTML reads `image` as `img`.
+        *name == self.token_name || (&**name == "img" && &*self.token_name == "image")
+    }
+}
+
+/// A tag token.
+struct Tag {
+    start: usize,
+    end: usize,
+    /// The name of the token, empty for the XML `</>`.
+    name: LocalName,
+    closing: bool,
+}
+
+impl Tag {
+    fn closes(&self, name: &str) -> bool {
+        self.closing && (self.name.is_empty() || str::eq_ignore_ascii_case(&self.name, name))
+    }
+}
+
+/// Source follows the tokens the tree builder consumes to find the tags of the
+/// created nodes. The input is fed a character at a time, so a token ends where
+/// the input fed so far ends. A start tag is the tag of the last element created
+/// with its name while the tree builder processes it, the elements it reopens or
+/// implies have no tag. The builder does not tell where elements are closed, so
+/// their ends are found among the tags once the tree is built.
+pub(crate) struct Source {
+    text: String,
+    line_starts: Vec<usize>,
+    /// Whether the source is XML, without void elements.
+    xml: bool,
+    /// The offset after the input fed so far, `None` while feeding input that is
+    /// not in the source.
+    offset: Option<usize>,
+    /// The offset after the last token.
+    token_end: usize,
+    /// The tags of the source, in order.
+    tags: Vec<Tag>,
+    /// The start tag being processed, and the elements created meanwhile.
+    start_tag: Option<StartTag>,
+    created: Vec<(NodeId, LocalName)>,
+    /// The span of the characters being processed.
+    chars: Option<(usize, usize)>,
+    /// The names and start offsets of the elements with contents, to be closed.
+    open: HashMap<NodeId, (LocalName, usize)>,
+    /// The spans of the text leaves without tags.
+    leaves: HashMap<NodeId, (usize, usize)>,
+}
+
+impl Source {
+    pub(crate) fn new(text: &str, xml: bool) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            text: text.to_string(),
+            line_starts,
+            xml,
+            offset: None,
+            token_end: 0,
+            tags: vec![],
+            start_tag: None,
+            created: vec![],
+            chars: None,
+            open: HashMap::new(),
+            leaves: HashMap::new(),
+        }
+    }
+
+    /// Records a tag token ending where the input fed so far ends.
+    fn tag(&mut self, name: &LocalName, closing: bool, self_closing: bool) {
+        let end = match self.offset {
+            Some(end) => end,
+            None => return,
+        };
+        let start = self.token_end + self.text[self.token_end..end].find('<').unwrap_or(0);
+        self.token_end = end;
+        self.tags.push(Tag {
+            start,
+            end,
+            name: name.clone(),
+            closing,
+        });
+        if closing {
+            return;
+        }
+        let tag = &self.text[start + 1..end];
+        let name_end = tag
+            .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+            .unwrap_or(tag.len());
+        self.start_tag = Some(StartTag {
+            start,
+            end,
+            token_name: name.clone(),
+            has_contents: !self_closing && (self.xml || !VOID.contains(&&**name)),
+            name: tag[..name_end].to_string(),
+            attrs: attr_names(&tag[name_end..]),
+        });
+    }
+
+    /// Records a character token ending where the input fed so far ends.
+    fn chars(&mut self) {
+        if let Some(end) = self.offset {
+            self.chars = Some((self.token_end, end));
+            self.token_end = end;
+        }
+    }
+
+    /// Records any other token ending where the input fed so far ends.
+    fn token(&mut self) {
+        if let Some(end) = self.offset {
+            self.token_end = end;
+        }
+    }
+
+    /// Records an element created by the tree builder.
+    pub(crate) fn element(&mut self, id: NodeId, name: LocalName) {
+        if self.start_tag.is_some() {
+            self.created.push((id, name));
+        }
+    }
+
+    /// Records a text leaf without tags created by the tree builder.
+    pub(crate) fn text(&mut self, id: NodeId) {
+        if let Some(chars) = self.chars {
+            self.leaves.insert(id, chars);
+        }
+    }
+
+    /// Records text appended to a node by the tree builder.
+    pub(crate) fn append_text(&mut self, id: NodeId) {
+        if let (Some(leaf), Some((_, end))) = (self.leaves.get_mut(&id), self.chars) {
+            leaf.1 = end;
+        }
+    }
+
+    /// Returns the start tag of the token processed by the tree builder and the
+    /// element it created, `None` if it created none. The span of the element is
+    /// extended to its end tag by [`Source::finish`].
+    pub(crate) fn processed(&mut self) -> Option<(NodeId, StartTag)> {
+        self.chars = None;
+        let created = std::mem::take(&mut self.created);
+        let tag = self.start_tag.take()?;
+        let (id, _) = created
+            .into_iter()
+            .rev()
+            .find(|(_, name)| tag.starts(name))?;
+        if tag.has_contents {
+            self.open.insert(id, (tag.token_name.clone(), tag.start));
+        }
+        Some((id, tag))
+    }
+
+    /// Returns the span of a start tag.
+    pub(crate) fn tag_span(&self, tag: &StartTag) -> SourceSpan {
+        self.span(tag.start, tag.end)
+    }
+
+    /// Returns the spans of the text leaves and the whole spans of the elements
+    /// with contents of the built tree.
+    pub(crate) fn finish(self, tree: &Tree<NodeData>) -> Vec<(NodeId, SourceSpan)> {
+        let mut spans = vec![];
+        self.close(&tree.root(), &mut vec![], &mut spans);
+        spans
+    }
+
+    /// Closes the elements of the subtree of `node` and returns where its contents
+    /// end in the source. `ancestors` are the names of the open elements above it.
+    fn close<'s>(
+        &'s self,
+        node: &Node,
+        ancestors: &mut Vec<&'s str>,
+        spans: &mut Vec<(NodeId, SourceSpan)>,
+    ) -> usize {
+        if let Some(&(start, end)) = self.leaves.get(&node.id) {
+            spans.push((node.id, self.span(start, end)));
+            return end;
+        }
+        let open = self.open.get(&node.id);
+        let mut end = node.source_span().map_or(0, |span| span.end.offset);
+        if let Some((name, _)) = open {
+            ancestors.push(name);
+        }
+        let template_children = node.template_contents().map(|contents| contents.children());
+        for child in node
+            .children()
+            .iter()
+            .chain(template_children.iter().flatten())
+        {
+            end = end.max(self.close(child, ancestors, spans));
+        }
+        if let Some((name, start)) = open {
+            ancestors.pop();
+            end = self.end_tag(end, name, ancestors);
+            spans.push((node.id, self.span(*start, end)));
+        }
+        end
+    }
+
+    /// Returns where an element named `name` ends, given where its contents end:
+    /// after its end tag if it is the next tag, or else where the tag closing it
+    /// starts. End tags closing nothing are skipped.
+    fn end_tag(&self, from: usize, name: &str, ancestors: &[&str]) -> usize {
+        let next = self.tags.partition_point(|tag| tag.start < from);
+        for tag in &self.tags[next..] {
+            if !tag.closing {
+                return tag.start;
+            }
+            if tag.closes(name) {
+                return tag.end;
+            }
+            if ancestors.iter().any(|ancestor| tag.closes(ancestor)) {
+                return tag.start;
+            }
+        }
+        self.text.len()
+    }
+
+    fn span(&self, start: usize, end: usize) -> SourceSpan {
+        SourceSpan {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+
+    fn position(&self, offset: usize) -> SourcePosition {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1];
+        SourcePosition {
+            offset,
+            line,
+            column: self.text[line_start..offset].chars().count() + 1,
+        }
+    }
+}
+
+/// Tokens passes the tokens of a tokenizer to a tree builder, recording them in the
+/// source of the sink first.
+struct Tokens<B>(B);
+
+impl html::TokenSink for Tokens<TreeBuilder<NodeId, DocumentTreeSink>> {
+    type Handle = NodeId;
+
+    fn process_token(&mut self, token: html::Token, line_number: u64) -> TokenSinkResult<NodeId> {
+        if let Some(source) = self.0.sink.source() {
+            match &token {
+                html::TagToken(tag) => {
+                    source.tag(&tag.name, tag.kind == html::EndTag, tag.self_closing)
+                }
+                html::CharacterTokens(_) | html::NullCharacterToken => source.chars(),
+                html::ParseError(_) | html::EOFToken => {}
+                _ => source.token(),
+            }
+        }
+        let result = self.0.process_token(token, line_number);
+        self.0.sink.token_processed();
+        result
+    }
+
+    fn end(&mut self) {
+        self.0.end()
+    }
+
+    fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+        self.0
+            .adjusted_current_node_present_but_not_in_html_namespace()
+    }
+}
+
+impl xml::TokenSink for Tokens<XmlTreeBuilder<NodeId, DocumentTreeSink>> {
+    fn process_token(&mut self, token: xml::Token) {
+        if let Some(source) = self.0.sink.source() {
+            match &token {
+                xml::TagToken(tag) => source.tag(
+                    &tag.name.local,
+                    matches!(tag.kind, xml::EndTag | xml::ShortTag),
+                    tag.kind == xml::EmptyTag,
+                ),
+                xml::CharacterTokens(_) | xml::NullCharacterToken => source.chars(),
+                xml::ParseError(_) | xml::EOFToken => {}
+                _ => source.token(),
+            }
+        }
+        self.0.process_token(token);
+        self.0.sink.token_processed();
+    }
+
+    fn end(&mut self) {
+        self.0.end()
+    }
+}
+
+/// Parses HTML with a sink that has a source, feeding it a character at a time.
+pub(crate) fn parse_html(sink: DocumentTreeSink, html: &str) -> Document {
+    let builder = TreeBuilder::new(sink, Default::default());
+    feed_html(builder, Default::default(), html)
+}
+
+/// Parses an HTML fragment in the context element like [`parse_html`], with the
+/// same layout as `html5ever::parse_fragment`.
+pub(crate) fn parse_html_fragment(
+    mut sink: DocumentTreeSink,
+    html: &str,
+    context: QualName,
+    opts: ParseOpts,
+) -> Document {
+    let context = create_element(&mut sink, context, vec![]);
+    let builder = TreeBuilder::new_for_fragment(sink, context, None, opts.tree_builder);
+    let opts = TokenizerOpts {
+        initial_state: Some(builder.tokenizer_state_for_context_elem()),
+        ..opts.tokenizer
+    };
+    feed_html(builder, opts, html)
+}
+
+fn feed_html(
+    builder: TreeBuilder<NodeId, DocumentTreeSink>,
+    opts: TokenizerOpts,
+    html: &str,
+) -> Document {
+    let mut tokenizer = Tokenizer::new(Tokens(builder), opts);
+    let mut input = BufferQueue::default();
+    for (offset, c) in html.char_indices() {
+        if let Some(source) = tokenizer.sink.0.sink.source() {
+            source.offset = Some(offset + c.len_utf8());
+        }
+        input.push_back(StrTendril::from_char(c));
+        while let TokenizerResult::Script(_) = tokenizer.feed(&mut input) {}
+    }
+    tokenizer.end();
+    tokenizer.sink.0.sink.finish()
+}
+
+/// Parses XML with a sink that has a source like [`parse_html`]. The content after
+/// the prolog goes in `html>body`, which are not in the source.
+pub(crate) fn parse_xml(sink: DocumentTreeSink, xml: &str, prolog_len: usize) -> Document {
+    let builder = XmlTreeBuilder::new(sink, Default::default());
+    let mut tokenizer = XmlTokenizer::new(Tokens(builder), Default::default());
+    let mut input = BufferQueue::default();
+    let (prolog, content) = xml.split_at(prolog_len);
+    for (text, from) in [
+        (prolog, Some(0)),
+        ("<html><head/><body>", None),
+        (content, Some(prolog_len)),
+        ("</body></html>", None),
+    ] {
+        for (offset, c) in text.char_indices() {
+            if let Some(source) = tokenizer.sink.0.sink.source() {
+                source.offset = from.map(|from| from + offset + c.len_utf8());
+            }
+            input.push_back(StrTendril::from_char(c));
+            tokenizer.feed(&mut input);
+        }
+    }
+    tokenizer.end();
+    tokenizer.sink.0.sink.finish()
+}
+
+/// Returns the attribute names of a start tag, from the end of its name.
+fn attr_names(tag: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut rest = tag;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if rest.is_empty() || rest.starts_with('>') {
+            return names;
+        }
+        let end = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len());
+        names.push(rest[..end].to_string());
+        rest = rest[end..].trim_start();
+        if let Some(value) = rest.strip_prefix('=') {
+            let value = value.trim_start();
+            let end = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => value[1..].find(quote).map_or(value.len(), |i| i + 2),
+                _ => value
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(value.len()),
+            };
+            rest = &value[end..];
+        }
+    }
+}
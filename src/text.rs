@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Document, Node, NodeData, Selection};
+
+/// The element names treated as blocks by default, the Plate block types.
+const DEFAULT_BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "ul",
+    "ol",
+    "li",
+    "lic",
+    "code_block",
+    "code_line",
+    "table",
+    "tr",
+    "td",
+    "th",
+    "hr",
+    "img",
+];
+
+/// TextOptions configures the plain-text extraction of [`Node::text_with`]. Unlike
+/// `Node::text`, the text of blocks is separated, so `<p>a</p><p>b</p>` becomes
+/// `"a\nb"`.
+///
+/// ```
+/// use dom_query::{Document, TextOptions};
+///
+/// let doc = Document::from_slate_html("<p>Items</p><ul><li>one</li><li>two</li></ul><img>");
+/// let options = TextOptions::new()
+///     .block_separator("\n\n")
+///     .bullet("- ")
+///     .void("img", "[image]");
+/// assert_eq!(doc.text_with(&options), "Items\n\n- one\n\n- two\n\n[image]");
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    blocks: HashSet<String>,
+    block_separator: String,
+    cell_separator: String,
+    row_separator: String,
+    bullet: Option<String>,
+    numbered: bool,
+    voids: HashMap<String, String>,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            blocks: DEFAULT_BLOCKS.iter().map(|name| name.to_string()).collect(),
+            block_separator: "\n".to_string(),
+            cell_separator: "\t".to_string(),
+            row_separator: "\n".to_string(),
+            bullet: None,
+            numbered: false,
+            voids: HashMap::new(),
+        }
+    }
+}
+
+impl TextOptions {
+    /// Creates the default options: the Plate block types are separated by a
+    /// newline, table cells by a tab and rows by a newline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the element names treated as blocks.
+    pub fn blocks<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.blocks = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Treats an element name as a block too.
+    pub fn block(mut self, name: &str) -> Self {
+        self.blocks.insert(name.to_string());
+        self
+    }
+
+    /// Sets the separator between blocks, `"\n"` by default.
+    pub fn block_separator(mut self, separator: &str) -> Self {
+        self.block_separator = separator.to_string();
+        self
+    }
+
+    /// Sets the separator between the cells of a table row, `"\t"` by default.
+    pub fn cell_separator(mut self, separator: &str) -> Self {
+        self.cell_separator = separator.to_string();
+        self
+    }
+
+    /// Sets the separator between the rows of a table, `"\n"` by default.
+    pub fn row_separator(mut self, separator: &str) -> Self {
+        self.row_separator = separator.to_string();
+        self
+    }
+
+    /// Prefixes the items of `ul` lists with the bullet. Nested lists are indented
+    /// by two spaces per level.
+    pub fn bullet(mut self, bullet: &str) -> Self {
+        self.bullet = Some(bullet.to_string());
+        self
+    }
+
+    /// Prefixes the items of `ol` lists with their number, like `"1. "`.
+    pub fn numbered(mut self) -> Self {
+        self.numbered = true;
+        self
+    }
+
+    /// Replaces the elements with the name, and their contents, with a placeholder,
+    /// e.g. `[image]` for images.
+    pub fn void(mut self, name: &str, placeholder: &str) -> Self {
+        self.voids.insert(name.to_string(), placeholder.to_string());
+        self
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Returns the text of the node and its descendants, with the blocks separated
+    /// as configured by the options.
+    pub fn text_with(&self, options: &TextOptions) -> String {
+        let mut extractor = Extractor::new(options);
+        extractor.walk(self, 0, 0);
+        extractor.out
+    }
+}
+
+impl<'a> Selection<'a> {
+    /// Returns the text of the matched nodes, see [`Node::text_with`]. The nodes
+    /// are separated like blocks.
+    pub fn text_with(&self, options: &TextOptions) -> String {
+        let mut extractor = Extractor::new(options);
+        for node in self.nodes() {
+            extractor.walk(node, 0, 0);
+            extractor.boundary(&options.block_separator, 0);
+        }
+        extractor.out
+    }
+}
+
+impl Document {
+    /// Returns the text of the document content, see [`Node::text_with`]. The
+    /// `head`, like a `title`, is left out.
+    pub fn text_with(&self, options: &TextOptions) -> String {
+        let root = self.tree.get_unchecked(&self.tree.content_root_id());
+        root.text_with(options)
+    }
+}
+
+struct Extractor<'o> {
+    options: &'o TextOptions,
+    out: String,
+    /// The separator to write before the next text, with the depth of the
+    /// outermost boundary crossed since the last text, which decides it.
+    pending: Option<(&'o str, usize)>,
+    /// The list item prefix to write before the next text.
+    prefix: String,
+}
+
+impl<'o> Extractor<'o> {
+    fn new(options: &'o TextOptions) -> Self {
+        Self {
+            options,
+            out: String::new(),
+            pending: None,
+            prefix: String::new(),
+        }
+    }
+
+    fn walk(&mut self, node: &Node, depth: usize, list_depth: usize) {
+        let name = match node
+            .query(|n| match n.data {
+                NodeData::Text(ref t) => Some(Err(t.contents.to_string())),
+                NodeData::Element(ref e) => Some(Ok(Some(e.name.to_string()))),
+                NodeData::Document => Some(Ok(None)),
+                _ => None,
+            })
+            .flatten()
+        {
+            Some(Err(text)) => return self.write(&text),
+            Some(Ok(name)) => name,
+            None => return,
+        };
+
+        let options = self.options;
+        let separator = match name.as_deref() {
+            Some("td") | Some("th") => Some(&options.cell_separator),
+            Some("tr") => Some(&options.row_separator),
+            Some(name) if options.blocks.contains(name) => Some(&options.block_separator),
+            _ => None,
+        };
+        if let Some(separator) = separator {
+            self.boundary(separator, depth);
+        }
+
+        let mut list_depth = list_depth;
+        match name.as_deref() {
+            Some("ul") | Some("ol") => list_depth += 1,
+            Some("li") => self.prefix = self.list_prefix(node, list_depth),
+            _ => {}
+        }
+
+        match name.as_ref().and_then(|name| options.voids.get(name)) {
+            Some(placeholder) => self.write(placeholder),
+            None => {
+                for child in node.children() {
+                    self.walk(&child, depth + 1, list_depth);
+                }
+            }
+        }
+
+        if let Some(separator) = separator {
+            self.boundary(separator, depth);
+        }
+    }
+
+    /// Marks a block boundary at `depth`. Between two texts, the separator of the
+    /// outermost boundary wins, e.g. a row separator over a cell separator.
+    fn boundary(&mut self, separator: &'o str, depth: usize) {
+        match self.pending {
+            Some((_, pending_depth)) if pending_depth <= depth => {}
+            _ => self.pending = Some((separator, depth)),
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some((separator, _)) = self.pending.take() {
+            if !self.out.is_empty() {
+                self.out.push_str(separator);
+            }
+        }
+        self.out.push_str(&self.prefix);
+        self.prefix.clear();
+        self.out.push_str(text);
+    }
+
+    fn list_prefix(&self, item: &Node, list_depth: usize) -> String {
+        let parent = match item.parent() {
+            Some(parent) => parent,
+            None => return String::new(),
+        };
+        let indent = "  ".repeat(list_depth.saturating_sub(1));
+        match parent.node_name().as_deref() {
+            Some("ol") if self.options.numbered => {
+                let number = parent
+                    .children()
+                    .iter()
+                    .take_while(|sibling| sibling.id != item.id)
+                    .filter(|sibling| sibling.node_name().as_deref() == Some("li"))
+                    .count()
+                    + 1;
+                format!("{}{}. ", indent, number)
+            }
+            Some("ul") => match self.options.bullet {
+                Some(ref bullet) => format!("{}{}", indent, bullet),
+                None => String::new(),
+            },
+            _ => String::new(),
+        }
+    }
+}
@@ -0,0 +1,884 @@
+use serde_json::{Map, Value};
+
+use crate::range::{byte_offset, text_len};
+use crate::{Affinity, Operation, Path, RangeProperties};
+
+/// Side decides which of two concurrent operations goes first when both insert at
+/// the same place, and which one wins when they can not both apply, see
+/// [`Operation::transform`]. The two sites of a collaboration must pass opposite
+/// sides, e.g. the server's operations are `Left` and the client's `Right`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The transformed operation goes first.
+    Left,
+    /// The concurrent operation goes first.
+    Right,
+}
+
+impl Side {
+    /// Returns the other side.
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+impl Operation {
+    /// Transforms the operation against `concurrent`, an operation made on the same
+    /// version of the document, so that it applies after `concurrent`. Applying `b`
+    /// then `a.transform(&b, side)` gives the same document as applying `a` then
+    /// `b.transform(&a, side.opposite())`.
+    ///
+    /// The result is empty if the operation has nothing left to do, e.g. its node
+    /// was removed, and has more than one operation if it is split, e.g. a text
+    /// removal around an insertion. Conflicts are resolved the same way on both
+    /// sides:
+    /// - removals win over changes to the removed nodes, a node moved or inserted
+    ///   into a removed node is removed too;
+    /// - for two moves of the same node, a merge and a move of the merged nodes,
+    ///   and the moves that would put two nodes inside each other, the `Left` one
+    ///   wins and the other is undone;
+    /// - a node inserted between merged nodes ends up after the merged node, and
+    ///   so does a node moved there, unless the move is `Left`;
+    /// - for properties set on the same node by both, the `Left` values win.
+    ///
+    /// Removals whose node is not known, like a node moved into a removed node, carry
+    /// a `null` node. Their [`Operation::inverse`] can not be applied, so apply them
+    /// with [`Document::apply`](crate::Document::apply): the operation it records and
+    /// adds to the undo history carries the node read from the document.
+    ///
+    /// ```
+    /// use dom_query::{Operation, Path, Side};
+    ///
+    /// let a = Operation::InsertText {
+    ///     path: Path::new(vec![0, 0]),
+    ///     offset: 5,
+    ///     text: " there".to_string(),
+    /// };
+    /// let b = Operation::InsertNode {
+    ///     path: Path::new(vec![0]),
+    ///     node: serde_json::json!({"type": "p", "children": [{"text": "Title"}]}),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     a.transform(&b, Side::Left),
+    ///     vec![Operation::InsertText {
+    ///         path: Path::new(vec![1, 0]),
+    ///         offset: 5,
+    ///         text: " there".to_string(),
+    ///     }]
+    /// );
+    /// ```
+    pub fn transform(&self, concurrent: &Operation, side: Side) -> Vec<Operation> {
+        if let Operation::SetSelection { .. } = concurrent {
+            return vec![self.clone()];
+        }
+        if is_noop_move(concurrent) {
+            return vec![self.clone()];
+        }
+        match self {
+            Operation::InsertText { path, offset, text } => {
+                transform_insert_text(path, *offset, text, concurrent, side)
+            }
+            Operation::RemoveText { path, offset, text } => {
+                transform_remove_text(path, *offset, text, concurrent)
+            }
+            Operation::InsertNode { path, node } => transform_gap(path, concurrent, side)
+                .map(|path| Operation::InsertNode {
+                    path,
+                    node: node.clone(),
+                })
+                .into_iter()
+                .collect(),
+            Operation::RemoveNode { path, node } => transform_remove_node(path, node, concurrent),
+            Operation::MergeNode {
+                path,
+                position,
+                properties,
+            } => transform_merge_node(self, path, *position, properties, concurrent, side),
+            Operation::SplitNode {
+                path,
+                position,
+                properties,
+            } => transform_split_node(path, *position, properties, concurrent, side),
+            Operation::MoveNode { path, new_path } => {
+                transform_move_node(self, path, new_path, concurrent, side)
+            }
+            Operation::SetNode {
+                path,
+                properties,
+                new_properties,
+            } => transform_set_node(path, properties, new_properties, concurrent, side),
+            Operation::SetSelection {
+                properties,
+                new_properties,
+            } => vec![Operation::SetSelection {
+                properties: properties.as_ref().map(|p| transform_range(p, concurrent)),
+                new_properties: new_properties
+                    .as_ref()
+                    .map(|p| transform_range(p, concurrent)),
+            }],
+        }
+    }
+
+    /// Transforms the operations against the concurrent ones, both lists made on
+    /// the same version of the document, so that they apply after `concurrent`.
+    /// This is how a server rebases a batch of edits, see [`Operation::transform`].
+    pub fn transform_all(
+        ops: &[Operation],
+        concurrent: &[Operation],
+        side: Side,
+    ) -> Vec<Operation> {
+        transform_lists(ops.to_vec(), concurrent.to_vec(), side).0
+    }
+}
+
+/// Transforms the lists against each other, returning both transformed lists.
+fn transform_lists(
+    ops: Vec<Operation>,
+    concurrent: Vec<Operation>,
+    side: Side,
+) -> (Vec<Operation>, Vec<Operation>) {
+    if ops.is_empty() || concurrent.is_empty() {
+        return (ops, concurrent);
+    }
+    if ops.len() == 1 && concurrent.len() == 1 {
+        let (a, b) = (&ops[0], &concurrent[0]);
+        return (a.transform(b, side), b.transform(a, side.opposite()));
+    }
+    if ops.len() > 1 {
+        let mut ops = ops;
+        let rest = ops.split_off(1);
+        let (mut first, concurrent) = transform_lists(ops, concurrent, side);
+        let (rest, concurrent) = transform_lists(rest, concurrent, side);
+        first.extend(rest);
+        return (first, concurrent);
+    }
+    let mut concurrent = concurrent;
+    let rest = concurrent.split_off(1);
+    let (ops, mut first) = transform_lists(ops, concurrent, side);
+    let (ops, rest) = transform_lists(ops, rest, side);
+    first.extend(rest);
+    (ops, first)
+}
+
+fn transform_insert_text(
+    path: &Path,
+    offset: usize,
+    text: &str,
+    b: &Operation,
+    side: Side,
+) -> Vec<Operation> {
+    let (path, offset) = match b {
+        Operation::InsertText {
+            path: q,
+            offset: b_offset,
+            text: b_text,
+        } if q == path => {
+            if *b_offset < offset || (*b_offset == offset && side == Side::Right) {
+                (path.clone(), offset + text_len(b_text))
+            } else {
+                (path.clone(), offset)
+            }
+        }
+        Operation::RemoveText {
+            path: q,
+            offset: b_offset,
+            text: b_text,
+        } if q == path => {
+            let end = b_offset + text_len(b_text);
+            if offset <= *b_offset {
+                (path.clone(), offset)
+            } else if offset >= end {
+                (path.clone(), offset - text_len(b_text))
+            } else {
+                (path.clone(), *b_offset)
+            }
+        }
+        Operation::SplitNode {
+            path: q, position, ..
+        } if q == path => match offset <= *position {
+            true => (path.clone(), offset),
+            false => (next(path), offset - position),
+        },
+        Operation::MergeNode {
+            path: q, position, ..
+        } if q == path => (previous(path), offset + position),
+        _ => match path.transform(b, Some(Affinity::Backward)) {
+            Some(path) => (path, offset),
+            None => return vec![],
+        },
+    };
+    vec![Operation::InsertText {
+        path,
+        offset,
+        text: text.to_string(),
+    }]
+}
+
+fn transform_remove_text(path: &Path, offset: usize, text: &str, b: &Operation) -> Vec<Operation> {
+    // Offsets count UTF-16 code units, so the text is split on them.
+    let remove = |path: &Path, offset: usize, units: &[u16]| Operation::RemoveText {
+        path: path.clone(),
+        offset,
+        text: String::from_utf16_lossy(units),
+    };
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let end = offset + units.len();
+
+    match b {
+        Operation::InsertText {
+            path: q,
+            offset: b_offset,
+            text: b_text,
+        } if q == path => {
+            let len = text_len(b_text);
+            if *b_offset <= offset {
+                vec![remove(path, offset + len, &units)]
+            } else if *b_offset >= end {
+                vec![remove(path, offset, &units)]
+            } else {
+                // Keep the inserted text, remove what is around it.
+                let (before, after) = units.split_at(b_offset - offset);
+                vec![
+                    remove(path, offset, before),
+                    remove(path, offset + len, after),
+                ]
+            }
+        }
+        Operation::RemoveText {
+            path: q,
+            offset: b_offset,
+            text: b_text,
+        } if q == path => {
+            let b_end = b_offset + text_len(b_text);
+            let left: Vec<u16> = units
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !(*b_offset..b_end).contains(&(offset + i)))
+                .map(|(_, c)| *c)
+                .collect();
+            if left.is_empty() {
+                return vec![];
+            }
+            let removed_before = b_end.min(offset).saturating_sub(*b_offset);
+            vec![remove(path, offset - removed_before, &left)]
+        }
+        Operation::SplitNode {
+            path: q, position, ..
+        } if q == path => {
+            if end <= *position {
+                vec![remove(path, offset, &units)]
+            } else if offset >= *position {
+                vec![remove(&next(path), offset - position, &units)]
+            } else {
+                let (before, after) = units.split_at(position - offset);
+                vec![remove(path, offset, before), remove(&next(path), 0, after)]
+            }
+        }
+        Operation::MergeNode {
+            path: q, position, ..
+        } if q == path => vec![remove(&previous(path), offset + position, &units)],
+        _ => path
+            .transform(b, Some(Affinity::Backward))
+            .map(|path| remove(&path, offset, &units))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn transform_remove_node(path: &Path, node: &Value, b: &Operation) -> Vec<Operation> {
+    let remove = |path: Path, node: Value| Operation::RemoveNode { path, node };
+    match b {
+        Operation::SplitNode {
+            path: q,
+            position,
+            properties,
+        } if q == path => {
+            let (first, second) = split_value(node, *position, properties);
+            vec![remove(path.clone(), first), remove(path.clone(), second)]
+        }
+        // The removed node was merged, or merged into: split the merge again and
+        // remove the node alone.
+        Operation::MergeNode {
+            path: q,
+            position,
+            properties,
+        } if q == path || q.previous().as_ref() == Some(path) => {
+            let target = previous(q);
+            vec![
+                Operation::SplitNode {
+                    path: target.clone(),
+                    position: *position,
+                    properties: properties.clone(),
+                },
+                remove(if q == path { q.clone() } else { target }, node.clone()),
+            ]
+        }
+        // A node moved out of the removed node is removed too.
+        Operation::MoveNode { path: from, .. } if path.is_ancestor(from) => {
+            let path = match path.transform(b, None) {
+                Some(path) => path,
+                None => return vec![],
+            };
+            let to = from
+                .transform(b, None)
+                .expect("a moved path always transforms");
+            if path.is_ancestor(&to) {
+                return vec![remove(path, node.clone())];
+            }
+            let mut node = node.clone();
+            let moved = take_value(&mut node, &from[path.len()..]).unwrap_or(Value::Null);
+            let removal = remove(to, moved);
+            let path = path
+                .transform(&removal, None)
+                .expect("the moved node is not an ancestor");
+            vec![removal, remove(path, node)]
+        }
+        _ => path
+            .transform(b, Some(Affinity::Backward))
+            .map(|path| remove(path, node.clone()))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn transform_merge_node(
+    a: &Operation,
+    path: &Path,
+    position: usize,
+    properties: &Map<String, Value>,
+    b: &Operation,
+    side: Side,
+) -> Vec<Operation> {
+    let target = match path.previous() {
+        Some(target) => target,
+        None => return vec![a.clone()],
+    };
+    let merge =
+        |path: Path, position: usize, properties: Map<String, Value>| Operation::MergeNode {
+            path,
+            position,
+            properties,
+        };
+
+    let mut position = position;
+    let mut properties = properties.clone();
+    match b {
+        Operation::InsertText { path: q, text, .. } if *q == target => {
+            position += text_len(text);
+        }
+        Operation::RemoveText { path: q, text, .. } if *q == target => {
+            position -= text_len(text);
+        }
+        Operation::SplitNode {
+            path: q,
+            position: b_position,
+            ..
+        } if *q == target => {
+            return vec![merge(next(path), position - b_position, properties)];
+        }
+        Operation::MergeNode { path: q, .. } if q == path => return vec![],
+        Operation::MergeNode {
+            path: q,
+            position: b_position,
+            ..
+        } if *q == target => return vec![merge(target, position + b_position, properties)],
+        Operation::RemoveNode { path: q, .. } if q == path || *q == target => return vec![],
+        // The merged nodes were moved, the `Left` one wins.
+        Operation::MoveNode { path: from, .. } if from == path || *from == target => {
+            return match side {
+                Side::Left => vec![b.inverse(), a.clone()],
+                Side::Right => vec![],
+            };
+        }
+        Operation::SetNode {
+            path: q,
+            properties: b_properties,
+            new_properties,
+        } if q == path => {
+            properties = set_properties(&properties, b_properties, new_properties);
+        }
+        _ => position = transform_position(&target, position, true, b),
+    }
+
+    let target = match target.transform(b, Some(Affinity::Backward)) {
+        Some(target) => target,
+        None => return vec![],
+    };
+    let path = match path.transform(b, Some(Affinity::Backward)) {
+        Some(path) => path,
+        None => return vec![],
+    };
+    let next_to_target = next(&target);
+    if path == next_to_target {
+        return vec![merge(path, position, properties)];
+    }
+    // A node was inserted or moved between the nodes: move it after them. For a
+    // move, the `Left` one wins.
+    let between = match b {
+        Operation::InsertNode { path: q, .. } => Some(q.clone()),
+        Operation::MoveNode { path: from, .. } => from.transform(b, None),
+        _ => None,
+    };
+    if between.as_ref() == Some(&next_to_target) && path == next(&next_to_target) {
+        if side == Side::Right && matches!(b, Operation::MoveNode { .. }) {
+            return vec![];
+        }
+        let mut ops = move_to_gap(&next_to_target, &next(&path))
+            .into_iter()
+            .collect::<Vec<_>>();
+        ops.push(merge(next_to_target, position, properties));
+        return ops;
+    }
+    // They were split apart: move the node back next to its target.
+    let mut ops = move_to_gap(&path, &next_to_target)
+        .into_iter()
+        .collect::<Vec<_>>();
+    ops.push(merge(next_to_target, position, properties));
+    ops
+}
+
+fn transform_split_node(
+    path: &Path,
+    position: usize,
+    properties: &Map<String, Value>,
+    b: &Operation,
+    side: Side,
+) -> Vec<Operation> {
+    let split =
+        |path: Path, position: usize, properties: Map<String, Value>| Operation::SplitNode {
+            path,
+            position,
+            properties,
+        };
+
+    let mut position = position;
+    let mut properties = properties.clone();
+    match b {
+        Operation::InsertText {
+            path: q,
+            offset,
+            text,
+        } if q == path => {
+            if *offset <= position {
+                position += text_len(text);
+            }
+        }
+        Operation::RemoveText {
+            path: q,
+            offset,
+            text,
+        } if q == path => {
+            position -= text_len(text).min(position.saturating_sub(*offset));
+        }
+        Operation::SplitNode {
+            path: q,
+            position: b_position,
+            properties: b_properties,
+        } if q == path => {
+            return match position.cmp(b_position) {
+                std::cmp::Ordering::Less => vec![split(path.clone(), position, properties)],
+                std::cmp::Ordering::Greater => {
+                    vec![split(next(path), position - b_position, properties)]
+                }
+                // The same split, the `Left` properties win.
+                std::cmp::Ordering::Equal if side == Side::Left && properties != *b_properties => {
+                    vec![Operation::SetNode {
+                        path: next(path),
+                        properties: b_properties.clone(),
+                        new_properties: properties,
+                    }]
+                }
+                std::cmp::Ordering::Equal => vec![],
+            };
+        }
+        Operation::MergeNode {
+            path: q,
+            position: b_position,
+            ..
+        } if q == path => {
+            return vec![split(previous(path), position + b_position, properties)];
+        }
+        Operation::SetNode {
+            path: q,
+            properties: b_properties,
+            new_properties,
+        } if q == path => {
+            properties = set_properties(&properties, b_properties, new_properties);
+        }
+        _ => position = transform_position(path, position, false, b),
+    }
+
+    path.transform(b, Some(Affinity::Backward))
+        .map(|path| split(path, position, properties))
+        .into_iter()
+        .collect()
+}
+
+fn transform_move_node(
+    a: &Operation,
+    path: &Path,
+    new_path: &Path,
+    b: &Operation,
+    side: Side,
+) -> Vec<Operation> {
+    if is_noop_move(a) {
+        return vec![];
+    }
+    let gap = move_gap(path, new_path);
+    match b {
+        // Both moved the node, the `Left` move wins.
+        Operation::MoveNode { path: from, .. } if from == path => {
+            return match side {
+                Side::Left => vec![b.inverse(), a.clone()],
+                Side::Right => vec![],
+            };
+        }
+        // The node was merged, the `Left` one wins.
+        Operation::MergeNode { path: q, .. }
+            if q == path || q.previous().as_ref() == Some(path) =>
+        {
+            return match side {
+                Side::Left => vec![b.inverse(), a.clone()],
+                Side::Right => vec![],
+            };
+        }
+        // The node is moved between merged nodes, the merge is undone if the move
+        // is `Left`, the node moved after the merged node if not.
+        Operation::MergeNode { path: q, .. } if side == Side::Left && is_between(a, path, q) => {
+            return vec![b.inverse(), a.clone()];
+        }
+        _ => {}
+    }
+
+    let from = match path.transform(b, Some(Affinity::Backward)) {
+        Some(from) => from,
+        None => return vec![],
+    };
+    let gap = match transform_gap(&gap, b, side) {
+        Some(gap) => gap,
+        // The node is moved into a removed node.
+        None => {
+            return vec![Operation::RemoveNode {
+                path: from,
+                node: Value::Null,
+            }];
+        }
+    };
+    // The moves put the nodes inside each other, the `Left` move wins.
+    if from.is_ancestor(&gap) {
+        return match side {
+            Side::Left => vec![b.inverse(), a.clone()],
+            Side::Right => vec![],
+        };
+    }
+
+    let mut ops: Vec<Operation> = move_to_gap(&from, &gap).into_iter().collect();
+    // The node was split, move both parts.
+    if let Operation::SplitNode { path: q, .. } = b {
+        if q == path {
+            let first = ops.first().cloned();
+            let (second, first_to) = match &first {
+                Some(op) => (
+                    next(&from)
+                        .transform(op, None)
+                        .expect("siblings always transform"),
+                    from.transform(op, None)
+                        .expect("a moved path always transforms"),
+                ),
+                None => (next(&from), from.clone()),
+            };
+            ops.extend(move_to_gap(&second, &next(&first_to)));
+        }
+    }
+    ops
+}
+
+fn transform_set_node(
+    path: &Path,
+    properties: &Map<String, Value>,
+    new_properties: &Map<String, Value>,
+    b: &Operation,
+    side: Side,
+) -> Vec<Operation> {
+    let set = |path: Path, properties: Map<String, Value>, new_properties: Map<String, Value>| {
+        Operation::SetNode {
+            path,
+            properties,
+            new_properties,
+        }
+    };
+    match b {
+        Operation::SetNode {
+            path: q,
+            properties: b_properties,
+            new_properties: b_new_properties,
+        } if q == path => {
+            if side == Side::Left {
+                return vec![set(
+                    path.clone(),
+                    properties.clone(),
+                    new_properties.clone(),
+                )];
+            }
+            // The `Left` values win, drop the keys the other operation set.
+            let untouched = |map: &Map<String, Value>| -> Map<String, Value> {
+                map.iter()
+                    .filter(|(k, _)| {
+                        !b_properties.contains_key(*k) && !b_new_properties.contains_key(*k)
+                    })
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect()
+            };
+            let (properties, new_properties) = (untouched(properties), untouched(new_properties));
+            if properties.is_empty() && new_properties.is_empty() {
+                return vec![];
+            }
+            vec![set(path.clone(), properties, new_properties)]
+        }
+        Operation::SplitNode { path: q, .. } if q == path => vec![
+            set(path.clone(), properties.clone(), new_properties.clone()),
+            set(next(path), properties.clone(), new_properties.clone()),
+        ],
+        Operation::MergeNode { path: q, .. } if q == path => vec![],
+        _ => path
+            .transform(b, Some(Affinity::Backward))
+            .map(|path| set(path, properties.clone(), new_properties.clone()))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn transform_range(props: &RangeProperties, b: &Operation) -> RangeProperties {
+    let point = |point: &Option<crate::Point>| {
+        point.as_ref().map(|point| {
+            point
+                .transform(b, Some(Affinity::Forward))
+                .unwrap_or_else(|| point.clone())
+        })
+    };
+    RangeProperties {
+        anchor: point(&props.anchor),
+        focus: point(&props.focus),
+    }
+}
+
+/// Transforms the place where a node is inserted, given as the path the node gets.
+/// Returns `None` if the place is removed.
+fn transform_gap(gap: &Path, b: &Operation, side: Side) -> Option<Path> {
+    match b {
+        Operation::InsertNode { path: q, .. } if q == gap => match side {
+            Side::Left => Some(gap.clone()),
+            Side::Right => Some(next(gap)),
+        },
+        // The gap before a removed, split or merged node stays where it is. After a
+        // merge, it is after the merged node.
+        Operation::RemoveNode { path: q, .. }
+        | Operation::SplitNode { path: q, .. }
+        | Operation::MergeNode { path: q, .. }
+            if q == gap =>
+        {
+            Some(gap.clone())
+        }
+        Operation::MoveNode { path: from, .. } => {
+            let to = from.transform(b, None)?;
+            if from.is_ancestor(gap) {
+                let mut path = to.to_vec();
+                path.extend_from_slice(&gap[from.len()..]);
+                return Some(Path::new(path));
+            }
+            let mut removed = gap.to_vec();
+            if from.ends_before(gap) {
+                removed[from.len() - 1] -= 1;
+            }
+            let removed = Path::new(removed);
+            if to == removed {
+                return match side {
+                    Side::Left => Some(removed),
+                    Side::Right => Some(next(&removed)),
+                };
+            }
+            let mut path = removed.to_vec();
+            if to.ends_before(&removed) || to.is_ancestor(&removed) {
+                path[to.len() - 1] += 1;
+            }
+            Some(Path::new(path))
+        }
+        _ => gap.transform(b, Some(Affinity::Forward)),
+    }
+}
+
+/// Transforms the number of children of the node at `path` that are before a
+/// position, for a `b` that changes the children of the node. A child inserted at
+/// the position counts if `at_end`, for the position of a merge.
+fn transform_position(path: &Path, position: usize, at_end: bool, b: &Operation) -> usize {
+    let is_child = |q: &Path| q.parent().as_ref() == Some(path);
+    let is_before =
+        |index: usize, position: usize| index < position || (at_end && index == position);
+    match b {
+        Operation::InsertNode { path: q, .. }
+            if is_child(q) && is_before(q[q.len() - 1], position) =>
+        {
+            position + 1
+        }
+        Operation::SplitNode { path: q, .. } if is_child(q) && q[q.len() - 1] < position => {
+            position + 1
+        }
+        Operation::RemoveNode { path: q, .. } | Operation::MergeNode { path: q, .. }
+            if is_child(q) && q[q.len() - 1] < position =>
+        {
+            position - 1
+        }
+        Operation::MoveNode { path: from, .. } => {
+            let mut position = position;
+            if is_child(from) && from[from.len() - 1] < position {
+                position -= 1;
+            }
+            let removal = Operation::RemoveNode {
+                path: from.clone(),
+                node: Value::Null,
+            };
+            let to = from.transform(b, None);
+            match (path.transform(&removal, None), to) {
+                (Some(path), Some(to))
+                    if to.parent().as_ref() == Some(&path)
+                        && is_before(to[to.len() - 1], position) =>
+                {
+                    position + 1
+                }
+                _ => position,
+            }
+        }
+        _ => position,
+    }
+}
+
+/// Returns the place a move puts its node, as a gap before the node is removed.
+fn move_gap(from: &Path, new_path: &Path) -> Path {
+    let op = Operation::MoveNode {
+        path: from.clone(),
+        new_path: new_path.clone(),
+    };
+    let to = from
+        .transform(&op, None)
+        .expect("a moved path always transforms");
+    let level = from.len() - 1;
+    let mut gap = to.to_vec();
+    if gap.len() > level && gap[..level] == from[..level] && gap[level] >= from[level] {
+        gap[level] += 1;
+    }
+    Path::new(gap)
+}
+
+/// Returns the move of the node at `from` to the gap, `None` if it stays.
+fn move_to_gap(from: &Path, gap: &Path) -> Option<Operation> {
+    let level = from.len() - 1;
+    let mut to = gap.to_vec();
+    if to.len() > level && to[..level] == from[..level] && to[level] > from[level] {
+        to[level] -= 1;
+    }
+    let to = Path::new(to);
+    if to == *from {
+        return None;
+    }
+    // Deeper paths are given before the removal, like the gap.
+    let new_path = match from.len() < to.len() {
+        true => gap.clone(),
+        false => to,
+    };
+    Some(Operation::MoveNode {
+        path: from.clone(),
+        new_path,
+    })
+}
+
+/// Checks if the move puts the node between the node at `merged` and its previous
+/// sibling.
+fn is_between(a: &Operation, from: &Path, merged: &Path) -> bool {
+    let to = from.transform(a, None);
+    let target = merged
+        .previous()
+        .and_then(|target| target.transform(a, None));
+    match (to, target, merged.transform(a, None)) {
+        (Some(to), Some(target), Some(merged)) => to == next(&target) && merged == next(&to),
+        _ => false,
+    }
+}
+
+fn is_noop_move(op: &Operation) -> bool {
+    match op {
+        Operation::MoveNode { path, new_path } => {
+            path == new_path || path.transform(op, None).as_ref() == Some(path)
+        }
+        _ => false,
+    }
+}
+
+/// Applies the changes of a `set_node` operation to properties, like applying
+/// the operation to the node does.
+fn set_properties(
+    props: &Map<String, Value>,
+    properties: &Map<String, Value>,
+    new_properties: &Map<String, Value>,
+) -> Map<String, Value> {
+    let mut props = props.clone();
+    for (key, value) in new_properties {
+        match value {
+            Value::Null => props.remove(key),
+            value => props.insert(key.clone(), value.clone()),
+        };
+    }
+    for key in properties.keys() {
+        if key != "type" && !new_properties.contains_key(key) {
+            props.remove(key);
+        }
+    }
+    props
+}
+
+/// Splits a Slate node value like a `split_node` operation splits the node.
+fn split_value(node: &Value, position: usize, properties: &Map<String, Value>) -> (Value, Value) {
+    let mut first = node.clone();
+    let mut second = Value::Object(properties.clone());
+    match (first.as_object_mut(), second.as_object_mut()) {
+        (Some(first), Some(second)) => {
+            if let Some(Value::String(text)) = first.get_mut("text") {
+                let at = byte_offset(text, position).unwrap_or(text.len());
+                let after = text.split_off(at);
+                second.insert("text".to_string(), Value::String(after));
+            } else if let Some(Value::Array(children)) = first.get_mut("children") {
+                let after = children.split_off(position.min(children.len()));
+                second.insert("children".to_string(), Value::Array(after));
+                if let (false, Some(name)) = (second.contains_key("type"), first.get("type")) {
+                    second.insert("type".to_string(), name.clone());
+                }
+            }
+        }
+        _ => return (Value::Null, Value::Null),
+    }
+    (first, second)
+}
+
+/// Removes the descendant at the relative path from a Slate node value.
+fn take_value(node: &mut Value, path: &[usize]) -> Option<Value> {
+    let (last, parent) = path.split_last()?;
+    let mut node = node;
+    for index in parent {
+        node = node.get_mut("children")?.get_mut(*index)?;
+    }
+    match node.get_mut("children")? {
+        Value::Array(children) if *last < children.len() => Some(children.remove(*last)),
+        _ => None,
+    }
+}
+
+fn next(path: &Path) -> Path {
+    path.next().unwrap_or_else(|| path.clone())
+}
+
+fn previous(path: &Path) -> Path {
+    path.previous().unwrap_or_else(|| path.clone())
+}
@@ -10,7 +10,7 @@ impl Document {
     /// # Panics
     ///
     /// Panics if failed to parse the given CSS selector.
-    pub fn select(&self, sel: &str) -> Selection {
+    pub fn select(&self, sel: &str) -> Selection<'_> {
         let matcher = Matcher::new(sel).expect("Invalid CSS selector");
         let root = self.tree.root();
         let nodes: Vec<Node> = Matches::from_one(root, &matcher, MatchScope::IncludeNode).collect();
@@ -23,13 +23,13 @@ impl Document {
     /// # Panics
     ///
     /// Panics if failed to parse the given CSS selector.
-    pub fn nip(&self, sel: &str) -> Selection {
+    pub fn nip(&self, sel: &str) -> Selection<'_> {
         self.select(sel)
     }
 
     /// Gets the descendants of the root document node in the current, filter by a selector.
     /// It returns a new selection object containing these matched elements.
-    pub fn try_select(&self, sel: &str) -> Option<Selection> {
+    pub fn try_select(&self, sel: &str) -> Option<Selection<'_>> {
         match Matcher::new(sel) {
             Ok(matcher) => {
                 let root = self.tree.root();
@@ -0,0 +1,478 @@
+use std::borrow::Cow;
+
+use html5ever::parse_document;
+use markup5ever::interface::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
+use markup5ever::{Attribute, ExpandedName, QualName};
+use serde_json::{Map, Value};
+use tendril::{StrTendril, TendrilSink};
+
+use crate::builder::SlateBuilder;
+use crate::Document;
+
+/// The elements dropped with their contents.
+const DROPPED: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "iframe", "object", "embed", "svg", "math",
+    "canvas", "audio", "video", "input", "select", "textarea", "caption", "colgroup",
+];
+
+/// The wrappers that separate blocks. Their contents are kept, but not the wrappers.
+const BLOCK_WRAPPERS: &[&str] = &[
+    "html",
+    "body",
+    "div",
+    "section",
+    "article",
+    "main",
+    "header",
+    "footer",
+    "nav",
+    "aside",
+    "figure",
+    "figcaption",
+    "address",
+    "details",
+    "summary",
+    "dl",
+    "dt",
+    "dd",
+    "form",
+    "fieldset",
+    "center",
+    "hgroup",
+];
+
+/// The elements that become blocks.
+const BLOCKS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "ul",
+    "ol",
+    "li",
+    "pre",
+    "hr",
+    "table",
+    "thead",
+    "tbody",
+    "tfoot",
+    "tr",
+    "td",
+    "th",
+];
+
+impl Document {
+    /// Parses ordinary HTML, like a web page or the clipboard contents, into a
+    /// document with the Plate element types. Unlike [`Document::from_slate_html`],
+    /// it does not expect `<text>` leaves:
+    ///
+    /// * `<strong>`, `<b>`, `<em>`, `<i>`, `<u>`, `<s>`, `<del>`, `<code>`, `<sup>`
+    ///   and `<sub>` become marks on the text leaves.
+    /// * Paragraphs, headings, block quotes, lists, tables, `<pre>`, `<hr>`, `<img>`
+    ///   and `<a>` become the Plate elements, see [`Document::to_markdown`].
+    /// * Scripts, styles and other non-content elements are dropped.
+    /// * Other elements are flattened into their contents, and the wrappers like
+    ///   `<div>` separate paragraphs.
+    ///
+    /// The whitespace is collapsed like browsers do, except in `<pre>`.
+    ///
+    /// ```
+    /// use dom_query::Document;
+    ///
+    /// let doc = Document::from_web_html(
+    ///     "<div><script>x()</script><h2>Title</h2><span>Some <b>bold</b></span></div>",
+    /// );
+    /// assert_eq!(
+    ///     doc.to_slate_json(),
+    ///     r#"[{"children":[{"text":"Title"}],"type":"h2"},{"children":[{"text":"Some "},{"bold":true,"text":"bold"}],"type":"p"}]"#
+    /// );
+    /// ```
+    pub fn from_web_html(html: &str) -> Document {
+        let sink = parse_document(WebSink::default(), Default::default()).one(html);
+        let mut importer = WebImporter {
+            sink: &sink,
+            builder: SlateBuilder::default(),
+        };
+        importer.children(0);
+        importer.builder.into_document()
+    }
+}
+
+/// A node of the DOM built by [`WebSink`].
+struct WebNode {
+    data: WebData,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+enum WebData {
+    Document,
+    Element(QualName, Vec<Attribute>),
+    Text(StrTendril),
+    /// Comments, processing instructions and template contents.
+    Other,
+}
+
+/// WebSink builds a plain DOM that keeps the text where the HTML parser puts it,
+/// unlike `DocumentTreeSink` which expects `<text>` leaves.
+struct WebSink {
+    nodes: Vec<WebNode>,
+}
+
+impl Default for WebSink {
+    fn default() -> Self {
+        Self {
+            nodes: vec![WebNode {
+                data: WebData::Document,
+                parent: None,
+                children: vec![],
+            }],
+        }
+    }
+}
+
+impl WebSink {
+    fn create(&mut self, data: WebData) -> usize {
+        self.nodes.push(WebNode {
+            data,
+            parent: None,
+            children: vec![],
+        });
+        self.nodes.len() - 1
+    }
+
+    fn detach(&mut self, id: usize) {
+        if let Some(parent) = self.nodes[id].parent.take() {
+            self.nodes[parent].children.retain(|child| *child != id);
+        }
+    }
+
+    /// Inserts the child in the parent at `index`, merging adjacent texts.
+    fn insert(&mut self, parent: usize, index: usize, child: NodeOrText<usize>) {
+        let id = match child {
+            NodeOrText::AppendNode(id) => id,
+            NodeOrText::AppendText(text) => {
+                let prev = index.checked_sub(1).map(|i| self.nodes[parent].children[i]);
+                if let Some(WebData::Text(contents)) = prev.map(|id| &mut self.nodes[id].data) {
+                    contents.push_tendril(&text);
+                    return;
+                }
+                self.create(WebData::Text(text))
+            }
+        };
+        self.detach(id);
+        self.nodes[id].parent = Some(parent);
+        let index = index.min(self.nodes[parent].children.len());
+        self.nodes[parent].children.insert(index, id);
+    }
+}
+
+impl TreeSink for WebSink {
+    type Handle = usize;
+    type Output = Self;
+
+    fn finish(self) -> Self {
+        self
+    }
+
+    fn parse_error(&mut self, _msg: Cow<'static, str>) {}
+
+    fn get_document(&mut self) -> usize {
+        0
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a usize) -> ExpandedName<'a> {
+        match self.nodes[*target].data {
+            WebData::Element(ref name, _) => name.expanded(),
+            _ => panic!("not an element"),
+        }
+    }
+
+    fn create_element(&mut self, name: QualName, attrs: Vec<Attribute>, _: ElementFlags) -> usize {
+        self.create(WebData::Element(name, attrs))
+    }
+
+    fn create_comment(&mut self, _text: StrTendril) -> usize {
+        self.create(WebData::Other)
+    }
+
+    fn create_pi(&mut self, _target: StrTendril, _data: StrTendril) -> usize {
+        self.create(WebData::Other)
+    }
+
+    fn append(&mut self, parent: &usize, child: NodeOrText<usize>) {
+        let index = self.nodes[*parent].children.len();
+        self.insert(*parent, index, child);
+    }
+
+    fn append_based_on_parent_node(
+        &mut self,
+        element: &usize,
+        prev_element: &usize,
+        child: NodeOrText<usize>,
+    ) {
+        match self.nodes[*element].parent {
+            Some(_) => self.append_before_sibling(element, child),
+            None => self.append(prev_element, child),
+        }
+    }
+
+    fn append_doctype_to_document(&mut self, _: StrTendril, _: StrTendril, _: StrTendril) {}
+
+    fn get_template_contents(&mut self, _target: &usize) -> usize {
+        // The template contents are dropped, like templates.
+        self.create(WebData::Other)
+    }
+
+    fn same_node(&self, x: &usize, y: &usize) -> bool {
+        x == y
+    }
+
+    fn set_quirks_mode(&mut self, _mode: QuirksMode) {}
+
+    fn append_before_sibling(&mut self, sibling: &usize, child: NodeOrText<usize>) {
+        let parent = match self.nodes[*sibling].parent {
+            Some(parent) => parent,
+            None => return,
+        };
+        // Detached first, so the index of the sibling does not change.
+        if let NodeOrText::AppendNode(id) = child {
+            self.detach(id);
+        }
+        let index = self.nodes[parent]
+            .children
+            .iter()
+            .position(|child| child == sibling)
+            .unwrap_or_default();
+        self.insert(parent, index, child);
+    }
+
+    fn add_attrs_if_missing(&mut self, target: &usize, attrs: Vec<Attribute>) {
+        if let WebData::Element(_, ref mut existing) = self.nodes[*target].data {
+            for attr in attrs {
+                if !existing.iter().any(|e| e.name == attr.name) {
+                    existing.push(attr);
+                }
+            }
+        }
+    }
+
+    fn remove_from_parent(&mut self, target: &usize) {
+        self.detach(*target);
+    }
+
+    fn reparent_children(&mut self, node: &usize, new_parent: &usize) {
+        for child in std::mem::take(&mut self.nodes[*node].children) {
+            self.nodes[child].parent = None;
+            self.append(new_parent, NodeOrText::AppendNode(child));
+        }
+    }
+}
+
+/// Walks the DOM of a [`WebSink`] and builds the Slate value.
+struct WebImporter<'a> {
+    sink: &'a WebSink,
+    builder: SlateBuilder,
+}
+
+impl<'a> WebImporter<'a> {
+    fn children(&mut self, id: usize) {
+        for &child in &self.sink.nodes[id].children {
+            self.node(child);
+        }
+    }
+
+    fn node(&mut self, id: usize) {
+        let (name, attrs) = match self.sink.nodes[id].data {
+            WebData::Element(ref name, ref attrs) => (&name.local[..], attrs),
+            WebData::Text(ref text) => return self.text(text),
+            WebData::Document | WebData::Other => return,
+        };
+        let attr = |key: &str| {
+            attrs
+                .iter()
+                .find(|attr| &attr.name.local[..] == key)
+                .map(|attr| attr.value.to_string())
+        };
+
+        let mark = match name {
+            "strong" | "b" => Some("bold"),
+            "em" | "i" => Some("italic"),
+            "u" | "ins" => Some("underline"),
+            "s" | "strike" | "del" => Some("strikethrough"),
+            "code" | "kbd" | "samp" | "tt" => Some("code"),
+            "sup" => Some("superscript"),
+            "sub" => Some("subscript"),
+            _ => None,
+        };
+        if let Some(mark) = mark {
+            self.builder.marks.push(mark);
+            self.children(id);
+            self.builder.marks.pop();
+            return;
+        }
+
+        match name {
+            _ if DROPPED.contains(&name) => {}
+            // Blocks in a link or a heading are flattened, Slate allows no blocks
+            // among inline content.
+            _ if self.builder.in_inline_container()
+                && !self.builder.is_implicit()
+                && (BLOCKS.contains(&name) || BLOCK_WRAPPERS.contains(&name)) =>
+            {
+                self.children(id)
+            }
+            _ if BLOCK_WRAPPERS.contains(&name) => {
+                self.close_implicit();
+                self.children(id);
+                self.close_implicit();
+            }
+            "br" => self.builder.text("\n"),
+            "a" => match attr("href") {
+                Some(href) => {
+                    self.builder.ensure_inline_container();
+                    self.builder
+                        .push_element("a", string_map("url", href), false);
+                    self.children(id);
+                    self.builder.close();
+                }
+                None => self.children(id),
+            },
+            "img" => {
+                self.builder.ensure_inline_container();
+                let mut element = string_map("url", attr("src").unwrap_or_default());
+                if let Some(alt) = attr("alt") {
+                    element.insert("alt".to_string(), Value::String(alt));
+                }
+                self.builder.push_element("img", element, false);
+                self.builder.close();
+            }
+            "hr" => {
+                self.close_implicit();
+                self.builder.push_element("hr", Map::new(), false);
+                self.builder.close();
+            }
+            "pre" => self.code_block(id, attr("class")),
+            "p" if self.builder.top_is("li") => self.block(id, "lic", Map::new()),
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "ul" | "li"
+            | "table" | "tr" => self.block(id, name, Map::new()),
+            "ol" => {
+                let mut element = Map::new();
+                if let Some(start) = attr("start").and_then(|s| s.trim().parse::<u64>().ok()) {
+                    if start != 1 {
+                        element.insert("start".to_string(), start.into());
+                    }
+                }
+                self.block(id, "ol", element)
+            }
+            // The rows of table sections are the rows of the table.
+            "thead" | "tbody" | "tfoot" => self.children(id),
+            "td" | "th" => {
+                self.close_implicit();
+                self.builder.push_element(name, Map::new(), false);
+                self.children(id);
+                self.close_implicit();
+                // The cell content goes in a paragraph, like in Plate.
+                if self.builder.is_empty() {
+                    self.builder.push_element("p", Map::new(), false);
+                    self.builder.close();
+                }
+                self.builder.close();
+            }
+            _ => self.children(id),
+        }
+    }
+
+    fn block(&mut self, id: usize, name: &str, element: Map<String, Value>) {
+        self.close_implicit();
+        self.builder.push_element(name, element, false);
+        self.children(id);
+        self.close_implicit();
+        self.builder.trim_end();
+        match name {
+            "p" => self.builder.close_paragraph(),
+            _ => self.builder.close(),
+        }
+    }
+
+    /// Ends the inline content that is not in a paragraph, at a block boundary.
+    fn close_implicit(&mut self) {
+        self.builder.trim_end();
+        self.builder.close_implicit();
+    }
+
+    fn code_block(&mut self, id: usize, class: Option<String>) {
+        let mut code = String::new();
+        self.raw_text(id, &mut code);
+        // The language is in the class of the `code` element, or of `pre`.
+        let code_class = self.sink.nodes[id].children.iter().find_map(|&child| {
+            match self.sink.nodes[child].data {
+                WebData::Element(ref name, ref attrs) if &name.local[..] == "code" => attrs
+                    .iter()
+                    .find(|attr| &attr.name.local[..] == "class")
+                    .map(|attr| attr.value.to_string()),
+                _ => None,
+            }
+        });
+        let lang = code_class
+            .or(class)
+            .unwrap_or_default()
+            .split_whitespace()
+            .find_map(|class| class.strip_prefix("language-").map(str::to_string));
+
+        self.close_implicit();
+        let element = match lang {
+            Some(lang) => string_map("lang", lang),
+            None => Map::new(),
+        };
+        self.builder.push_element("code_block", element, false);
+        let code = code.strip_suffix('\n').unwrap_or(&code);
+        for line in code.split('\n') {
+            self.builder.push_element("code_line", Map::new(), false);
+            self.builder.push_text(line);
+            self.builder.close();
+        }
+        self.builder.close();
+    }
+
+    fn raw_text(&self, id: usize, out: &mut String) {
+        for &child in &self.sink.nodes[id].children {
+            match self.sink.nodes[child].data {
+                WebData::Text(ref text) => out.push_str(text),
+                WebData::Element(ref name, _) if &name.local[..] == "br" => out.push('\n'),
+                WebData::Element(..) => self.raw_text(child, out),
+                _ => {}
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let mut collapsed = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c.is_ascii_whitespace() {
+                true if collapsed.ends_with(' ') => {}
+                true => collapsed.push(' '),
+                false => collapsed.push(c),
+            }
+        }
+        // The whitespace between blocks is not content.
+        if collapsed.trim().is_empty() && !self.builder.in_inline_container() {
+            return;
+        }
+        let text = match self.builder.after_whitespace() {
+            true => collapsed.trim_start(),
+            false => &collapsed[..],
+        };
+        self.builder.text(text);
+    }
+}
+
+fn string_map(key: &str, value: String) -> Map<String, Value> {
+    let mut map = Map::new();
+    map.insert(key.to_string(), Value::String(value));
+    map
+}
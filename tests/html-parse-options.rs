@@ -0,0 +1,144 @@
+use dom_query::{Document, DocumentTreeSink, NodeData, Operation, ParseOptions, Path};
+use markup5ever::interface::{NodeOrText, TreeSink};
+use serde_json::json;
+
+const HTML: &str = "<!DOCTYPE html><p>Hello <!-- note -->world</p><ul><li><!-- empty --></li></ul>";
+
+fn keep_all() -> ParseOptions {
+    ParseOptions::new()
+        .keep_comments(true)
+        .keep_doctype(true)
+        .keep_processing_instructions(true)
+}
+
+#[test]
+fn test_drops_comments_by_default() {
+    let doc = Document::from_slate_html(HTML);
+    assert_eq!(
+        &*doc.html(),
+        "<p><text>Hello world</text></p><ul><li></li></ul>"
+    );
+    assert!(!doc.root().outer_html().contains("DOCTYPE"));
+
+    let doc = Document::from_slate_html_with(HTML, &ParseOptions::new());
+    assert_eq!(
+        &*doc.html(),
+        "<p><text>Hello world</text></p><ul><li></li></ul>"
+    );
+}
+
+#[test]
+fn test_keeps_comments() {
+    let doc = Document::from_slate_html_with(HTML, &keep_all());
+    assert_eq!(
+        &*doc.html(),
+        "<p><text>Hello </text><!-- note --><text>world</text></p><ul><li><!-- empty --></li></ul>"
+    );
+    assert!(doc.root().outer_html().starts_with("<!DOCTYPE html><html>"));
+
+    let comment = doc.select("p").nodes()[0].children()[1].clone();
+    assert!(comment.is_comment());
+    assert!(matches!(
+        comment.query(|node| node.data.clone()),
+        Some(NodeData::Comment { contents }) if &*contents == " note "
+    ));
+    let doctype = doc.root().first_child().unwrap();
+    assert!(matches!(
+        doctype.query(|node| node.data.clone()),
+        Some(NodeData::Doctype { name, .. }) if &*name == "html"
+    ));
+}
+
+#[test]
+fn test_kept_comments_are_not_content() {
+    let doc = Document::from_slate_html_with(HTML, &keep_all());
+
+    assert_eq!(&*doc.select("body").text(), "Hello world");
+    assert_eq!(doc.select("p *").length(), 2);
+    assert_eq!(doc.select("li:empty").length(), 1);
+    assert_eq!(
+        doc.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "Hello "}, {"text": "world"}]},
+            {"type": "ul", "children": [{"type": "li", "children": []}]}
+        ])
+    );
+}
+
+#[test]
+fn test_kept_comments_have_no_path() {
+    let doc = Document::from_slate_html_with("<p><!--c--><text>a</text></p>", &keep_all());
+    assert_eq!(
+        doc.to_slate_value(),
+        json!([{"type": "p", "children": [{"text": "a"}]}])
+    );
+    let p = doc.select("p").nodes()[0].clone();
+    assert_eq!(p.children()[0].path(), None);
+    assert_eq!(p.children()[1].path(), Some(Path::from(&[0, 0][..])));
+    assert_eq!(
+        doc.node_at_path(&Path::from(&[0, 0][..])).unwrap().id,
+        p.children()[1].id
+    );
+
+    let op = |value| serde_json::from_value::<Operation>(value).unwrap();
+    doc.apply(&op(
+        json!({"type": "insert_text", "path": [0, 0], "offset": 1, "text": "b"}),
+    ))
+    .unwrap();
+    doc.apply(&op(
+        json!({"type": "insert_node", "path": [0, 0], "node": {"text": "x"}}),
+    ))
+    .unwrap();
+    doc.apply(&op(
+        json!({"type": "split_node", "path": [0], "position": 1, "properties": {"type": "p"}}),
+    ))
+    .unwrap();
+    assert_eq!(
+        doc.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "x"}]},
+            {"type": "p", "children": [{"text": "ab"}]}
+        ])
+    );
+    assert_eq!(
+        &*doc.html(),
+        "<p><!--c--><text>x</text></p><p><text>ab</text></p>"
+    );
+}
+
+#[test]
+fn test_doctype_ids_round_trip() {
+    let options = ParseOptions::new().keep_doctype(true);
+    for doctype in [
+        "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\" \"http://www.w3.org/TR/html4/strict.dtd\">",
+        "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\">",
+        "<!DOCTYPE html SYSTEM \"about:legacy-compat\">",
+        "<!DOCTYPE html>",
+    ] {
+        let html = format!("{}<p><text>a</text></p>", doctype);
+        let doc = Document::from_slate_html_with(&html, &options);
+        let outer = doc.root().outer_html();
+        assert!(outer.starts_with(&format!("{}<html>", doctype)), "{}", outer);
+        let doc = Document::from_slate_html_with(&outer, &options);
+        assert_eq!(doc.root().outer_html(), outer);
+    }
+}
+
+#[test]
+fn test_keeps_processing_instructions() {
+    let mut sink = DocumentTreeSink::new(keep_all());
+    let document = sink.get_document();
+    let pi = sink.create_pi("xml-stylesheet".into(), "href=\"a.css\"".into());
+    sink.append(&document, NodeOrText::AppendNode(pi));
+    let doc = sink.finish();
+    assert_eq!(
+        &*doc.root().outer_html(),
+        "<?xml-stylesheet href=\"a.css\">"
+    );
+
+    let mut sink = DocumentTreeSink::new(ParseOptions::new());
+    let document = sink.get_document();
+    let pi = sink.create_pi("xml-stylesheet".into(), "href=\"a.css\"".into());
+    sink.append(&document, NodeOrText::AppendNode(pi));
+    assert_eq!(&*sink.finish().root().outer_html(), "");
+}
@@ -0,0 +1,158 @@
+use dom_query::{Document, ParseOptions};
+use serde_json::json;
+
+const HTML: &str = r#"<codeBlock textAlign="left" data-Lang='rust'><text fontSize="2">a</text></codeBlock><p colSpan=2><text>b</text></p>"#;
+
+fn parse(html: &str) -> Document {
+    Document::from_slate_html_with(html, &ParseOptions::new().preserve_case(true))
+}
+
+#[test]
+fn test_preserves_names() {
+    let doc = parse(HTML);
+    assert_eq!(
+        &*doc.html(),
+        r#"<codeBlock textAlign="left" data-Lang="rust"><text fontSize="2">a</text></codeBlock><p colSpan="2"><text>b</text></p>"#
+    );
+    assert_eq!(
+        doc.to_slate_value(),
+        json!([
+            {"type": "codeBlock", "textAlign": "left", "data-Lang": "rust", "children": [
+                {"text": "a", "fontSize": 2}
+            ]},
+            {"type": "p", "colSpan": 2, "children": [{"text": "b"}]}
+        ])
+    );
+}
+
+#[test]
+fn test_lowercases_by_default() {
+    let doc = Document::from_slate_html(HTML);
+    assert_eq!(
+        &*doc.html(),
+        r#"<codeblock textalign="left" data-lang="rust"><text fontsize="2">a</text></codeblock><p colspan="2"><text>b</text></p>"#
+    );
+}
+
+#[test]
+fn test_selectors_match_case() {
+    let doc = parse(HTML);
+    for (selector, length) in [
+        ("codeBlock", 1),
+        ("codeblock", 0),
+        ("[textAlign]", 1),
+        ("[textalign=left]", 0),
+        ("codeBlock[data-Lang=\"rust\"]", 1),
+        ("text[fontSize]", 1),
+        ("p[colspan='2']", 0),
+    ] {
+        assert_eq!(doc.select(selector).length(), length, "{}", selector);
+    }
+
+    // HTML lowercases the names and the selectors.
+    let doc = Document::from_slate_html(HTML);
+    assert_eq!(doc.select("codeBlock[textAlign=left]").length(), 1);
+    assert_eq!(doc.select("codeblock[textalign=left]").length(), 1);
+
+    let doc = Document::from_slate_value(&json!([
+        {"type": "codeBlock", "textAlign": "left", "children": [{"text": "a"}]}
+    ]))
+    .unwrap();
+    assert_eq!(doc.select("codeBlock[textAlign=left]").length(), 1);
+    assert_eq!(doc.select("[textalign]").length(), 0);
+    assert_eq!(doc.select("codeblock").length(), 0);
+}
+
+#[test]
+fn test_round_trips_slate_value() {
+    let value = json!([
+        {"type": "numberedList", "listStyleType": "decimal", "children": [
+            {"type": "listItem", "children": [{"text": "one", "isBold": true}]}
+        ]},
+        {"type": "table", "children": [
+            {"type": "tableRow", "children": [
+                {"type": "tableCell", "colSpan": 2, "children": [{"text": "cell"}]}
+            ]}
+        ]}
+    ]);
+    let html = Document::from_slate_value(&value).unwrap().html();
+    let options = ParseOptions::new().preserve_case(true).xml(true);
+    assert_eq!(
+        Document::from_slate_html_with(&html, &options).to_slate_value(),
+        value
+    );
+    let doc = Document::from_slate_html_with(&html, &ParseOptions::new().xml(true));
+    assert_eq!(doc.to_slate_value(), value);
+}
+
+#[test]
+fn test_preserves_names_after_renamed_elements() {
+    let doc = parse(r#"<p><image srcSet="a"></image></p><blockQuote><text>q</text></blockQuote>"#);
+    assert_eq!(doc.select("img[srcSet]").length(), 1);
+    assert_eq!(
+        doc.select("blockQuote").nodes()[0].node_name().unwrap(),
+        "blockQuote".into()
+    );
+}
+
+#[test]
+fn test_with_source_spans() {
+    let options = ParseOptions::new().preserve_case(true).source_spans(true);
+    let doc = Document::from_slate_html_with(HTML, &options);
+    let span = doc.select("p").nodes()[0].source_span().unwrap();
+    assert_eq!(
+        &HTML[span.start.offset..span.end.offset],
+        "<p colSpan=2><text>b</text></p>"
+    );
+    assert!(parse(HTML).select("p").nodes()[0].source_span().is_none());
+}
+
+#[test]
+fn test_preserves_names_of_misnested_elements() {
+    let doc = parse(r#"<p><b>x<p>y<B textAlign="l">z</B>"#);
+    let names = doc
+        .select("b, B")
+        .nodes()
+        .iter()
+        .map(|node| node.node_name().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["b", "b", "B"]);
+    assert_eq!(doc.select("B[textAlign]").length(), 1);
+    assert_eq!(doc.select("b[textAlign]").length(), 0);
+}
+
+#[test]
+fn test_preserves_names_of_inserted_html() {
+    let doc = parse(HTML);
+    doc.select("p")
+        .set_slate_html(r#"<codeBlock textAlign="l"><text>x</text></codeBlock>"#);
+    assert_eq!(doc.select("p codeBlock[textAlign]").length(), 1);
+    assert_eq!(doc.select("codeblock").length(), 0);
+
+    doc.select("text[fontSize]")
+        .replace_with_html(r#"<text fontSize="3">c</text>"#);
+    assert_eq!(doc.select("text[fontSize='3']").length(), 1);
+
+    // HTML lowercases them.
+    let doc = Document::from_slate_html(HTML);
+    doc.select("p")
+        .set_slate_html("<codeBlock><text>x</text></codeBlock>");
+    assert_eq!(
+        &*doc.html(),
+        r#"<codeblock textalign="left" data-lang="rust"><text fontsize="2">a</text></codeblock><p colspan="2"><codeblock><text>x</text></codeblock></p>"#
+    );
+
+    let doc = Document::from_slate_json(r#"[{"type":"p","children":[{"text":"a"}]}]"#).unwrap();
+    doc.select("p")
+        .append_slate_html("<codeBlock><text>x</text></codeBlock>");
+    assert_eq!(doc.select("p codeBlock").length(), 1);
+}
+
+#[test]
+fn test_parses_inserted_html_as_xml() {
+    let options = ParseOptions::new().xml(true);
+    let doc = Document::from_slate_html_with("<blockQuote><text>a</text></blockQuote>", &options);
+    let html = "<p><text>b</text><p><text>c</text></p></p><table><text>d</text></table>";
+    doc.select("blockQuote").set_slate_html(html);
+    assert_eq!(&*doc.html(), format!("<blockQuote>{}</blockQuote>", html));
+}
@@ -0,0 +1,167 @@
+use dom_query::{Document, Node, ParseOptions, SourceSpan};
+
+fn parse(html: &str) -> Document {
+    Document::from_slate_html_with(html, &ParseOptions::new().source_spans(true))
+}
+
+fn source<'h>(html: &'h str, node: &Node) -> &'h str {
+    let span = node.source_span().unwrap();
+    &html[span.start.offset..span.end.offset]
+}
+
+fn lines(span: SourceSpan) -> ((usize, usize), (usize, usize)) {
+    (
+        (span.start.line, span.start.column),
+        (span.end.line, span.end.column),
+    )
+}
+
+#[test]
+fn test_spans_of_elements_and_leaves() {
+    let html = "<h1><text>Title</text></h1>\n<ul>\n  <li><text bold=\"true\">wörld</text><text>!</text></li>\n</ul>";
+    let doc = parse(html);
+
+    let h1 = doc.select("h1").nodes()[0].clone();
+    assert_eq!(source(html, &h1), "<h1><text>Title</text></h1>");
+    assert_eq!(lines(h1.source_span().unwrap()), ((1, 1), (1, 28)));
+
+    let ul = doc.select("ul").nodes()[0].clone();
+    assert_eq!(source(html, &ul), &html[28..]);
+    assert_eq!(lines(ul.source_span().unwrap()), ((2, 1), (4, 6)));
+
+    let li = doc.select("li").nodes()[0].clone();
+    assert_eq!(lines(li.source_span().unwrap()), ((3, 3), (3, 56)));
+
+    let leaves = doc.select("li text").nodes().to_vec();
+    assert_eq!(source(html, &leaves[0]), "<text bold=\"true\">wörld</text>");
+    assert_eq!(lines(leaves[0].source_span().unwrap()), ((3, 7), (3, 37)));
+    assert_eq!(source(html, &leaves[1]), "<text>!</text>");
+    assert_eq!(lines(leaves[1].source_span().unwrap()), ((3, 37), (3, 51)));
+    assert_eq!(leaves[1].source_span().unwrap().start.offset, 70);
+}
+
+#[test]
+fn test_implied_nodes_have_no_span() {
+    let doc = parse("<p><text>a</text></p>");
+    assert!(doc.root().source_span().is_none());
+    assert!(doc.select("html").nodes()[0].source_span().is_none());
+    assert!(doc.select("body").nodes()[0].source_span().is_none());
+    assert!(doc.select("p").nodes()[0].source_span().is_some());
+}
+
+#[test]
+fn test_spans_of_text_without_tags() {
+    let html = "<p>Hello <b>world</b></p>";
+    let doc = parse(html);
+
+    let p = doc.select("p").nodes()[0].clone();
+    let leaf = p.children()[0].clone();
+    assert!(leaf.is_text());
+    assert_eq!(source(html, &leaf), "Hello ");
+    assert_eq!(source(html, &p), html);
+}
+
+#[test]
+fn test_spans_of_void_and_implicitly_closed_elements() {
+    let html = "<p>one<br>two<p>three</p>";
+    let doc = parse(html);
+
+    let br = doc.select("br").nodes()[0].clone();
+    assert_eq!(source(html, &br), "<br>");
+
+    let ps = doc.select("p").nodes().to_vec();
+    assert_eq!(source(html, &ps[0]), "<p>one<br>two");
+    assert_eq!(source(html, &ps[1]), "<p>three</p>");
+}
+
+#[test]
+fn test_spans_of_unclosed_elements() {
+    let html = "<ul><li><text>one</text></ul>\n<blockquote><text>two</text>";
+    let doc = parse(html);
+
+    let li = doc.select("li").nodes()[0].clone();
+    assert_eq!(source(html, &li), "<li><text>one</text>");
+    let ul = doc.select("ul").nodes()[0].clone();
+    assert_eq!(source(html, &ul), "<ul><li><text>one</text></ul>");
+    let blockquote = doc.select("blockquote").nodes()[0].clone();
+    assert_eq!(source(html, &blockquote), "<blockquote><text>two</text>");
+}
+
+#[test]
+fn test_spans_skip_comments_and_raw_text() {
+    let html =
+        "<!-- <p> --><p><text>a</text></p><script>let p = '<p>';</script><p><text>b</text></p>";
+    let doc = parse(html);
+
+    let ps = doc.select("p").nodes().to_vec();
+    assert_eq!(source(html, &ps[0]), "<p><text>a</text></p>");
+    assert_eq!(source(html, &ps[1]), "<p><text>b</text></p>");
+    let script = doc.select("script").nodes()[0].clone();
+    assert_eq!(source(html, &script), "<script>let p = '<p>';</script>");
+}
+
+#[test]
+fn test_no_spans_by_default() {
+    let html = "<p><text>a</text></p>";
+    for doc in [
+        Document::from_slate_html(html),
+        Document::from_slate_html_with(html, &ParseOptions::new()),
+    ] {
+        assert!(doc.select("p").nodes()[0].source_span().is_none());
+        assert!(doc.select("text").nodes()[0].source_span().is_none());
+    }
+}
+
+#[test]
+fn test_spans_in_template_contents() {
+    let html = "<template><p><text>a</text></p></template><p><text>b</text></p>";
+    let doc = parse(html);
+
+    let template = doc.select("template").nodes()[0].clone();
+    assert_eq!(
+        source(html, &template),
+        "<template><p><text>a</text></p></template>"
+    );
+    let p = template.template_contents().unwrap().children()[0].clone();
+    assert_eq!(source(html, &p), "<p><text>a</text></p>");
+    let p = doc.select("body > p").nodes()[0].clone();
+    assert_eq!(source(html, &p), "<p><text>b</text></p>");
+}
+
+#[test]
+fn test_spans_of_misnested_elements() {
+    let html = "<p><b>x<p>y<b id=\"two\">z</b>";
+    let doc = parse(html);
+
+    // The second `p` reopens the first `b`, which has no tag of its own.
+    let bs = doc.select("b").nodes().to_vec();
+    assert_eq!(bs.len(), 3);
+    assert_eq!(source(html, &bs[0]), "<b>x");
+    assert!(bs[1].source_span().is_none());
+    assert_eq!(source(html, &bs[2]), "<b id=\"two\">z</b>");
+    assert_eq!(source(html, &bs[1].children()[0]), "y");
+
+    let ps = doc.select("p").nodes().to_vec();
+    assert_eq!(source(html, &ps[0]), "<p><b>x");
+    assert_eq!(source(html, &ps[1]), "<p>y<b id=\"two\">z</b>");
+
+    let html = "<b>1<p>2</b>3</p>";
+    let doc = parse(html);
+    let bs = doc.select("b").nodes().to_vec();
+    assert_eq!(source(html, &bs[0]), "<b>1");
+    assert!(bs[1].source_span().is_none());
+    let p = doc.select("p").nodes()[0].clone();
+    assert_eq!(source(html, &p), "<p>2</b>3</p>");
+}
+
+#[test]
+fn test_spans_of_implied_elements() {
+    let html = "<table><tr><td><text>a</text></td></tr></table>";
+    let doc = parse(html);
+
+    assert!(doc.select("tbody").nodes()[0].source_span().is_none());
+    let tr = doc.select("tr").nodes()[0].clone();
+    assert_eq!(source(html, &tr), "<tr><td><text>a</text></td></tr>");
+    let table = doc.select("table").nodes()[0].clone();
+    assert_eq!(source(html, &table), html);
+}
@@ -0,0 +1,81 @@
+use dom_query::{Document, Element, NodeData};
+
+const HTML: &str = r#"<template id="row"><li><text>item</text></li></template><ul><li><text>first</text></li></ul>"#;
+
+#[test]
+fn test_template_contents() {
+    let doc = Document::from_slate_html(HTML);
+    let template = doc.select("template").nodes()[0].clone();
+
+    assert!(template.children().is_empty());
+    let contents = template.template_contents().unwrap();
+    assert!(matches!(
+        contents.query(|node| node.data.clone()),
+        Some(NodeData::Fragment)
+    ));
+    assert_eq!(contents.children().len(), 1);
+    assert_eq!(contents.children()[0].node_name().unwrap(), "li".into());
+    assert_eq!(&*contents.children()[0].text(), "item");
+    assert!(contents.parent().is_none());
+    assert!(contents.children()[0].path().is_none());
+
+    assert!(doc.select("ul").nodes()[0].template_contents().is_none());
+}
+
+#[test]
+fn test_template_contents_are_not_content() {
+    let doc = Document::from_slate_html(HTML);
+
+    assert_eq!(doc.select("li").length(), 1);
+    assert_eq!(&*doc.select("template").text(), "");
+    assert_eq!(doc.to_slate_value()[0]["type"], "ul");
+}
+
+#[test]
+fn test_serialize_template() {
+    let doc = Document::from_slate_html(HTML);
+
+    assert_eq!(
+        &*doc.select("template").outer_html(),
+        r#"<template id="row"><li><text>item</text></li></template>"#
+    );
+    assert_eq!(
+        &*doc.select("template").inner_html(),
+        "<li><text>item</text></li>"
+    );
+}
+
+#[test]
+fn test_append_template() {
+    let doc = Document::from_slate_html(HTML);
+    let mut ul = doc.select("ul");
+    ul.append_slate_html(r#"<template><li><text>more</text></li></template>"#);
+
+    assert_eq!(
+        &*doc.html(),
+        r#"<ul><li><text>first</text></li><template><li><text>more</text></li></template></ul>"#
+    );
+    let template = doc.select("ul > template").nodes()[0].clone();
+    assert_eq!(&*template.template_contents().unwrap().text(), "more");
+}
+
+#[test]
+fn test_copied_template_data_has_no_contents() {
+    let doc = Document::from_slate_html(HTML);
+    let template = doc.select("template").nodes()[0].clone();
+    let data = template.query(|node| node.data.clone()).unwrap();
+
+    let ul = doc.select("ul").nodes()[0].clone();
+    ul.append_last_child(data);
+    let copy = ul.children().pop().unwrap();
+    assert!(copy.template_contents().is_none());
+    assert_eq!(&*copy.outer_html(), r#"<template id="row"></template>"#);
+    assert_eq!(&*template.template_contents().unwrap().text(), "item");
+
+    let element = Element {
+        name: "template".into(),
+        attrs: vec![],
+    };
+    ul.append_last_child(NodeData::Element(element));
+    assert!(ul.children().pop().unwrap().template_contents().is_none());
+}
@@ -0,0 +1,127 @@
+use dom_query::{Document, ParseOptions, Path};
+use serde_json::json;
+
+fn parse(html: &str) -> Document {
+    Document::from_slate_html_with(html, &ParseOptions::new().xml(true))
+}
+
+#[test]
+fn test_keeps_nesting() {
+    let html =
+        "<p><text>a</text><p><text>b</text></p></p><a href=\"/\"><h1><text>c</text></h1></a>";
+    let doc = parse(html);
+    assert_eq!(&*doc.html(), html);
+    assert_eq!(doc.select("p p").length(), 1);
+    assert!(doc.errors.is_empty());
+
+    let doc = Document::from_slate_html(html);
+    assert_eq!(doc.select("p p").length(), 0);
+}
+
+#[test]
+fn test_no_foster_parenting() {
+    let html = "<table><text>a</text><p><text>b</text></p></table><li><text>c</text></li>";
+    let doc = parse(html);
+    assert_eq!(&*doc.html(), html);
+    assert_eq!(
+        doc.select("table").nodes()[0].children()[1].path(),
+        Some(Path::from(&[0, 1][..]))
+    );
+}
+
+#[test]
+fn test_content_in_body() {
+    let doc = parse("<p><text>a</text></p><p><text>b</text></p>");
+    assert_eq!(
+        &*doc.root().outer_html(),
+        "<html><head></head><body><p><text>a</text></p><p><text>b</text></p></body></html>"
+    );
+    assert_eq!(&*doc.select("body").text(), "ab");
+
+    let doc = parse("");
+    assert_eq!(&*doc.html(), "");
+}
+
+#[test]
+fn test_prolog() {
+    let html = "<?xml version=\"1.0\"?>\n<!DOCTYPE slate>\n<!-- saved -->\n<p><text>a</text></p>";
+    let options = ParseOptions::new()
+        .xml(true)
+        .keep_doctype(true)
+        .keep_comments(true)
+        .keep_processing_instructions(true);
+    let doc = Document::from_slate_html_with(html, &options);
+    assert_eq!(&*doc.html(), "<p><text>a</text></p>");
+    assert_eq!(
+        &*doc.root().outer_html(),
+        "<?xml version=\"1.0\"?><!DOCTYPE slate><!-- saved --><html><head></head><body><p><text>a</text></p></body></html>"
+    );
+
+    let doc = parse(html);
+    assert_eq!(
+        &*doc.root().outer_html(),
+        "<html><head></head><body><p><text>a</text></p></body></html>"
+    );
+}
+
+#[test]
+fn test_round_trips_slate_value() {
+    let value = json!([
+        {"type": "p", "children": [
+            {"text": "a"},
+            {"type": "p", "children": [{"text": "b", "bold": true}]}
+        ]},
+        {"type": "table", "children": [
+            {"text": "c"},
+            {"type": "div", "children": [{"text": "d"}]}
+        ]},
+        {"type": "li", "children": [{"text": "e"}]},
+        {"type": "a", "url": "/", "children": [
+            {"type": "h1", "children": [{"text": "f"}]}
+        ]}
+    ]);
+    let html = Document::from_slate_value(&value).unwrap().html();
+    assert_eq!(parse(&html).to_slate_value(), value);
+}
+
+#[test]
+fn test_round_trips_void_elements_and_processing_instructions() {
+    let value = json!([
+        {"type": "hr", "children": [{"text": ""}]},
+        {"type": "p", "children": [
+            {"text": "a"},
+            {"type": "img", "src": "a.png", "children": [{"text": ""}]},
+            {"text": "b"}
+        ]}
+    ]);
+    let html = "<hr><text></text></hr><p><text>a</text><?mark x?>\
+        <img src=\"a.png\"><text></text></img><text>b</text></p>";
+    let options = ParseOptions::new()
+        .xml(true)
+        .keep_processing_instructions(true);
+    let doc = Document::from_slate_html_with(html, &options);
+    assert_eq!(&*doc.html(), html);
+    assert_eq!(doc.to_slate_value(), value);
+
+    let doc = Document::from_slate_html_with(&doc.html(), &options);
+    assert_eq!(&*doc.html(), html);
+    assert_eq!(doc.to_slate_value(), value);
+    assert_eq!(parse(&doc.html()).to_slate_value(), value);
+}
+
+#[test]
+fn test_source_spans() {
+    let html = "<!DOCTYPE slate>\n<p><text>a</text>\n<p><text>b</text></p></p>";
+    let options = ParseOptions::new().xml(true).source_spans(true);
+    let doc = Document::from_slate_html_with(html, &options);
+
+    let span = doc.select("p p").nodes()[0].source_span().unwrap();
+    assert_eq!(
+        &html[span.start.offset..span.end.offset],
+        "<p><text>b</text></p>"
+    );
+    assert_eq!((span.start.line, span.start.column), (3, 1));
+    let span = doc.select("body > p").nodes()[0].source_span().unwrap();
+    assert_eq!(&html[span.start.offset..span.end.offset], &html[17..]);
+    assert!(doc.select("body").nodes()[0].source_span().is_none());
+}
@@ -0,0 +1,197 @@
+use dom_query::{Change, Document, Path};
+use serde_json::{json, Value};
+
+fn doc(value: Value) -> Document {
+    Document::from_slate_value(&value).unwrap()
+}
+
+fn path(indexes: &[usize]) -> Path {
+    Path::new(indexes.to_vec())
+}
+
+#[test]
+fn test_diff_identical() {
+    let value = json!([
+        {"type": "p", "children": [{"text": "Hello "}, {"text": "world", "bold": true}]},
+        {"type": "ul", "children": [{"type": "li", "children": [{"text": "one"}]}]}
+    ]);
+    assert!(doc(value.clone()).diff(&doc(value)).is_empty());
+}
+
+#[test]
+fn test_diff_inserted_and_removed() {
+    let old = doc(json!([
+        {"type": "p", "children": [{"text": "a"}]},
+        {"type": "hr", "children": [{"text": ""}]},
+        {"type": "p", "children": [{"text": "b"}]}
+    ]));
+    let new = doc(json!([
+        {"type": "p", "children": [{"text": "a"}]},
+        {"type": "p", "children": [{"text": "b"}]},
+        {"type": "blockquote", "children": [{"text": "c"}]}
+    ]));
+
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            Change::Removed {
+                path: path(&[1]),
+                node: json!({"type": "hr", "children": [{"text": ""}]}),
+            },
+            Change::Inserted {
+                path: path(&[2]),
+                node: json!({"type": "blockquote", "children": [{"text": "c"}]}),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_diff_moved() {
+    let old = doc(json!([
+        {"type": "p", "children": [{"text": "a"}]},
+        {"type": "p", "children": [{"text": "b"}]},
+        {"type": "blockquote", "children": [
+            {"type": "p", "children": [{"text": "c"}]}
+        ]}
+    ]));
+    let new = doc(json!([
+        {"type": "p", "children": [{"text": "b"}]},
+        {"type": "blockquote", "children": [
+            {"type": "p", "children": [{"text": "c"}]},
+            {"type": "p", "children": [{"text": "a"}]}
+        ]}
+    ]));
+
+    assert_eq!(
+        old.diff(&new),
+        vec![Change::Moved {
+            from: path(&[0]),
+            to: path(&[1, 1]),
+        }]
+    );
+}
+
+#[test]
+fn test_diff_attributes() {
+    let old = doc(json!([
+        {"type": "a", "url": "/old", "title": "t", "children": [{"text": "link", "bold": true}]}
+    ]));
+    let new = doc(json!([
+        {"type": "a", "url": "/new", "target": "_blank", "children": [{"text": "link", "italic": true}]}
+    ]));
+
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            Change::AttributeChanged {
+                path: path(&[0]),
+                key: "title".to_string(),
+                old: Some(json!("t")),
+                new: None,
+            },
+            Change::AttributeChanged {
+                path: path(&[0]),
+                key: "url".to_string(),
+                old: Some(json!("/old")),
+                new: Some(json!("/new")),
+            },
+            Change::AttributeChanged {
+                path: path(&[0]),
+                key: "target".to_string(),
+                old: None,
+                new: Some(json!("_blank")),
+            },
+            Change::AttributeChanged {
+                path: path(&[0, 0]),
+                key: "bold".to_string(),
+                old: Some(json!(true)),
+                new: None,
+            },
+            Change::AttributeChanged {
+                path: path(&[0, 0]),
+                key: "italic".to_string(),
+                old: None,
+                new: Some(json!(true)),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_diff_text() {
+    let old = doc(json!([
+        {"type": "p", "children": [{"text": "héllo wörld"}]},
+        {"type": "p", "children": [{"text": "same"}]}
+    ]));
+    let new = doc(json!([
+        {"type": "p", "children": [{"text": "héllo dear wörld!"}]},
+        {"type": "p", "children": [{"text": "same"}]}
+    ]));
+
+    assert_eq!(
+        old.diff(&new),
+        vec![Change::TextChanged {
+            path: path(&[0, 0]),
+            range: 6..11,
+            text: "dear wörld!".to_string(),
+        }]
+    );
+
+    let old = doc(json!([{"type": "p", "children": [{"text": "abcdef"}]}]));
+    let new = doc(json!([{"type": "p", "children": [{"text": "abXYef"}]}]));
+    assert_eq!(
+        old.diff(&new),
+        vec![Change::TextChanged {
+            path: path(&[0, 0]),
+            range: 2..4,
+            text: "XY".to_string(),
+        }]
+    );
+
+    let old = doc(json!([{"type": "p", "children": [{"text": "😀ab"}]}]));
+    let new = doc(json!([{"type": "p", "children": [{"text": "😀Xb"}]}]));
+    assert_eq!(
+        old.diff(&new),
+        vec![Change::TextChanged {
+            path: path(&[0, 0]),
+            range: 2..3,
+            text: "X".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn test_diff_paths_follow_each_side() {
+    let old = doc(json!([
+        {"type": "h1", "children": [{"text": "Title"}]},
+        {"type": "p", "children": [{"text": "old"}]},
+        {"type": "p", "children": [{"text": "text"}]}
+    ]));
+    let new = doc(json!([
+        {"type": "p", "children": [{"text": "intro"}]},
+        {"type": "p", "children": [{"text": "text!"}]},
+        {"type": "h1", "children": [{"text": "Title"}]}
+    ]));
+
+    let changes = old.diff(&new);
+    assert_eq!(
+        changes,
+        vec![
+            Change::Moved {
+                from: path(&[0]),
+                to: path(&[2]),
+            },
+            Change::TextChanged {
+                path: path(&[0, 0]),
+                range: 0..3,
+                text: "intro".to_string(),
+            },
+            Change::TextChanged {
+                path: path(&[1, 0]),
+                range: 4..4,
+                text: "!".to_string(),
+            },
+        ]
+    );
+}
@@ -0,0 +1,204 @@
+use dom_query::{Document, NodeData, Operation, Point, Range};
+use serde_json::{json, Value};
+
+fn value() -> Value {
+    json!([
+        {"type": "p", "children": [{"text": "Hello "}, {"text": "world", "bold": true}]},
+        {"type": "blockquote", "children": [
+            {"type": "p", "children": [{"text": "quoted"}]}
+        ]},
+        {"type": "p", "id": "last", "children": [{"text": "last"}]}
+    ])
+}
+
+fn op(value: Value) -> Operation {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_inverse() {
+    let pairs = [
+        (
+            json!({"type": "insert_text", "path": [0, 0], "offset": 1, "text": "ab"}),
+            json!({"type": "remove_text", "path": [0, 0], "offset": 1, "text": "ab"}),
+        ),
+        (
+            json!({"type": "insert_node", "path": [1], "node": {"text": "a"}}),
+            json!({"type": "remove_node", "path": [1], "node": {"text": "a"}}),
+        ),
+        (
+            json!({"type": "split_node", "path": [0, 1], "position": 2, "properties": {"bold": true}}),
+            json!({"type": "merge_node", "path": [0, 2], "position": 2, "properties": {"bold": true}}),
+        ),
+        (
+            json!({"type": "set_node", "path": [0], "properties": {"type": "p"}, "newProperties": {"type": "h1"}}),
+            json!({"type": "set_node", "path": [0], "properties": {"type": "h1"}, "newProperties": {"type": "p"}}),
+        ),
+        (
+            json!({"type": "move_node", "path": [0], "newPath": [2]}),
+            json!({"type": "move_node", "path": [2], "newPath": [0]}),
+        ),
+        (
+            json!({"type": "move_node", "path": [0], "newPath": [2, 1]}),
+            json!({"type": "move_node", "path": [1, 1], "newPath": [0]}),
+        ),
+    ];
+    for (a, b) in pairs {
+        assert_eq!(op(a.clone()).inverse(), op(b.clone()));
+        assert_eq!(op(b).inverse(), op(a));
+    }
+}
+
+#[test]
+fn test_undo_redo_operations() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.enable_history();
+
+    let ops = [
+        json!({"type": "insert_text", "path": [0, 0], "offset": 5, "text": ","}),
+        json!({"type": "split_node", "path": [0, 1], "position": 2, "properties": {"bold": true}}),
+        json!({"type": "split_node", "path": [0], "position": 2, "properties": {"type": "p"}}),
+        json!({"type": "move_node", "path": [0], "newPath": [2, 1]}),
+        json!({"type": "set_node", "path": [2], "properties": {}, "newProperties": {"type": "h1", "id": null}}),
+        // Operations from the outside may not carry the removed node and text.
+        json!({"type": "remove_node", "path": [0], "node": {}}),
+        json!({"type": "remove_text", "path": [0, 0, 0], "offset": 0, "text": "xx"}),
+    ];
+    let mut values = vec![document.to_slate_value()];
+    for value in ops {
+        document.apply(&op(value)).unwrap();
+        document.checkpoint();
+        values.push(document.to_slate_value());
+    }
+
+    for expected in values.iter().rev().skip(1) {
+        assert!(document.undo().unwrap());
+        assert_eq!(&document.to_slate_value(), expected);
+    }
+    assert!(!document.can_undo());
+    assert!(!document.undo().unwrap());
+
+    for expected in values.iter().skip(1) {
+        assert!(document.redo().unwrap());
+        assert_eq!(&document.to_slate_value(), expected);
+    }
+    assert!(!document.redo().unwrap());
+}
+
+#[test]
+fn test_undo_mutations() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.enable_history();
+
+    document.select("#last").set_attr("align", "center");
+    document.select("p").add_class("para");
+    document.select("blockquote").remove();
+    document.select("#last").append_slate_html("<em>new</em>");
+    let changed = document.to_slate_value();
+
+    assert!(document.undo().unwrap());
+    assert_eq!(document.to_slate_value(), value());
+    assert!(document.can_redo());
+    assert!(document.redo().unwrap());
+    assert_eq!(document.to_slate_value(), changed);
+}
+
+#[test]
+fn test_batches() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.enable_history();
+
+    document.batch(|| {
+        document.select("#last").set_attr("a", 1);
+        document.select("#last").set_attr("b", 2);
+    });
+    let first = document.to_slate_value();
+    document.select("#last").set_attr("c", 3);
+
+    assert!(document.undo().unwrap());
+    assert_eq!(document.to_slate_value(), first);
+    assert!(document.undo().unwrap());
+    assert_eq!(document.to_slate_value(), value());
+
+    // A new change drops the undone batches.
+    assert!(document.redo().unwrap());
+    document.select("#last").set_attr("d", 4);
+    assert!(!document.can_redo());
+
+    document.clear_history();
+    assert!(!document.can_undo());
+    document.disable_history();
+    document.select("#last").set_attr("e", 5);
+    assert!(!document.can_undo());
+}
+
+#[test]
+fn test_failed_undo_and_redo_revert_the_batch() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.enable_history();
+    document.batch(|| {
+        for value in [
+            json!({"type": "set_node", "path": [2], "properties": {}, "newProperties": {"align": "left"}}),
+            json!({"type": "insert_text", "path": [0, 0], "offset": 6, "text": "!"}),
+            json!({"type": "insert_node", "path": [3], "node": {"type": "hr", "children": [{"text": ""}]}}),
+        ] {
+            document.apply(&op(value)).unwrap();
+        }
+    });
+    let changed = document.to_slate_value();
+
+    // A change the history does not know about makes the middle operation fail.
+    let leaf = document.select("p").nodes()[0].children()[0].clone();
+    let set_leaf = |text: &str| {
+        leaf.update(|node| {
+            if let NodeData::Text(ref mut t) = node.data {
+                t.contents = text.into();
+            }
+        });
+    };
+
+    set_leaf("x");
+    let before = document.to_slate_value();
+    assert!(document.undo().is_err());
+    assert_eq!(document.to_slate_value(), before);
+    assert!(document.can_undo());
+    assert!(!document.can_redo());
+    set_leaf("Hello !");
+    assert!(document.undo().unwrap());
+    assert_eq!(document.to_slate_value(), value());
+
+    set_leaf("x");
+    let before = document.to_slate_value();
+    assert!(document.redo().is_err());
+    assert_eq!(document.to_slate_value(), before);
+    assert!(document.can_redo());
+    set_leaf("Hello ");
+    assert!(document.redo().unwrap());
+    assert_eq!(document.to_slate_value(), changed);
+}
+
+#[test]
+fn test_undo_is_recorded() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document
+        .set_selection(Some(Range::collapsed(Point::new(vec![2, 0], 2))))
+        .unwrap();
+    document.enable_history();
+    document.start_recording();
+
+    document
+        .apply(&op(
+            json!({"type": "insert_node", "path": [2], "node": {"type": "hr", "children": []}}),
+        ))
+        .unwrap();
+    document.select("#last").remove();
+    document.undo().unwrap();
+    assert_eq!(document.to_slate_value(), value());
+
+    // Connected editors end up with the same value by replaying the log.
+    let replica = Document::from_slate_value(&value()).unwrap();
+    for op in document.stop_recording() {
+        replica.apply(&op).unwrap();
+    }
+    assert_eq!(replica.to_slate_value(), document.to_slate_value());
+}
@@ -0,0 +1,105 @@
+use dom_query::Document;
+use serde_json::json;
+
+#[test]
+fn test_from_slate_json() {
+    let json = r#"[
+        {"type": "h1", "children": [{"text": "Welcome to slate-query"}]},
+        {"type": "p", "align": "center", "children": [{"text": "slate "}, {"text": "with css", "bold": true}]}
+    ]"#;
+    let document = Document::from_slate_json(json).unwrap();
+
+    assert_eq!(
+        &document.html()[..],
+        r#"<h1><text>Welcome to slate-query</text></h1><p align="center"><text>slate </text><text bold="true">with css</text></p>"#
+    );
+    assert_eq!(document.select("p").attr("align"), Some(json!("center")));
+    assert_eq!(&document.select("text[bold=true]").text()[..], "with css");
+}
+
+#[test]
+fn test_from_slate_value_single_node() {
+    let value = json!({"type": "foo", "num": 1, "list": ["a"], "children": []});
+    let document = Document::from_slate_value(&value).unwrap();
+
+    let foo = document.select("foo");
+    assert_eq!(foo.length(), 1);
+    assert_eq!(foo.attr("num"), Some(json!(1)));
+    assert_eq!(foo.attr("list"), Some(json!(["a"])));
+    assert_eq!(foo.children().length(), 0);
+}
+
+#[test]
+fn test_from_slate_json_matches_html() {
+    let html = r#"<doc_meta><text></text></doc_meta><doc_content><section variation="default"><p><text>hello</text></p></section></doc_content>"#;
+    let json = r#"[
+        {"type": "doc_meta", "children": [{"text": ""}]},
+        {"type": "doc_content", "children": [
+            {"type": "section", "variation": "default", "children": [
+                {"type": "p", "children": [{"text": "hello"}]}
+            ]}
+        ]}
+    ]"#;
+
+    let from_html = Document::from_slate_html(html);
+    let from_json = Document::from_slate_json(json).unwrap();
+    assert_eq!(from_html.html(), from_json.html());
+}
+
+#[test]
+fn test_from_slate_json_errors() {
+    assert!(Document::from_slate_json("not json").is_err());
+    assert!(Document::from_slate_json("1").is_err());
+    assert!(Document::from_slate_json(r#"[{"children": []}]"#).is_err());
+    assert!(Document::from_slate_json(r#"[{"type": "p", "children": {}}]"#).is_err());
+}
+
+#[test]
+fn test_to_slate_json() {
+    let html = r#"
+      <h1><text>Welcome to slate-query</text></h1>
+      <p><text>slate </text><text bold="true">with css</text></p>
+    "#;
+    let document = Document::from_slate_html(html);
+
+    assert_eq!(
+        document.to_slate_json(),
+        r#"[{"children":[{"text":"Welcome to slate-query"}],"type":"h1"},{"children":[{"text":"slate "},{"bold":true,"text":"with css"}],"type":"p"}]"#
+    );
+}
+
+#[test]
+fn test_to_slate_value_wrappers_and_empty_elements() {
+    let html = r#"<doc_meta><text></text></doc_meta><doc_content><foo num="1"></foo></doc_content>"#;
+    let document = Document::from_slate_html(html);
+
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "doc_meta", "children": [{"text": ""}]},
+            {"type": "doc_content", "children": [{"type": "foo", "num": 1, "children": []}]}
+        ])
+    );
+    assert_eq!(
+        document.select("foo").nodes()[0].to_slate_value(),
+        json!({"type": "foo", "num": 1, "children": []})
+    );
+    assert_eq!(
+        document.select("doc_meta text").nodes()[0].to_slate_value(),
+        json!({"text": ""})
+    );
+}
+
+#[test]
+fn test_slate_json_roundtrip() {
+    let value = json!([
+        {"type": "p", "align": "center", "children": [
+            {"text": "plain "},
+            {"text": "marked", "bold": true, "color": {"r": 1}},
+            {"type": "a", "url": "/x", "children": [{"text": "link"}]}
+        ]}
+    ]);
+    let document = Document::from_slate_value(&value).unwrap();
+    assert_eq!(document.to_slate_value(), value);
+    assert_eq!(Document::default().to_slate_value(), json!([]));
+}
@@ -0,0 +1,79 @@
+use dom_query::{Document, Operation};
+use serde_json::json;
+
+fn document() -> Document {
+    Document::from_slate_value(&json!([
+        {"type": "p", "children": [
+            {"text": "Hello wörld", "bold": true},
+            {"text": "!"}
+        ]}
+    ]))
+    .unwrap()
+}
+
+#[test]
+fn test_split_text_at() {
+    let document = document();
+    let leaf = document.node_at_path(&vec![0, 0].into()).unwrap();
+    let (left, right) = leaf.split_text_at(7).unwrap();
+
+    assert_eq!(left.text().to_string(), "Hello w");
+    assert_eq!(right.text().to_string(), "örld");
+    assert_eq!(right.attr("bold"), Some(json!(true)));
+    assert_eq!(
+        document.to_slate_value()[0]["children"],
+        json!([
+            {"text": "Hello w", "bold": true},
+            {"text": "örld", "bold": true},
+            {"text": "!"}
+        ])
+    );
+
+    // The edges give an empty leaf, past the end gives nothing.
+    let leaf = document.node_at_path(&vec![0, 2].into()).unwrap();
+    assert!(leaf.split_text_at(2).is_none());
+    let (_, right) = leaf.split_text_at(1).unwrap();
+    assert_eq!(right.text().to_string(), "");
+
+    let p = document.node_at_path(&vec![0].into()).unwrap();
+    assert!(p.split_text_at(0).is_none());
+}
+
+#[test]
+fn test_merge_with_previous() {
+    let document = document();
+    let leaf = document.node_at_path(&vec![0, 1].into()).unwrap();
+    let prev = leaf.merge_with_previous().unwrap();
+
+    assert_eq!(prev.text().to_string(), "Hello wörld!");
+    assert_eq!(
+        document.to_slate_value()[0]["children"],
+        json!([{"text": "Hello wörld!", "bold": true}])
+    );
+
+    // The first leaf has nothing to merge into.
+    assert!(prev.merge_with_previous().is_none());
+}
+
+#[test]
+fn test_split_and_merge_are_recorded() {
+    let document = document();
+    document.start_recording();
+    let leaf = document.node_at_path(&vec![0, 0].into()).unwrap();
+    let (_, right) = leaf.split_text_at(5).unwrap();
+    right.set_text_attrs(vec![]);
+    document
+        .node_at_path(&vec![0, 2].into())
+        .unwrap()
+        .merge_with_previous()
+        .unwrap();
+    let ops = document.stop_recording();
+    assert!(matches!(ops[0], Operation::SplitNode { position: 5, .. }));
+    assert!(matches!(ops[2], Operation::MergeNode { position: 6, .. }));
+
+    let replica = self::document();
+    for op in &ops {
+        replica.apply(op).unwrap();
+    }
+    assert_eq!(replica.to_slate_value(), document.to_slate_value());
+}
@@ -0,0 +1,250 @@
+use dom_query::{Document, MarkdownSerializer, Normalizer};
+use serde_json::json;
+
+fn markdown(value: serde_json::Value) -> String {
+    Document::from_slate_value(&value).unwrap().to_markdown()
+}
+
+#[test]
+fn test_markdown_blocks() {
+    let md = markdown(json!([
+        {"type": "h2", "children": [{"text": "Title"}]},
+        {"type": "p", "children": [{"text": "Some text."}]},
+        {"type": "blockquote", "children": [
+            {"type": "p", "children": [{"text": "quoted"}]},
+            {"type": "p", "children": [{"text": "twice"}]}
+        ]},
+        {"type": "hr", "children": [{"text": ""}]},
+        {"type": "code_block", "lang": "rust", "children": [
+            {"type": "code_line", "children": [{"text": "let a = 1;"}]},
+            {"type": "code_line", "children": [{"text": "let b = a * 2;"}]}
+        ]},
+        {"type": "img", "url": "a.png", "alt": "An image", "children": [{"text": ""}]}
+    ]));
+    assert_eq!(
+        md,
+        "## Title\n\nSome text.\n\n> quoted\n>\n> twice\n\n---\n\n\
+         ```rust\nlet a = 1;\nlet b = a * 2;\n```\n\n![An image](a.png)\n"
+    );
+    assert_eq!(markdown(json!([])), "");
+}
+
+#[test]
+fn test_markdown_lists() {
+    let md = markdown(json!([
+        {"type": "ul", "children": [
+            {"type": "li", "children": [
+                {"type": "lic", "children": [{"text": "one"}]},
+                {"type": "ol", "children": [
+                    {"type": "li", "children": [{"type": "lic", "children": [{"text": "first"}]}]},
+                    {"type": "li", "children": [{"type": "lic", "children": [{"text": "second"}]}]}
+                ]}
+            ]},
+            {"type": "li", "children": [{"text": "two"}]}
+        ]}
+    ]));
+    assert_eq!(md, "- one\n  1. first\n  2. second\n- two\n");
+}
+
+#[test]
+fn test_markdown_table() {
+    let md = markdown(json!([
+        {"type": "table", "children": [
+            {"type": "tr", "children": [
+                {"type": "th", "children": [{"type": "p", "children": [{"text": "name"}]}]},
+                {"type": "th", "children": [{"type": "p", "children": [{"text": "a|b"}]}]}
+            ]},
+            {"type": "tr", "children": [
+                {"type": "td", "children": [{"type": "p", "children": [{"text": "ann"}]}]}
+            ]}
+        ]}
+    ]));
+    assert_eq!(md, "| name | a\\|b |\n| --- | --- |\n| ann |  |\n");
+}
+
+#[test]
+fn test_markdown_marks() {
+    let md = markdown(json!([
+        {"type": "p", "children": [
+            {"text": "a "},
+            {"text": "bold ", "bold": true},
+            {"text": "both", "bold": true, "italic": true},
+            {"text": " and ", "bold": true},
+            {"text": "x`y", "code": true},
+            {"text": " "},
+            {"text": "gone", "strikethrough": true},
+            {"text": " see "},
+            {"type": "a", "url": "https://example.com", "children": [
+                {"text": "the "},
+                {"text": "link", "italic": true}
+            ]},
+            {"text": " *not bold*"}
+        ]},
+        {"type": "p", "children": [{"text": "# not a heading"}]}
+    ]));
+    assert_eq!(
+        md,
+        "a **bold *both* and** ``x`y`` ~~gone~~ see [the *link*](https://example.com) \
+         \\*not bold\\*\n\n\\# not a heading\n"
+    );
+}
+
+#[test]
+fn test_markdown_escapes_round_trip() {
+    let value = json!([
+        {"type": "p", "children": [
+            {"text": "un"},
+            {"text": "believ", "italic": true},
+            {"text": "able"}
+        ]},
+        {"type": "p", "children": [{"text": "1. not a list"}]},
+        {"type": "p", "children": [{"text": "2) nor this"}]},
+        {"type": "p", "children": [{"text": "a\n3. after a break"}]},
+        {"type": "p", "children": [
+            {"type": "a", "url": "/a b)c", "children": [{"text": "only a link"}]}
+        ]},
+        {"type": "p", "children": [
+            {"text": "see "},
+            {"type": "img", "url": "/<x>.png", "alt": "x", "children": [{"text": ""}]},
+            {"text": ""}
+        ]}
+    ]);
+    let md = markdown(value.clone());
+    assert_eq!(
+        md,
+        "un*believ*able\n\n1\\. not a list\n\n2\\) nor this\n\na\\\n3\\. after a break\n\n\
+         [only a link](</a b)c>)\n\nsee ![x](</\\<x\\>.png>)\n"
+    );
+
+    let parsed = Document::from_markdown(&md).to_slate_value();
+    assert_eq!(parsed[0], value[0]);
+    for i in 1..4 {
+        assert_eq!(parsed[i]["children"][0]["text"], value[i]["children"][0]["text"]);
+    }
+    assert_eq!(parsed[4]["children"][1]["url"], "/a b)c");
+    assert_eq!(parsed[5]["children"][1]["url"], "/<x>.png");
+}
+
+#[test]
+fn test_markdown_handlers() {
+    let doc = Document::from_slate_value(&json!([
+        {"type": "callout", "children": [
+            {"type": "p", "children": [{"text": "note"}]}
+        ]},
+        {"type": "h1", "children": [{"text": "kept"}]}
+    ]))
+    .unwrap();
+
+    let serializer = MarkdownSerializer::new()
+        .handler("callout", |_, children| {
+            format!("> **Note**\n> {}", children)
+        })
+        .handler("h1", |node, _| format!("{}\n===", node.text()));
+    assert_eq!(
+        doc.to_markdown_with(&serializer),
+        "> **Note**\n> note\n\nkept\n===\n"
+    );
+}
+
+const CORPUS: &[&str] = &[
+    include_str!("../test-pages/markdown/basics.md"),
+    include_str!("../test-pages/markdown/code.md"),
+    include_str!("../test-pages/markdown/inline.md"),
+    include_str!("../test-pages/markdown/nested-lists.md"),
+    include_str!("../test-pages/markdown/tables.md"),
+    include_str!("../test-pages/markdown/tasks.md"),
+];
+
+#[test]
+fn test_markdown_corpus_round_trip() {
+    for markdown in CORPUS {
+        let doc = Document::from_markdown(markdown);
+        let value = doc.to_slate_value();
+        assert!(!value.as_array().unwrap().is_empty());
+
+        // The parsed value is normalized already.
+        doc.normalize_with(&Normalizer::new().inline(|node| {
+            let parent = node.parent().and_then(|parent| parent.node_name());
+            match node.node_name().as_deref() {
+                Some("a") => true,
+                Some("img") => parent.as_deref() == Some("p"),
+                _ => false,
+            }
+        }))
+        .unwrap();
+        assert_eq!(doc.to_slate_value(), value);
+
+        let rendered = doc.to_markdown();
+        assert_eq!(
+            Document::from_markdown(&rendered).to_slate_value(),
+            value,
+            "{}",
+            rendered
+        );
+    }
+}
+
+#[test]
+fn test_from_markdown_nested_lists() {
+    let lic = |text: &str| json!({"type": "lic", "children": [{"text": text}]});
+    let doc = Document::from_markdown("- a\n  1. b\n  2. c\n- d\n\n7. e\n");
+    assert_eq!(
+        doc.to_slate_value(),
+        json!([
+            {"type": "ul", "children": [
+                {"type": "li", "children": [
+                    lic("a"),
+                    {"type": "ol", "children": [
+                        {"type": "li", "children": [lic("b")]},
+                        {"type": "li", "children": [lic("c")]}
+                    ]}
+                ]},
+                {"type": "li", "children": [lic("d")]}
+            ]},
+            {"type": "ol", "start": 7, "children": [{"type": "li", "children": [lic("e")]}]}
+        ])
+    );
+}
+
+#[test]
+fn test_from_markdown_tables_and_tasks() {
+    let cell = |name: &str, text: &str| json!({"type": name, "children": [{"type": "p", "children": [{"text": text}]}]});
+    let doc = Document::from_markdown("| a | b |\n|---|---|\n| 1 |\n\n- [x] done\n- [ ] todo\n");
+    assert_eq!(
+        doc.to_slate_value(),
+        json!([
+            {"type": "table", "children": [
+                {"type": "tr", "children": [cell("th", "a"), cell("th", "b")]},
+                {"type": "tr", "children": [cell("td", "1"), cell("td", "")]}
+            ]},
+            {"type": "ul", "children": [
+                {"type": "li", "checked": true, "children": [
+                    {"type": "lic", "children": [{"text": "done"}]}
+                ]},
+                {"type": "li", "checked": false, "children": [
+                    {"type": "lic", "children": [{"text": "todo"}]}
+                ]}
+            ]}
+        ])
+    );
+}
+
+#[test]
+fn test_from_markdown_inlines() {
+    let doc = Document::from_markdown("[**a**](u) `x` ![i](p.png)\n\n![only](q.png)");
+    assert_eq!(
+        doc.to_slate_value(),
+        json!([
+            {"type": "p", "children": [
+                {"text": ""},
+                {"type": "a", "url": "u", "children": [{"text": "a", "bold": true}]},
+                {"text": " "},
+                {"text": "x", "code": true},
+                {"text": " "},
+                {"type": "img", "url": "p.png", "alt": "i", "children": [{"text": ""}]},
+                {"text": ""}
+            ]},
+            {"type": "img", "url": "q.png", "alt": "only", "children": [{"text": ""}]}
+        ])
+    );
+}
@@ -0,0 +1,169 @@
+use dom_query::{Document, OperationError, Path, Point, Range};
+use serde_json::{json, Value};
+
+fn value() -> Value {
+    json!([
+        {"type": "p", "children": [{"text": "Hello world"}]},
+        {"type": "p", "children": [
+            {"text": "one "},
+            {"text": "two", "italic": true},
+            {"text": " three"}
+        ]}
+    ])
+}
+
+fn range(anchor: (&[usize], usize), focus: (&[usize], usize)) -> Range {
+    Range::new(
+        Point::new(anchor.0.to_vec(), anchor.1),
+        Point::new(focus.0.to_vec(), focus.1),
+    )
+}
+
+#[test]
+fn test_add_mark_inside_leaf() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document
+        .add_mark(&range((&[0, 0], 3), (&[0, 0], 8)), "bold", json!(true))
+        .unwrap();
+
+    assert_eq!(
+        document.to_slate_value()[0],
+        json!({"type": "p", "children": [
+            {"text": "Hel"},
+            {"text": "lo wo", "bold": true},
+            {"text": "rld"}
+        ]})
+    );
+}
+
+#[test]
+fn test_add_mark_after_astral_characters() {
+    let document =
+        Document::from_slate_value(&json!([{"type": "p", "children": [{"text": "😀abc"}]}]))
+            .unwrap();
+    document
+        .add_mark(&range((&[0, 0], 2), (&[0, 0], 3)), "bold", json!(true))
+        .unwrap();
+
+    assert_eq!(
+        document.to_slate_value()[0]["children"],
+        json!([{"text": "😀"}, {"text": "a", "bold": true}, {"text": "bc"}])
+    );
+}
+
+#[test]
+fn test_add_mark_across_leaves() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    // A backward range covers the same text.
+    document
+        .add_mark(&range((&[1, 2], 3), (&[1, 0], 2)), "italic", json!(true))
+        .unwrap();
+
+    assert_eq!(
+        document.to_slate_value()[1],
+        json!({"type": "p", "children": [
+            {"text": "on"},
+            {"text": "e two th", "italic": true},
+            {"text": "ree"}
+        ]})
+    );
+}
+
+#[test]
+fn test_remove_mark_merges() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document
+        .remove_mark(&range((&[1, 1], 0), (&[1, 1], 3)), "italic")
+        .unwrap();
+    assert_eq!(
+        document.to_slate_value()[1],
+        json!({"type": "p", "children": [{"text": "one two three"}]})
+    );
+
+    // Removing a mark that is not there changes nothing.
+    document
+        .remove_mark(&range((&[0, 0], 0), (&[0, 0], 5)), "bold")
+        .unwrap();
+    assert_eq!(document.to_slate_value()[0], value()[0]);
+}
+
+#[test]
+fn test_toggle_mark() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    let all = range((&[0, 0], 0), (&[1, 2], 6));
+
+    document.toggle_mark(&all, "bold").unwrap();
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "Hello world", "bold": true}]},
+            {"type": "p", "children": [
+                {"text": "one ", "bold": true},
+                {"text": "two", "italic": true, "bold": true},
+                {"text": " three", "bold": true}
+            ]}
+        ])
+    );
+    document.toggle_mark(&all, "bold").unwrap();
+    assert_eq!(document.to_slate_value(), value());
+
+    // A collapsed range does not change anything.
+    let collapsed = Range::collapsed(Point::new(vec![0, 0], 2));
+    document.toggle_mark(&collapsed, "bold").unwrap();
+    assert_eq!(document.to_slate_value(), value());
+}
+
+#[test]
+fn test_selection_follows_marks() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    let selection = range((&[0, 0], 6), (&[1, 0], 2));
+    document.set_selection(Some(selection.clone())).unwrap();
+    let text = document.range_text(&selection).unwrap();
+
+    document.add_mark(&selection, "code", json!(true)).unwrap();
+    let selection = document.selection().unwrap();
+    assert_eq!(selection.anchor, Point::new(vec![0, 1], 0));
+    // Like in Slate, a point at a split moves to the new leaf.
+    assert_eq!(selection.focus, Point::new(vec![1, 1], 0));
+    assert_eq!(document.range_text(&selection).unwrap(), text);
+}
+
+#[test]
+fn test_mark_errors() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    assert_eq!(
+        document.add_mark(&range((&[0, 0], 0), (&[5, 0], 0)), "bold", json!(true)),
+        Err(OperationError::InvalidPath(Path::new(vec![5, 0])))
+    );
+    assert_eq!(
+        document.add_mark(&range((&[0, 0], 0), (&[0, 0], 50)), "bold", json!(true)),
+        Err(OperationError::InvalidOffset {
+            path: Path::new(vec![0, 0]),
+            offset: 50
+        })
+    );
+    assert!(document
+        .add_mark(&range((&[0, 0], 0), (&[0, 0], 2)), "text", json!("x"))
+        .is_err());
+    assert_eq!(document.to_slate_value(), value());
+}
+
+#[test]
+fn test_marks_are_undoable() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.enable_history();
+    document.start_recording();
+    document
+        .add_mark(&range((&[0, 0], 3), (&[1, 1], 1)), "bold", json!(true))
+        .unwrap();
+    let marked = document.to_slate_value();
+
+    let replica = Document::from_slate_value(&value()).unwrap();
+    for op in document.stop_recording() {
+        replica.apply(&op).unwrap();
+    }
+    assert_eq!(replica.to_slate_value(), marked);
+
+    document.undo().unwrap();
+    assert_eq!(document.to_slate_value(), value());
+}
@@ -0,0 +1,215 @@
+use dom_query::{Document, Element, NormalizeError, Normalizer};
+use serde_json::{json, Value};
+
+fn normalized(value: Value) -> Value {
+    let document = Document::from_slate_value(&value).unwrap();
+    document.normalize().unwrap();
+    document.to_slate_value()
+}
+
+#[test]
+fn test_empty_elements() {
+    assert_eq!(
+        normalized(json!([
+            {"type": "p", "children": []},
+            {"type": "blockquote", "children": [{"type": "p", "children": []}]}
+        ])),
+        json!([
+            {"type": "p", "children": [{"text": ""}]},
+            {"type": "blockquote", "children": [{"type": "p", "children": [{"text": ""}]}]}
+        ])
+    );
+}
+
+#[test]
+fn test_merge_texts() {
+    assert_eq!(
+        normalized(json!([{"type": "p", "children": [
+            {"text": "a", "bold": true},
+            {"text": "b", "bold": true},
+            {"text": ""},
+            {"text": "c"},
+            {"text": ""},
+            {"text": "d", "italic": true},
+            {"text": "e", "italic": true, "bold": false}
+        ]}])),
+        json!([{"type": "p", "children": [
+            {"text": "ab", "bold": true},
+            {"text": "c"},
+            {"text": "d", "italic": true},
+            {"text": "e", "italic": true, "bold": false}
+        ]}])
+    );
+}
+
+#[test]
+fn test_block_and_inline_children() {
+    assert_eq!(
+        normalized(json!([
+            {"text": "top level text"},
+            {"type": "p", "children": [
+                {"text": "starts with text"},
+                {"type": "p", "children": [{"text": "nested block"}]}
+            ]},
+            {"type": "blockquote", "children": [
+                {"type": "p", "children": [{"text": "block"}]},
+                {"text": "text among blocks"}
+            ]}
+        ])),
+        json!([
+            {"type": "p", "children": [{"text": "starts with text"}]},
+            {"type": "blockquote", "children": [{"type": "p", "children": [{"text": "block"}]}]}
+        ])
+    );
+}
+
+#[test]
+fn test_inline_elements() {
+    let normalizer = Normalizer::new().inline(|node| node.node_name().as_deref() == Some("a"));
+    let document = Document::from_slate_value(&json!([
+        {"type": "p", "children": [
+            {"type": "a", "url": "/1", "children": [{"text": "one"}]},
+            {"type": "a", "url": "/2", "children": [{"text": "two"}]}
+        ]},
+        {"type": "a", "url": "/3", "children": [{"text": "not in a block"}]}
+    ]))
+    .unwrap();
+    document.normalize_with(&normalizer).unwrap();
+
+    assert_eq!(
+        document.to_slate_value(),
+        json!([{"type": "p", "children": [
+            {"text": ""},
+            {"type": "a", "url": "/1", "children": [{"text": "one"}]},
+            {"text": ""},
+            {"type": "a", "url": "/2", "children": [{"text": "two"}]},
+            {"text": ""}
+        ]}])
+    );
+}
+
+#[test]
+fn test_text_children() {
+    let document = Document::from_slate_value(&json!([
+        {"type": "p", "children": [{"text": "leaf"}]}
+    ]))
+    .unwrap();
+    let p = document.select("p");
+    let leaf = &p.nodes()[0].children()[0];
+    leaf.append_last_child(Element::new("strong").into());
+    leaf.append_last_child(Element::new("em").into());
+
+    document.normalize().unwrap();
+    assert_eq!(
+        document.to_slate_value(),
+        json!([{"type": "p", "children": [{"text": "leaf"}]}])
+    );
+}
+
+#[test]
+fn test_normalize_html() {
+    let document = Document::from_slate_html(
+        r#"<p>one <strong>two</strong></p>
+        <ul><li>item</li></ul>"#,
+    );
+    document.normalize().unwrap();
+
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "one "}]},
+            {"type": "ul", "children": [{"type": "li", "children": [{"text": "item"}]}]}
+        ])
+    );
+}
+
+#[test]
+fn test_normalize_is_recorded() {
+    let value = json!([
+        {"text": "top"},
+        {"type": "p", "children": [{"text": "a"}, {"text": "b"}, {"text": ""}]},
+        {"type": "p", "children": []}
+    ]);
+    let document = Document::from_slate_value(&value).unwrap();
+    document.enable_history();
+    document.start_recording();
+    document.normalize().unwrap();
+    let normalized = document.to_slate_value();
+
+    let replica = Document::from_slate_value(&value).unwrap();
+    for op in document.stop_recording() {
+        replica.apply(&op).unwrap();
+    }
+    assert_eq!(replica.to_slate_value(), normalized);
+
+    document.undo().unwrap();
+    assert_eq!(document.to_slate_value(), value);
+}
+
+#[test]
+fn test_custom_rules() {
+    let normalizer = Normalizer::new().rule(|document, node| {
+        // Every block ends with a paragraph.
+        if node.path().map_or(false, |path| path.is_empty())
+            && !document.select("body > *:last-child").is("p")
+        {
+            node.append_last_child(Element::new("p").into());
+            return true;
+        }
+        false
+    });
+
+    let document = Document::from_slate_value(&json!([
+        {"type": "h1", "children": [{"text": "title"}]}
+    ]))
+    .unwrap();
+    document.normalize_with(&normalizer).unwrap();
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "h1", "children": [{"text": "title"}]},
+            {"type": "p", "children": [{"text": ""}]}
+        ])
+    );
+}
+
+#[test]
+fn test_endless_rule() {
+    let document = Document::from_slate_value(&json!([
+        {"type": "p", "children": [{"text": ""}]}
+    ]))
+    .unwrap();
+    let err = document
+        .normalize_with(&Normalizer::new().rule(|_, _| true))
+        .unwrap_err();
+    assert!(matches!(err, NormalizeError::Unfinished { .. }));
+    assert!(err
+        .to_string()
+        .starts_with("could not completely normalize"));
+}
+
+#[test]
+fn test_rule_removing_nodes() {
+    let document = Document::from_slate_value(&json!([
+        {"type": "p", "children": [{"text": "a"}]},
+        {"type": "hr", "children": []},
+        {"type": "p", "children": [{"text": "b"}]}
+    ]))
+    .unwrap();
+    // Removes each `hr` while normalizing the node before it.
+    let normalizer = Normalizer::new().rule(|_, node| match node.next_sibling() {
+        Some(next) if next.node_name().as_deref() == Some("hr") => {
+            next.remove_from_parent();
+            true
+        }
+        _ => false,
+    });
+    document.normalize_with(&normalizer).unwrap();
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "a"}]},
+            {"type": "p", "children": [{"text": "b"}]}
+        ])
+    );
+}
@@ -0,0 +1,345 @@
+use dom_query::{Document, Operation, OperationError, Path, Point, Range};
+use serde_json::json;
+
+fn doc() -> Document {
+    Document::from_slate_value(&json!([
+        {"type": "p", "children": [{"text": "Hello "}, {"text": "world", "bold": true}]},
+        {"type": "p", "children": [{"text": "second"}]}
+    ]))
+    .unwrap()
+}
+
+fn op(value: serde_json::Value) -> Operation {
+    serde_json::from_value(value).unwrap()
+}
+
+fn point(path: &[usize], offset: usize) -> Point {
+    Point::new(Path::from(path), offset)
+}
+
+#[test]
+fn test_text_operations() {
+    let document = doc();
+    document
+        .apply(&op(
+            json!({"type": "insert_text", "path": [0, 0], "offset": 5, "text": ","}),
+        ))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "remove_text", "path": [1, 0], "offset": 0, "text": "sec"}),
+        ))
+        .unwrap();
+
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "Hello, "}, {"text": "world", "bold": true}]},
+            {"type": "p", "children": [{"text": "ond"}]}
+        ])
+    );
+}
+
+#[test]
+fn test_text_offsets_count_utf16_code_units() {
+    let document =
+        Document::from_slate_value(&json!([{"type": "p", "children": [{"text": "😀ab"}]}]))
+            .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "insert_text", "path": [0, 0], "offset": 2, "text": "X"}),
+        ))
+        .unwrap();
+    assert_eq!(document.to_slate_value()[0]["children"][0]["text"], "😀Xab");
+
+    document
+        .apply(&op(
+            json!({"type": "remove_text", "path": [0, 0], "offset": 0, "text": "😀X"}),
+        ))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "insert_text", "path": [0, 0], "offset": 2, "text": "🎉"}),
+        ))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "split_node", "path": [0, 0], "position": 2, "properties": {}}),
+        ))
+        .unwrap();
+    assert_eq!(
+        document.to_slate_value()[0]["children"],
+        json!([{"text": "ab"}, {"text": "🎉"}])
+    );
+
+    let inside_pair = op(json!({"type": "insert_text", "path": [0, 1], "offset": 1, "text": "X"}));
+    assert_eq!(
+        document.apply(&inside_pair),
+        Err(OperationError::InvalidOffset {
+            path: Path::from(&[0, 1][..]),
+            offset: 1,
+        })
+    );
+    let past_end = op(json!({"type": "insert_text", "path": [0, 1], "offset": 3, "text": "X"}));
+    assert!(document.apply(&past_end).is_err());
+}
+
+#[test]
+fn test_node_operations() {
+    let document = doc();
+    document
+        .apply(&op(json!({
+            "type": "insert_node",
+            "path": [1],
+            "node": {"type": "h1", "children": [{"text": "title"}]}
+        })))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "remove_node", "path": [0, 1], "node": {"text": "world", "bold": true}}),
+        ))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "move_node", "path": [2], "newPath": [0]}),
+        ))
+        .unwrap();
+
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "second"}]},
+            {"type": "p", "children": [{"text": "Hello "}]},
+            {"type": "h1", "children": [{"text": "title"}]}
+        ])
+    );
+}
+
+#[test]
+fn test_move_node_into_later_sibling() {
+    let document = doc();
+    document
+        .apply(&op(
+            json!({"type": "move_node", "path": [0, 0], "newPath": [1, 1]}),
+        ))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "move_node", "path": [0], "newPath": [1, 0]}),
+        ))
+        .unwrap();
+
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "p", "children": [
+                {"type": "p", "children": [{"text": "world", "bold": true}]},
+                {"text": "second"},
+                {"text": "Hello "}
+            ]}
+        ])
+    );
+}
+
+#[test]
+fn test_split_and_merge() {
+    let document = doc();
+    document
+        .apply(&op(json!({"type": "split_node", "path": [0, 1], "position": 2, "properties": {"bold": true}})))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "split_node", "path": [0], "position": 2, "properties": {"type": "p"}}),
+        ))
+        .unwrap();
+    assert_eq!(
+        document.to_slate_value(),
+        json!([
+            {"type": "p", "children": [{"text": "Hello "}, {"text": "wo", "bold": true}]},
+            {"type": "p", "children": [{"text": "rld", "bold": true}]},
+            {"type": "p", "children": [{"text": "second"}]}
+        ])
+    );
+
+    document
+        .apply(&op(
+            json!({"type": "merge_node", "path": [1], "position": 2, "properties": {}}),
+        ))
+        .unwrap();
+    document
+        .apply(&op(
+            json!({"type": "merge_node", "path": [0, 2], "position": 2, "properties": {}}),
+        ))
+        .unwrap();
+    assert_eq!(document.to_slate_value(), doc().to_slate_value());
+}
+
+#[test]
+fn test_set_node() {
+    let document = doc();
+    document
+        .apply(&op(json!({
+            "type": "set_node",
+            "path": [0],
+            "properties": {},
+            "newProperties": {"type": "h2", "align": "center"}
+        })))
+        .unwrap();
+    document
+        .apply(&op(json!({
+            "type": "set_node",
+            "path": [0, 1],
+            "properties": {"bold": true},
+            "newProperties": {"italic": true}
+        })))
+        .unwrap();
+    document
+        .apply(&op(json!({
+            "type": "set_node",
+            "path": [0],
+            "properties": {"align": "center"},
+            "newProperties": {"align": null}
+        })))
+        .unwrap();
+
+    assert_eq!(
+        document.to_slate_value()[0],
+        json!({"type": "h2", "children": [{"text": "Hello "}, {"text": "world", "italic": true}]})
+    );
+}
+
+#[test]
+fn test_invalid_operations() {
+    let document = doc();
+    let before = document.to_slate_value();
+
+    let invalid = [
+        (
+            json!({"type": "insert_text", "path": [0, 5], "offset": 0, "text": "a"}),
+            OperationError::InvalidPath(Path::from(&[0, 5][..])),
+        ),
+        (
+            json!({"type": "insert_text", "path": [0, 0], "offset": 7, "text": "a"}),
+            OperationError::InvalidOffset {
+                path: Path::from(&[0, 0][..]),
+                offset: 7,
+            },
+        ),
+        (
+            json!({"type": "insert_text", "path": [0], "offset": 0, "text": "a"}),
+            OperationError::InvalidNode(Path::from(&[0][..])),
+        ),
+        (
+            json!({"type": "remove_node", "path": [], "node": {}}),
+            OperationError::InvalidPath(Path::default()),
+        ),
+        (
+            json!({"type": "merge_node", "path": [1, 0], "position": 0}),
+            OperationError::InvalidPath(Path::from(&[1, 0][..])),
+        ),
+        (
+            json!({"type": "move_node", "path": [0], "newPath": [0, 1]}),
+            OperationError::InvalidPath(Path::from(&[0, 1][..])),
+        ),
+        (
+            json!({"type": "move_node", "path": [0, 0], "newPath": [1, 0, 0]}),
+            OperationError::InvalidPath(Path::from(&[1, 0, 0][..])),
+        ),
+        (
+            json!({"type": "move_node", "path": [1], "newPath": []}),
+            OperationError::InvalidPath(Path::default()),
+        ),
+        (
+            json!({"type": "move_node", "path": [], "newPath": []}),
+            OperationError::InvalidPath(Path::default()),
+        ),
+        (
+            json!({"type": "move_node", "path": [1], "newPath": [2]}),
+            OperationError::InvalidPath(Path::from(&[2][..])),
+        ),
+        (
+            json!({"type": "insert_node", "path": [0, 0, 0], "node": {"text": "a"}}),
+            OperationError::InvalidPath(Path::from(&[0, 0, 0][..])),
+        ),
+    ];
+    for (value, error) in invalid {
+        assert_eq!(document.apply(&op(value)), Err(error));
+    }
+
+    let err = document
+        .apply(&op(
+            json!({"type": "insert_node", "path": [0], "node": {"children": []}}),
+        ))
+        .unwrap_err();
+    assert!(matches!(err, OperationError::InvalidOperation(_)));
+    assert_eq!(document.to_slate_value(), before);
+}
+
+#[test]
+fn test_selection_follows_operations() {
+    let document = doc();
+    document
+        .apply(&op(json!({
+            "type": "set_selection",
+            "properties": null,
+            "newProperties": {"anchor": {"path": [0, 1], "offset": 3}, "focus": {"path": [1, 0], "offset": 2}}
+        })))
+        .unwrap();
+
+    document
+        .apply(&op(
+            json!({"type": "insert_text", "path": [0, 1], "offset": 0, "text": "big "}),
+        ))
+        .unwrap();
+    document
+        .apply(&op(json!({"type": "insert_node", "path": [0], "node": {"type": "h1", "children": [{"text": ""}]}})))
+        .unwrap();
+    assert_eq!(
+        document.selection(),
+        Some(Range::new(point(&[1, 1], 7), point(&[2, 0], 2)))
+    );
+
+    document
+        .apply(&op(json!({"type": "split_node", "path": [1, 1], "position": 4, "properties": {"bold": true}})))
+        .unwrap();
+    assert_eq!(document.selection().unwrap().anchor, point(&[1, 2], 3));
+
+    document
+        .apply(&op(json!({"type": "remove_node", "path": [2], "node": {}})))
+        .unwrap();
+    assert_eq!(document.selection().unwrap().focus, point(&[1, 2], 5));
+
+    document.set_selection(None).unwrap();
+    assert_eq!(document.selection(), None);
+    assert!(document
+        .apply(&op(json!({"type": "set_selection", "properties": null, "newProperties": {"anchor": {"path": [0, 0], "offset": 0}}})))
+        .is_err());
+}
+
+#[test]
+fn test_operation_serde() {
+    let value = json!({"type": "move_node", "path": [0, 1], "newPath": [2]});
+    let operation = op(value.clone());
+    assert_eq!(
+        operation,
+        Operation::MoveNode {
+            path: Path::from(&[0, 1][..]),
+            new_path: Path::from(&[2][..])
+        }
+    );
+    assert_eq!(serde_json::to_value(&operation).unwrap(), value);
+}
+
+#[test]
+fn test_path_transform() {
+    let path = Path::from(&[1, 2][..]);
+    let insert = op(json!({"type": "insert_node", "path": [1, 0], "node": {"text": ""}}));
+    let remove = op(json!({"type": "remove_node", "path": [1], "node": {}}));
+    let merge = op(json!({"type": "merge_node", "path": [1], "position": 3}));
+    let split = op(json!({"type": "split_node", "path": [1], "position": 1}));
+
+    assert_eq!(path.transform(&insert, None), Some(Path::from(&[1, 3][..])));
+    assert_eq!(path.transform(&remove, None), None);
+    assert_eq!(path.transform(&merge, None), Some(Path::from(&[0, 5][..])));
+    assert_eq!(path.transform(&split, None), Some(Path::from(&[2, 1][..])));
+}
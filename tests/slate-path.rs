@@ -0,0 +1,95 @@
+use dom_query::{Document, Path};
+use std::cmp::Ordering;
+
+fn path(indexes: &[usize]) -> Path {
+    Path::from(indexes)
+}
+
+#[test]
+fn test_node_path() {
+    let html = r#"<h1><text>title</text></h1><p><text>a</text><a url="/"><text>link</text></a><text>b</text></p>"#;
+    let document = Document::from_slate_html(html);
+
+    let link = document.select("a text");
+    assert_eq!(link.nodes()[0].path(), Some(path(&[1, 1, 0])));
+    assert_eq!(
+        document.select("p > text").paths(),
+        vec![path(&[1, 0]), path(&[1, 2])]
+    );
+    assert_eq!(document.select("h1").nodes()[0].path(), Some(path(&[0])));
+    assert_eq!(document.select("body").nodes()[0].path(), Some(path(&[])));
+    assert_eq!(document.select("head").nodes()[0].path(), None);
+}
+
+#[test]
+fn test_node_at_path() {
+    let html = r#"<h1><text>title</text></h1><p><text>a</text><a url="/"><text>link</text></a></p>"#;
+    let document = Document::from_slate_html(html);
+
+    let node = document.node_at_path(&path(&[1, 1, 0])).unwrap();
+    assert_eq!(&node.text()[..], "link");
+    assert_eq!(node.path(), Some(path(&[1, 1, 0])));
+    assert!(document.node_at_path(&path(&[2])).is_none());
+    assert!(document.node_at_path(&path(&[1, 5])).is_none());
+
+    for p in document.select("text").paths() {
+        assert_eq!(document.node_at_path(&p).unwrap().path(), Some(p));
+    }
+}
+
+#[test]
+fn test_removed_node_has_no_path() {
+    let document = Document::from_slate_html(r#"<p><text>a</text></p><p><text>b</text></p>"#);
+    let mut first = document.select("p:first-child");
+    let node = first.nodes()[0].clone();
+    first.remove();
+
+    assert_eq!(node.path(), None);
+    assert_eq!(document.select("p").paths(), vec![path(&[0])]);
+}
+
+#[test]
+fn test_path_relations() {
+    let a = path(&[0, 1]);
+    let b = path(&[0, 1, 2]);
+    let c = path(&[0, 2]);
+
+    assert!(a.is_ancestor(&b));
+    assert!(!b.is_ancestor(&a));
+    assert!(!a.is_ancestor(&a));
+    assert!(b.is_descendant(&a));
+    assert!(a.is_parent(&b));
+    assert!(a.is_sibling(&c));
+    assert!(!a.is_sibling(&b));
+
+    assert!(a.is_before(&c));
+    assert!(b.is_before(&c));
+    assert!(!a.is_before(&b));
+    assert!(c.is_after(&b));
+    assert_eq!(a.compare(&b), Ordering::Equal);
+    assert_eq!(c.compare(&a), Ordering::Greater);
+
+    assert!(a.ends_before(&c));
+    assert!(a.ends_before(&path(&[0, 2, 5])));
+    assert!(!b.ends_before(&c));
+    assert!(c.ends_after(&b));
+
+    assert_eq!(b.common(&c), path(&[0]));
+    assert_eq!(a.common(&b), a);
+    assert_eq!(path(&[1]).common(&c), path(&[]));
+}
+
+#[test]
+fn test_path_navigation() {
+    let p = path(&[1, 2]);
+    assert_eq!(p.parent(), Some(path(&[1])));
+    assert_eq!(p.next(), Some(path(&[1, 3])));
+    assert_eq!(p.previous(), Some(path(&[1, 1])));
+    assert_eq!(p.child(0), path(&[1, 2, 0]));
+    assert_eq!(p.ancestors(), vec![path(&[]), path(&[1])]);
+    assert_eq!(path(&[1, 0]).previous(), None);
+    assert_eq!(path(&[]).parent(), None);
+
+    assert_eq!(serde_json::to_string(&p).unwrap(), "[1,2]");
+    assert_eq!(serde_json::from_str::<Path>("[0,3]").unwrap(), path(&[0, 3]));
+}
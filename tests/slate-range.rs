@@ -0,0 +1,105 @@
+use dom_query::{Document, Path, Point, Range};
+use serde_json::json;
+
+fn point(path: &[usize], offset: usize) -> Point {
+    Point::new(Path::from(path), offset)
+}
+
+fn doc() -> Document {
+    Document::from_slate_html(
+        r#"<p><text>Hello </text><text bold="true">wörld</text></p><p><text>second</text><a url="/"><text>link</text></a><text>!</text></p>"#,
+    )
+}
+
+#[test]
+fn test_point_compare() {
+    assert!(point(&[0, 0], 3).is_before(&point(&[0, 0], 4)));
+    assert!(point(&[0, 1], 0).is_after(&point(&[0, 0], 6)));
+    assert!(point(&[1, 1, 0], 0).is_after(&point(&[1, 0], 6)));
+    assert!(!point(&[0, 0], 1).is_before(&point(&[0, 0], 1)));
+}
+
+#[test]
+fn test_range_edges() {
+    let forward = Range::new(point(&[0, 0], 1), point(&[1, 0], 2));
+    let backward = Range::new(point(&[1, 0], 2), point(&[0, 0], 1));
+
+    assert!(!forward.is_backward());
+    assert!(backward.is_backward());
+    assert_eq!(forward.edges(), backward.edges());
+    assert_eq!(backward.start(), &point(&[0, 0], 1));
+    assert_eq!(backward.end(), &point(&[1, 0], 2));
+    assert!(backward.includes(&point(&[0, 1], 0)));
+    assert!(!backward.includes(&point(&[1, 0], 3)));
+    assert!(Range::collapsed(point(&[0, 0], 1)).is_collapsed());
+}
+
+#[test]
+fn test_resolve_point() {
+    let document = doc();
+    let leaf = document.resolve_point(&point(&[0, 1], 5)).unwrap();
+    assert_eq!(&leaf.text()[..], "wörld");
+
+    assert!(document.resolve_point(&point(&[0, 1], 6)).is_none());
+    assert!(document.resolve_point(&point(&[0], 0)).is_none());
+    assert!(document.resolve_point(&point(&[3, 0], 0)).is_none());
+}
+
+#[test]
+fn test_range_text() {
+    let document = doc();
+
+    let range = Range::new(point(&[0, 0], 2), point(&[0, 1], 3));
+    assert_eq!(document.range_text(&range).unwrap(), "llo wör");
+
+    let range = Range::new(point(&[1, 1, 0], 2), point(&[0, 1], 1));
+    assert_eq!(document.range_text(&range).unwrap(), "örldsecondli");
+
+    let range = Range::collapsed(point(&[1, 0], 2));
+    assert_eq!(document.range_text(&range).unwrap(), "");
+
+    let range = Range::new(point(&[0, 0], 0), point(&[0, 0], 9));
+    assert_eq!(document.range_text(&range), None);
+}
+
+#[test]
+fn test_points_count_utf16_code_units() {
+    let document = Document::from_slate_html("<p><text>a😀b</text><text>🎉</text></p>");
+
+    assert!(document.resolve_point(&point(&[0, 0], 4)).is_some());
+    assert!(document.resolve_point(&point(&[0, 0], 5)).is_none());
+    assert!(document.resolve_point(&point(&[0, 1], 2)).is_some());
+
+    let range = Range::new(point(&[0, 0], 1), point(&[0, 1], 2));
+    assert_eq!(document.range_text(&range).unwrap(), "😀b🎉");
+    let range = Range::new(point(&[0, 0], 3), point(&[0, 0], 4));
+    assert_eq!(document.range_text(&range).unwrap(), "b");
+    let range = Range::new(point(&[0, 0], 2), point(&[0, 0], 4));
+    assert_eq!(document.range_text(&range), None);
+}
+
+#[test]
+fn test_range_leaves() {
+    let document = doc();
+    let range = Range::new(point(&[0, 1], 5), point(&[1, 1, 0], 0));
+    let leaves: Vec<String> = document
+        .range_leaves(&range)
+        .iter()
+        .map(|leaf| leaf.text().to_string())
+        .collect();
+    assert_eq!(leaves, vec!["wörld", "second", "link"]);
+}
+
+#[test]
+fn test_range_serde() {
+    let range: Range = serde_json::from_value(json!({
+        "anchor": {"path": [0, 0], "offset": 1},
+        "focus": {"path": [1, 0], "offset": 2}
+    }))
+    .unwrap();
+    assert_eq!(range, Range::new(point(&[0, 0], 1), point(&[1, 0], 2)));
+    assert_eq!(
+        serde_json::to_value(&range.anchor).unwrap(),
+        json!({"path": [0, 0], "offset": 1})
+    );
+}
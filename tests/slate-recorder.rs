@@ -0,0 +1,177 @@
+use dom_query::{Document, Element, Operation, Path, Text};
+use serde_json::{json, Value};
+
+fn value() -> Value {
+    json!([
+        {"type": "p", "id": "a", "children": [{"text": "Hello "}, {"text": "world", "bold": true}]},
+        {"type": "blockquote", "children": [
+            {"type": "p", "id": "b", "children": [{"text": "quoted"}]}
+        ]},
+        {"type": "p", "id": "c", "children": [{"text": "last"}]}
+    ])
+}
+
+/// Checks that replaying the recorded operations on the original value gives the
+/// same result as the recorded mutations.
+fn assert_replays(document: &Document, ops: &[Operation]) {
+    let replica = Document::from_slate_value(&value()).unwrap();
+    for op in ops {
+        replica.apply(op).unwrap();
+    }
+    assert_eq!(replica.to_slate_value(), document.to_slate_value());
+}
+
+#[test]
+fn test_record_set_node() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.start_recording();
+
+    document.select("#a").set_attr("align", "center");
+    document.select("#b").remove_attr("id");
+    document.select("#c").add_class("big");
+    document.select("#c").set_attr("id", "c");
+
+    let ops = document.stop_recording();
+    assert_eq!(
+        serde_json::to_value(&ops).unwrap(),
+        json!([
+            {"type": "set_node", "path": [0], "properties": {}, "newProperties": {"align": "center"}},
+            {"type": "set_node", "path": [1, 0], "properties": {"id": "b"}, "newProperties": {}},
+            {"type": "set_node", "path": [2], "properties": {}, "newProperties": {"class": "big"}}
+        ])
+    );
+    assert_replays(&document, &ops);
+}
+
+#[test]
+fn test_record_text() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.start_recording();
+
+    let p = document.select("#a");
+    let leaves = p.nodes()[0].children();
+    leaves[0].set_text_contents("Hi ");
+    leaves[1].append_text_contents("!");
+    leaves[1].set_text_attrs(vec![]);
+
+    let ops = document.stop_recording();
+    assert_eq!(
+        serde_json::to_value(&ops).unwrap(),
+        json!([
+            {"type": "remove_text", "path": [0, 0], "offset": 1, "text": "ello"},
+            {"type": "insert_text", "path": [0, 0], "offset": 1, "text": "i"},
+            {"type": "insert_text", "path": [0, 1], "offset": 5, "text": "!"},
+            {"type": "set_node", "path": [0, 1], "properties": {"bold": true}, "newProperties": {}}
+        ])
+    );
+    assert_replays(&document, &ops);
+}
+
+#[test]
+fn test_record_remove_and_insert() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.start_recording();
+
+    document.select("blockquote").remove();
+    let p = document.select("#c");
+    let node = &p.nodes()[0];
+    node.insert_before(Element::new("hr").into());
+    node.insert_after(Element::new("hr").into());
+    node.append_first_child(Text::new("first ").into());
+    node.append_last_child(Text::new(" end").into());
+
+    let ops = document.stop_recording();
+    assert_eq!(
+        ops[0],
+        Operation::RemoveNode {
+            path: Path::new(vec![1]),
+            node: value()[1].clone()
+        }
+    );
+    assert_eq!(
+        serde_json::to_value(&ops[1..]).unwrap(),
+        json!([
+            {"type": "insert_node", "path": [1], "node": {"type": "hr", "children": []}},
+            {"type": "insert_node", "path": [3], "node": {"type": "hr", "children": []}},
+            {"type": "insert_node", "path": [2, 0], "node": {"text": "first "}},
+            {"type": "insert_node", "path": [2, 2], "node": {"text": " end"}}
+        ])
+    );
+    assert_replays(&document, &ops);
+}
+
+#[test]
+fn test_record_html() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.start_recording();
+
+    document
+        .select("#a")
+        .append_slate_html("<strong>bold</strong> text");
+    document.select("#b").set_slate_html("<em>new</em>");
+    document
+        .select("#c")
+        .replace_with_html(r#"<h1>One</h1><h2>Two</h2>"#);
+
+    let ops = document.stop_recording();
+    assert!(ops.iter().all(|op| matches!(
+        op,
+        Operation::InsertNode { .. } | Operation::RemoveNode { .. }
+    )));
+    assert_replays(&document, &ops);
+}
+
+#[test]
+fn test_record_moves() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    document.start_recording();
+
+    // Move a later node into an earlier one, and an earlier node into a later one.
+    let c = document.select("#c");
+    document.select("blockquote").append_selection(&c);
+    let a = document.select("#a");
+    document.select("blockquote").append_selection(&a);
+    // And a node before its previous sibling.
+    let first = document.select("#b");
+    document
+        .select("blockquote > p:last-child")
+        .replace_with_selection(&first);
+
+    let ops = document.stop_recording();
+    assert_eq!(
+        serde_json::to_value(&ops[..2]).unwrap(),
+        json!([
+            {"type": "move_node", "path": [2], "newPath": [1, 1]},
+            {"type": "move_node", "path": [0], "newPath": [1, 2]}
+        ])
+    );
+    assert_replays(&document, &ops);
+}
+
+#[test]
+fn test_recording_state() {
+    let document = Document::from_slate_value(&value()).unwrap();
+    assert!(!document.is_recording());
+    document.select("#a").set_attr("x", 1);
+    assert!(document.take_operations().is_empty());
+
+    document.start_recording();
+    assert!(document.is_recording());
+    // Changes outside of the content are not part of the Slate value.
+    document.select("head").set_attr("lang", "en");
+    document.select("body").set_attr("lang", "en");
+    assert!(document.take_operations().is_empty());
+
+    let op = Operation::InsertText {
+        path: Path::new(vec![2, 0]),
+        offset: 0,
+        text: "the ".to_string(),
+    };
+    document.apply(&op).unwrap();
+    assert_eq!(document.take_operations(), vec![op]);
+    assert!(document.is_recording());
+
+    document.select("#a").remove_attr("x");
+    assert_eq!(document.stop_recording().len(), 1);
+    assert!(!document.is_recording());
+}
@@ -0,0 +1,81 @@
+use dom_query::{Document, HtmlRenderer};
+use serde_json::json;
+
+fn render(value: serde_json::Value) -> String {
+    Document::from_slate_value(&value).unwrap().render_html()
+}
+
+#[test]
+fn test_render_blocks() {
+    let html = render(json!([
+        {"type": "h1", "children": [{"text": "Title"}]},
+        {"type": "blockquote", "children": [{"text": "quote"}]},
+        {"type": "ol", "start": 3, "children": [
+            {"type": "li", "children": [{"type": "lic", "children": [{"text": "three"}]}]},
+            {"type": "li", "checked": true, "children": [{"type": "lic", "children": [{"text": "done"}]}]}
+        ]},
+        {"type": "code_block", "lang": "rust", "children": [
+            {"type": "code_line", "children": [{"text": "if a < b {"}]},
+            {"type": "code_line", "children": [{"text": "}"}]}
+        ]},
+        {"type": "table", "children": [
+            {"type": "tr", "children": [
+                {"type": "th", "children": [{"type": "p", "children": [{"text": "a"}]}]}
+            ]}
+        ]},
+        {"type": "hr", "children": [{"text": ""}]},
+        {"type": "img", "url": "a.png", "alt": "\"A\"", "children": [{"text": ""}]}
+    ]));
+    assert_eq!(
+        html,
+        "<h1>Title</h1><blockquote>quote</blockquote>\
+         <ol start=\"3\"><li>three</li><li><input type=\"checkbox\" disabled checked> done</li></ol>\
+         <pre><code class=\"language-rust\">if a &lt; b {\n}</code></pre>\
+         <table><tr><th><p>a</p></th></tr></table><hr><img src=\"a.png\" alt=\"&quot;A&quot;\">"
+    );
+}
+
+#[test]
+fn test_render_marks_and_links() {
+    let html = render(json!([
+        {"type": "p", "children": [
+            {"text": "<b>", "bold": true, "italic": true},
+            {"text": " & "},
+            {"text": "x", "code": true, "strikethrough": true},
+            {"text": "line\nbreak"},
+            {"type": "a", "url": "https://example.com/?a=1&b=2", "children": [{"text": "ok"}]},
+            {"type": "a", "url": " JavaScript:alert(1)", "children": [{"text": "bad"}]},
+            {"type": "mention", "children": [{"text": "ann"}]},
+            {"text": ""}
+        ]}
+    ]));
+    assert_eq!(
+        html,
+        "<p><strong><em>&lt;b&gt;</em></strong> &amp; <s><code>x</code></s>line<br>break\
+         <a href=\"https://example.com/?a=1&amp;b=2\">ok</a><a>bad</a><span>ann</span></p>"
+    );
+}
+
+#[test]
+fn test_render_overrides() {
+    let doc = Document::from_slate_value(&json!([
+        {"type": "p", "children": [{"text": "text"}]},
+        {"type": "h1", "children": [{"text": "title"}]}
+    ]))
+    .unwrap();
+    let renderer = HtmlRenderer::new()
+        .tag("p", "div")
+        .handler("h1", |node, children| {
+            format!(
+                "<h1 id=\"{}\">{}</h1>",
+                node.text().to_lowercase(),
+                children
+            )
+        });
+    assert_eq!(
+        doc.render_html_with(&renderer),
+        "<div>text</div><h1 id=\"title\">title</h1>"
+    );
+    // The internal representation is left as it is.
+    assert!(doc.html().contains("<text>"));
+}
@@ -0,0 +1,137 @@
+use dom_query::{Document, ElementSchema, JsonType, Path, Schema, Violation, ViolationKind};
+use serde_json::json;
+
+fn schema() -> Schema {
+    Schema::new()
+        .element("p", ElementSchema::block())
+        .element("h1", ElementSchema::block())
+        .element("a", ElementSchema::inline().attr("url", JsonType::String))
+        .element(
+            "img",
+            ElementSchema::block().void().attr("url", JsonType::String),
+        )
+        .element("mention", ElementSchema::inline().void())
+        .element("ul", ElementSchema::block().children(["li"]))
+        .element("li", ElementSchema::block().children(["lic", "ul"]))
+        .element("lic", ElementSchema::block().children(["a"]).text())
+}
+
+fn violation(path: &[usize], kind: ViolationKind) -> Violation {
+    Violation {
+        path: Path::new(path.to_vec()),
+        kind,
+    }
+}
+
+#[test]
+fn test_valid_document() {
+    let document = Document::from_slate_value(&json!([
+        {"type": "h1", "children": [{"text": "Title"}]},
+        {"type": "p", "children": [
+            {"text": "see "},
+            {"type": "a", "url": "/x", "children": [{"text": "this"}]},
+            {"text": " and "},
+            {"type": "mention", "value": "bob", "children": [{"text": ""}]},
+            {"text": ""}
+        ]},
+        {"type": "img", "url": "/img.png", "children": [{"text": ""}]},
+        {"type": "ul", "children": [
+            {"type": "li", "children": [{"type": "lic", "children": [{"text": "item"}]}]}
+        ]}
+    ]))
+    .unwrap();
+    assert_eq!(document.validate(&schema()), vec![]);
+}
+
+#[test]
+fn test_element_violations() {
+    let document = Document::from_slate_value(&json!([
+        {"type": "blink", "children": [{"text": "?"}]},
+        {"type": "img", "url": 1, "children": [{"text": "caption"}]},
+        {"type": "p", "children": [
+            {"type": "a", "children": [{"text": "no url"}]}
+        ]}
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        document.validate(&schema()),
+        vec![
+            violation(&[0], ViolationKind::UnknownElement("blink".to_string())),
+            violation(
+                &[1],
+                ViolationKind::InvalidAttribute {
+                    element: "img".to_string(),
+                    attr: "url".to_string(),
+                    expected: JsonType::String,
+                }
+            ),
+            violation(&[1], ViolationKind::InvalidVoid("img".to_string())),
+            violation(
+                &[2, 0],
+                ViolationKind::MissingAttribute {
+                    element: "a".to_string(),
+                    attr: "url".to_string(),
+                }
+            ),
+        ]
+    );
+    assert!(document
+        .validate(&schema().allow_unknown())
+        .iter()
+        .all(|v| !matches!(v.kind, ViolationKind::UnknownElement(_))));
+}
+
+#[test]
+fn test_children_violations() {
+    let document = Document::from_slate_value(&json!([
+        {"text": "top level"},
+        {"type": "ul", "children": [
+            {"type": "p", "children": [{"text": "not an item"}]},
+            {"text": "text"}
+        ]},
+        {"type": "p", "children": [
+            {"text": "text"},
+            {"type": "h1", "children": [{"text": "block"}]}
+        ]}
+    ]))
+    .unwrap();
+
+    let violations = document.validate(&schema());
+    let messages: Vec<_> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "[0]: inline node among blocks",
+            "[1, 0]: `p` is not allowed in `ul`",
+            "[1, 1]: `text` is not allowed in `ul`",
+            "[1, 1]: inline node among blocks",
+            "[2, 1]: block among inline nodes",
+        ]
+    );
+}
+
+#[test]
+fn test_schema_normalizer() {
+    let schema = schema();
+    assert!(schema.get("a").unwrap().is_inline());
+    assert!(schema.get("img").unwrap().is_void());
+
+    let document = Document::from_slate_value(&json!([
+        {"type": "p", "children": [
+            {"type": "a", "url": "/x", "children": [{"text": "link"}]}
+        ]}
+    ]))
+    .unwrap();
+    document.normalize_with(&schema.normalizer()).unwrap();
+
+    assert_eq!(document.validate(&schema), vec![]);
+    assert_eq!(
+        document.to_slate_value(),
+        json!([{"type": "p", "children": [
+            {"text": ""},
+            {"type": "a", "url": "/x", "children": [{"text": "link"}]},
+            {"text": ""}
+        ]}])
+    );
+}
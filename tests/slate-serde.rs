@@ -0,0 +1,159 @@
+use dom_query::{Attrib, Document, Element, NodeData, ParseOptions, Text};
+use serde_json::json;
+
+const PAGES: [&str; 5] = [
+    include_str!("../test-pages/page.html"),
+    include_str!("../test-pages/page2.html"),
+    include_str!("../test-pages/rustwiki.html"),
+    include_str!("../test-pages/hacker_news.html"),
+    include_str!("../test-pages/wisburg.html"),
+];
+
+#[test]
+fn test_document_roundtrip_test_pages() {
+    for page in PAGES {
+        let document = Document::from_slate_html(page);
+        let json = serde_json::to_string(&document).unwrap();
+        let restored: Document = serde_json::from_str(&json).unwrap();
+
+        // The Slate value is what round-trips, see `impl Serialize for Document`.
+        assert_eq!(restored.to_slate_value(), document.to_slate_value());
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        assert_eq!(
+            restored.select("body").text(),
+            document.select("body").text()
+        );
+    }
+}
+
+#[test]
+fn test_document_serializes_content_only() {
+    let html = "<title>T</title><p><text>a</text></p><!-- c --></i>";
+    let document = Document::from_slate_html_with(html, &ParseOptions::new().keep_comments(true));
+    assert!(!document.errors.is_empty());
+
+    let json = serde_json::to_string(&document).unwrap();
+    let restored: Document = serde_json::from_str(&json).unwrap();
+    assert_eq!(&*restored.html(), "<p><text>a</text></p>");
+    assert_eq!(&*restored.select("head").inner_html(), "");
+    assert!(restored.errors.is_empty());
+}
+
+#[test]
+fn test_document_serializes_as_slate_value() {
+    let document = Document::from_slate_html(r#"<p><text bold="true">a</text></p>"#);
+    assert_eq!(
+        serde_json::to_value(&document).unwrap(),
+        json!([{"type": "p", "children": [{"text": "a", "bold": true}]}])
+    );
+}
+
+#[test]
+fn test_node_data_roundtrip() {
+    let text: NodeData = Text::with_attrs(
+        "hello",
+        vec![Attrib {
+            name: "bold".into(),
+            value: json!(true),
+        }],
+    )
+    .into();
+    let value = serde_json::to_value(&text).unwrap();
+    assert_eq!(value, json!({"text": "hello", "bold": true}));
+    match serde_json::from_value::<NodeData>(value).unwrap() {
+        NodeData::Text(t) => {
+            assert_eq!(&t.contents[..], "hello");
+            assert_eq!(t.attrs[0].value, json!(true));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let mut element = Element::new("a");
+    element.set_attr("url", json!("/x"));
+    let value = serde_json::to_value(NodeData::from(element)).unwrap();
+    assert_eq!(value, json!({"type": "a", "url": "/x"}));
+    match serde_json::from_value::<NodeData>(value).unwrap() {
+        NodeData::Element(e) => {
+            assert_eq!(&e.name[..], "a");
+            assert_eq!(&e.attrs[0].name[..], "url");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let value = serde_json::to_value(NodeData::Document).unwrap();
+    assert_eq!(value, json!({"document": true}));
+    assert!(matches!(
+        serde_json::from_value::<NodeData>(value).unwrap(),
+        NodeData::Document
+    ));
+}
+
+#[test]
+fn test_other_node_data_roundtrip() {
+    let nodes = [
+        (NodeData::Document, json!({"document": true})),
+        (NodeData::Fragment, json!({"fragment": true})),
+        (
+            NodeData::Comment {
+                contents: " draft ".into(),
+            },
+            json!({"comment": " draft "}),
+        ),
+        (
+            NodeData::Doctype {
+                name: "html".into(),
+                public_id: "".into(),
+                system_id: "about:legacy-compat".into(),
+            },
+            json!({"doctype": "html", "publicId": "", "systemId": "about:legacy-compat"}),
+        ),
+        (
+            NodeData::ProcessingInstruction {
+                target: "xml-stylesheet".into(),
+                contents: "href=\"a.css\"".into(),
+            },
+            json!({"processingInstruction": "xml-stylesheet", "data": "href=\"a.css\""}),
+        ),
+    ];
+    for (node, expected) in nodes {
+        let value = serde_json::to_value(&node).unwrap();
+        assert_eq!(value, expected);
+        let restored: NodeData = serde_json::from_value(value).unwrap();
+        assert_eq!(format!("{:?}", restored), format!("{:?}", node));
+    }
+
+    // Kept comments keep their kind through serde.
+    let options = ParseOptions::new().keep_comments(true);
+    let document = Document::from_slate_html_with("<p><!--c--><text>a</text></p>", &options);
+    let comment = document.select("p").nodes()[0].children()[0].clone();
+    let value = comment.query(|node| serde_json::to_value(&node.data).unwrap());
+    assert_eq!(value, Some(json!({"comment": "c"})));
+    assert!(matches!(
+        serde_json::from_value::<NodeData>(value.unwrap()).unwrap(),
+        NodeData::Comment { .. }
+    ));
+}
+
+#[test]
+fn test_attrib_roundtrip() {
+    let attr = Attrib {
+        name: "list".into(),
+        value: json!(["a", 1]),
+    };
+    let value = serde_json::to_value(&attr).unwrap();
+    assert_eq!(value, json!({"name": "list", "value": ["a", 1]}));
+
+    let restored: Attrib = serde_json::from_value(value).unwrap();
+    assert_eq!(&restored.name[..], "list");
+    assert_eq!(restored.value, json!(["a", 1]));
+}
+
+#[test]
+fn test_deserialize_errors() {
+    assert!(serde_json::from_value::<Text>(json!({"bold": true})).is_err());
+    assert!(serde_json::from_value::<Element>(json!({"children": []})).is_err());
+    assert!(serde_json::from_value::<Document>(json!("p")).is_err());
+    assert!(serde_json::from_value::<NodeData>(json!({})).is_err());
+    assert!(serde_json::from_value::<NodeData>(json!({"document": false})).is_err());
+    assert!(serde_json::from_value::<NodeData>(json!({"comment": 1})).is_err());
+}
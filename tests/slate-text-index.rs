@@ -0,0 +1,95 @@
+use dom_query::{Document, Path, Point, Range, TextLeaf};
+
+fn point(path: &[usize], offset: usize) -> Point {
+    Point::new(Path::from(path), offset)
+}
+
+fn doc() -> Document {
+    Document::from_slate_html(
+        r#"<p><text>Hello </text><text bold="true">wörld</text></p><p><text>second</text><a url="/"><text>link</text></a><text></text></p>"#,
+    )
+}
+
+#[test]
+fn test_text_index_leaves() {
+    let document = doc();
+    let index = document.text_index();
+
+    assert_eq!(index.text(), "Hello wörldsecondlink");
+    assert_eq!(index.text(), &*document.select("body").text());
+    assert_eq!(index.len(), 21);
+    assert_eq!(
+        index.leaves()[1],
+        TextLeaf {
+            path: Path::from(&[0, 1][..]),
+            start: 6,
+            len: 5,
+        }
+    );
+    assert_eq!(index.leaves()[3].end(), 21);
+    assert_eq!(index.leaf_at(11).unwrap().path, Path::from(&[1, 0][..]));
+    assert_eq!(index.leaf_at(21).unwrap().path, Path::from(&[1, 2][..]));
+    assert_eq!(index.leaf_at(22), None);
+}
+
+#[test]
+fn test_text_index_points() {
+    let index = doc().text_index();
+
+    assert_eq!(index.point(0), Some(point(&[0, 0], 0)));
+    assert_eq!(index.point(6), Some(point(&[0, 1], 0)));
+    assert_eq!(index.point(8), Some(point(&[0, 1], 2)));
+    assert_eq!(index.point(21), Some(point(&[1, 2], 0)));
+    assert_eq!(index.point(22), None);
+
+    assert_eq!(index.offset(&point(&[0, 1], 2)), Some(8));
+    assert_eq!(index.offset(&point(&[1, 2], 0)), Some(21));
+    assert_eq!(index.offset(&point(&[0, 1], 6)), None);
+    assert_eq!(index.offset(&point(&[1], 0)), None);
+}
+
+#[test]
+fn test_text_index_ranges() {
+    let document = doc();
+    let index = document.text_index();
+
+    let range = index.range(6..11).unwrap();
+    assert_eq!(range, Range::new(point(&[0, 1], 0), point(&[0, 1], 5)));
+    assert_eq!(document.range_text(&range).unwrap(), "wörld");
+
+    let range = index.range(8..19).unwrap();
+    assert_eq!(range, Range::new(point(&[0, 1], 2), point(&[1, 1, 0], 2)));
+    assert_eq!(document.range_text(&range).unwrap(), "rldsecondli");
+    assert_eq!(index.span(&range), Some(8..19));
+
+    let backward = Range::new(point(&[1, 1, 0], 2), point(&[0, 1], 2));
+    assert_eq!(index.span(&backward), Some(8..19));
+    assert_eq!(index.range(3..3), Some(Range::collapsed(point(&[0, 0], 3))));
+    assert_eq!(index.range(3..22), None);
+}
+
+#[test]
+fn test_text_index_counts_utf16_code_units() {
+    let document = Document::from_slate_html("<p><text>😀</text><text>ab</text></p>");
+    let index = document.text_index();
+
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.leaves()[0].len, 2);
+    assert_eq!(index.point(3), Some(point(&[0, 1], 1)));
+    assert_eq!(index.offset(&point(&[0, 1], 1)), Some(3));
+    let range = index.range(0..3).unwrap();
+    assert_eq!(document.range_text(&range).unwrap(), "😀a");
+}
+
+#[test]
+fn test_selection_text_index() {
+    let document = doc();
+    let selection = document.select("a, p:first-child text[bold]");
+    let index = selection.text_index();
+
+    assert_eq!(index.text(), &*selection.text());
+    assert_eq!(index.text(), "wörldlink");
+    assert_eq!(index.point(5), Some(point(&[1, 1, 0], 0)));
+    assert_eq!(index.offset(&point(&[1, 1, 0], 4)), Some(9));
+    assert_eq!(index.offset(&point(&[0, 0], 0)), None);
+}
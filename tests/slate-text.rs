@@ -0,0 +1,92 @@
+use dom_query::{Document, TextOptions};
+use serde_json::json;
+
+#[test]
+fn test_text_with_blocks() {
+    let doc = Document::from_slate_html("<p>a</p><p>b <strong>c</strong></p><h1>d</h1>");
+    assert_eq!(doc.select("body").text().to_string(), "ab cd");
+    assert_eq!(doc.text_with(&TextOptions::new()), "a\nb c\nd");
+    assert_eq!(
+        doc.text_with(&TextOptions::new().block_separator("\n\n")),
+        "a\n\nb c\n\nd"
+    );
+
+    // Only the configured names are blocks.
+    let options = TextOptions::new().blocks(["h1"]);
+    assert_eq!(doc.text_with(&options), "ab c\nd");
+}
+
+#[test]
+fn test_text_with_skips_head() {
+    let doc = Document::from_slate_html(
+        "<html><head><title>Title</title></head><body><p>a</p><p>b</p></body></html>",
+    );
+    assert_eq!(doc.text_with(&TextOptions::new()), "a\nb");
+}
+
+#[test]
+fn test_text_with_lists() {
+    let doc = Document::from_slate_value(&json!([
+        {"type": "ul", "children": [
+            {"type": "li", "children": [
+                {"type": "lic", "children": [{"text": "one"}]},
+                {"type": "ol", "children": [
+                    {"type": "li", "children": [{"type": "lic", "children": [{"text": "first"}]}]},
+                    {"type": "li", "children": [{"type": "lic", "children": [{"text": "second"}]}]}
+                ]}
+            ]},
+            {"type": "li", "children": [{"type": "lic", "children": [{"text": "two"}]}]}
+        ]}
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        doc.text_with(&TextOptions::new()),
+        "one\nfirst\nsecond\ntwo"
+    );
+    assert_eq!(
+        doc.text_with(&TextOptions::new().bullet("* ").numbered()),
+        "* one\n  1. first\n  2. second\n* two"
+    );
+}
+
+#[test]
+fn test_text_with_tables() {
+    let doc = Document::from_slate_html(
+        "<p>Scores</p><table><tr><th>name</th><th>score</th></tr>\
+         <tr><td><p>ann</p></td><td><p>3</p></td></tr></table><p>end</p>",
+    );
+    assert_eq!(
+        doc.text_with(&TextOptions::new().block_separator("\n\n")),
+        "Scores\n\nname\tscore\nann\t3\n\nend"
+    );
+    let options = TextOptions::new()
+        .cell_separator(" | ")
+        .row_separator(" / ");
+    assert_eq!(
+        doc.text_with(&options),
+        "Scores\nname | score / ann | 3\nend"
+    );
+}
+
+#[test]
+fn test_text_with_voids() {
+    let doc = Document::from_slate_value(&json!([
+        {"type": "p", "children": [
+            {"text": "hi "},
+            {"type": "mention", "value": "ann", "children": [{"text": ""}]},
+            {"text": "!"}
+        ]},
+        {"type": "img", "url": "a.png", "children": [{"text": ""}]}
+    ]))
+    .unwrap();
+    let options = TextOptions::new()
+        .void("mention", "@someone")
+        .void("img", "[image]");
+    assert_eq!(doc.text_with(&options), "hi @someone!\n[image]");
+
+    let sel = doc.select("p");
+    assert_eq!(sel.text_with(&options), "hi @someone!");
+    let node = doc.select("img").nodes()[0].clone();
+    assert_eq!(node.text_with(&options), "[image]");
+}
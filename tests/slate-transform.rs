@@ -0,0 +1,509 @@
+use dom_query::{Document, Operation, Path, Side};
+use proptest::prelude::*;
+use serde_json::{json, Map, Value};
+
+fn op(value: Value) -> Operation {
+    serde_json::from_value(value).unwrap()
+}
+
+fn ops(values: Vec<Value>) -> Vec<Operation> {
+    values.into_iter().map(op).collect()
+}
+
+fn apply_all(base: &Value, ops: &[&[Operation]]) -> Result<Value, String> {
+    let doc = Document::from_slate_value(base).unwrap();
+    for op in ops.iter().flat_map(|ops| ops.iter()) {
+        doc.apply(op).map_err(|e| format!("{}: {:?}", e, op))?;
+    }
+    Ok(doc.to_slate_value())
+}
+
+/// Applies `a` then `b` transformed against it, and `b` then `a` transformed
+/// against it, and checks that both give the same document.
+fn assert_converges(base: &Value, a: &[Operation], b: &[Operation]) -> Result<(), String> {
+    for side in [Side::Left, Side::Right] {
+        let a_then_b = Operation::transform_all(b, a, side.opposite());
+        let b_then_a = Operation::transform_all(a, b, side);
+        let first = apply_all(base, &[a, &a_then_b])?;
+        let second = apply_all(base, &[b, &b_then_a])?;
+        if first != second {
+            return Err(format!(
+                "{:?}: {} != {}\na: {:?}\nb': {:?}\nb: {:?}\na': {:?}",
+                side, first, second, a, a_then_b, b, b_then_a
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn base() -> Value {
+    json!([
+        {"type": "p", "children": [{"text": "Hello "}, {"text": "world", "bold": true}]},
+        {"type": "p", "children": [{"text": "second"}]},
+        {"type": "blockquote", "children": [
+            {"type": "p", "children": [{"text": "quoted"}]}
+        ]}
+    ])
+}
+
+#[test]
+fn test_transform_text() {
+    let a = op(json!({"type": "insert_text", "path": [0, 0], "offset": 2, "text": "ab"}));
+    let b = op(json!({"type": "insert_text", "path": [0, 0], "offset": 2, "text": "xy"}));
+    assert_eq!(a.transform(&b, Side::Left), vec![a.clone()]);
+    assert_eq!(
+        a.transform(&b, Side::Right),
+        ops(vec![
+            json!({"type": "insert_text", "path": [0, 0], "offset": 4, "text": "ab"})
+        ])
+    );
+    assert_converges(&base(), &[a], &[b]).unwrap();
+
+    // The removal around the insertion keeps the inserted text.
+    let a = op(json!({"type": "remove_text", "path": [1, 0], "offset": 1, "text": "eco"}));
+    let b = op(json!({"type": "insert_text", "path": [1, 0], "offset": 2, "text": "--"}));
+    assert_eq!(
+        a.transform(&b, Side::Left),
+        ops(vec![
+            json!({"type": "remove_text", "path": [1, 0], "offset": 1, "text": "e"}),
+            json!({"type": "remove_text", "path": [1, 0], "offset": 3, "text": "co"}),
+        ])
+    );
+    assert_converges(&base(), &[a], &[b]).unwrap();
+
+    // Overlapping removals remove the rest only.
+    let a = op(json!({"type": "remove_text", "path": [1, 0], "offset": 0, "text": "sec"}));
+    let b = op(json!({"type": "remove_text", "path": [1, 0], "offset": 2, "text": "con"}));
+    assert_eq!(
+        a.transform(&b, Side::Left),
+        ops(vec![
+            json!({"type": "remove_text", "path": [1, 0], "offset": 0, "text": "se"})
+        ])
+    );
+    assert_converges(&base(), &[a], &[b]).unwrap();
+}
+
+#[test]
+fn test_transform_paths() {
+    let a = op(json!({"type": "insert_text", "path": [2, 0, 0], "offset": 0, "text": "> "}));
+    let b = op(json!({"type": "move_node", "path": [2, 0], "newPath": [0]}));
+    assert_eq!(
+        a.transform(&b, Side::Left),
+        ops(vec![
+            json!({"type": "insert_text", "path": [0, 0], "offset": 0, "text": "> "})
+        ])
+    );
+    assert_converges(&base(), &[a], &[b]).unwrap();
+
+    // Changes inside a removed node are dropped.
+    let a = op(
+        json!({"type": "set_node", "path": [2, 0], "properties": {}, "newProperties": {"id": "q"}}),
+    );
+    let b = op(json!({"type": "remove_node", "path": [2], "node": base()[2]}));
+    assert_eq!(a.transform(&b, Side::Left), vec![]);
+    assert_converges(&base(), &[a], &[b]).unwrap();
+
+    // Text typed into a split leaf follows its half.
+    let a = op(json!({"type": "insert_text", "path": [1, 0], "offset": 4, "text": "!"}));
+    let b = op(json!({"type": "split_node", "path": [1, 0], "position": 3, "properties": {}}));
+    assert_eq!(
+        a.transform(&b, Side::Left),
+        ops(vec![
+            json!({"type": "insert_text", "path": [1, 1], "offset": 1, "text": "!"})
+        ])
+    );
+    assert_converges(&base(), &[a], &[b]).unwrap();
+}
+
+#[test]
+fn test_transform_conflicts() {
+    // A node inserted between two merged nodes ends up after the merged node.
+    let a = ops(vec![
+        json!({"type": "merge_node", "path": [1], "position": 2, "properties": {"type": "p"}}),
+    ]);
+    let b = ops(vec![
+        json!({"type": "insert_node", "path": [1], "node": {"type": "hr", "children": [{"text": ""}]}}),
+    ]);
+    assert_converges(&base(), &a, &b).unwrap();
+    assert_eq!(
+        apply_all(&base(), &[&b, &a[0].transform(&b[0], Side::Left)]).unwrap()[1]["type"],
+        json!("hr")
+    );
+
+    // Both moved the same node, the left move wins.
+    let a = ops(vec![
+        json!({"type": "move_node", "path": [0], "newPath": [2]}),
+    ]);
+    let b = ops(vec![
+        json!({"type": "move_node", "path": [0], "newPath": [2, 1]}),
+    ]);
+    assert_converges(&base(), &a, &b).unwrap();
+    assert_eq!(
+        apply_all(&base(), &[&b, &a[0].transform(&b[0], Side::Left)]).unwrap(),
+        apply_all(&base(), &[&a]).unwrap()
+    );
+
+    // A merge and a move of the merged node, the left one wins.
+    let a = ops(vec![
+        json!({"type": "merge_node", "path": [1], "position": 2, "properties": {"type": "p"}}),
+    ]);
+    let b = ops(vec![
+        json!({"type": "move_node", "path": [1], "newPath": [2, 0]}),
+    ]);
+    assert_converges(&base(), &a, &b).unwrap();
+    assert_eq!(b[0].transform(&a[0], Side::Right), vec![]);
+    assert_eq!(
+        apply_all(&base(), &[&b, &a[0].transform(&b[0], Side::Left)]).unwrap(),
+        apply_all(&base(), &[&a]).unwrap()
+    );
+
+    // Both set the same property, the left value wins.
+    let a = op(
+        json!({"type": "set_node", "path": [0], "properties": {}, "newProperties": {"id": "a", "align": "left"}}),
+    );
+    let b = op(
+        json!({"type": "set_node", "path": [0], "properties": {}, "newProperties": {"id": "b"}}),
+    );
+    assert_eq!(b.transform(&a, Side::Right), vec![]);
+    assert_converges(&base(), &[a], &[b]).unwrap();
+}
+
+#[test]
+fn test_removal_of_unknown_node_is_undoable() {
+    // The node is moved into the blockquote, which is removed concurrently.
+    let a = op(json!({"type": "move_node", "path": [1], "newPath": [1, 1]}));
+    let b = op(json!({"type": "remove_node", "path": [2], "node": base()[2].clone()}));
+    let transformed = a.transform(&b, Side::Left);
+    assert_eq!(
+        transformed,
+        vec![Operation::RemoveNode {
+            path: Path::new(vec![1]),
+            node: Value::Null,
+        }]
+    );
+
+    let doc = Document::from_slate_value(&base()).unwrap();
+    doc.apply(&b).unwrap();
+    let before = doc.to_slate_value();
+    doc.enable_history();
+    doc.start_recording();
+    doc.apply(&transformed[0]).unwrap();
+    assert_eq!(
+        doc.take_operations(),
+        vec![Operation::RemoveNode {
+            path: Path::new(vec![1]),
+            node: base()[1].clone(),
+        }]
+    );
+    doc.undo().unwrap();
+    assert_eq!(doc.to_slate_value(), before);
+}
+
+/// A stream of choices drawn by proptest, so that failures shrink.
+struct Choices {
+    values: Vec<u32>,
+    index: usize,
+}
+
+impl Choices {
+    fn pick(&mut self, n: usize) -> usize {
+        let value = self.values.get(self.index).copied().unwrap_or(0);
+        self.index += 1;
+        value as usize % n.max(1)
+    }
+
+    fn flip(&mut self) -> bool {
+        self.pick(2) == 1
+    }
+}
+
+fn random_leaf(choices: &mut Choices) -> Value {
+    let text = ["", "ab", "c😀e", "fghi"][choices.pick(4)];
+    match choices.flip() {
+        true => json!({"text": text, "bold": true}),
+        false => json!({"text": text}),
+    }
+}
+
+fn random_leaves(choices: &mut Choices) -> Value {
+    let count = 1 + choices.pick(3);
+    Value::Array((0..count).map(|_| random_leaf(choices)).collect())
+}
+
+fn random_block(choices: &mut Choices) -> Value {
+    match choices.pick(3) {
+        0 => json!({"type": "p", "children": random_leaves(choices)}),
+        1 => {
+            let count = 1 + choices.pick(2);
+            let children: Vec<Value> = (0..count)
+                .map(|_| json!({"type": "p", "children": random_leaves(choices)}))
+                .collect();
+            json!({"type": "blockquote", "children": children})
+        }
+        _ => {
+            let count = 1 + choices.pick(2);
+            let children: Vec<Value> = (0..count)
+                .map(|_| json!({"type": "li", "id": "i", "children": random_leaves(choices)}))
+                .collect();
+            json!({"type": "ul", "children": children})
+        }
+    }
+}
+
+fn random_document(choices: &mut Choices) -> Value {
+    let count = 1 + choices.pick(3);
+    let value = Value::Array((0..count).map(|_| random_block(choices)).collect());
+    // Read it back, the leaves may be normalized.
+    Document::from_slate_value(&value).unwrap().to_slate_value()
+}
+
+fn children(node: &Value) -> &[Value] {
+    match node {
+        Value::Array(children) => children,
+        node => node["children"].as_array().map_or(&[], |c| c.as_slice()),
+    }
+}
+
+fn node_at<'a>(root: &'a Value, path: &[usize]) -> &'a Value {
+    path.iter()
+        .fold(root, |node, index| &children(node)[*index])
+}
+
+/// Returns the paths of all the nodes, with the content root as `[]`.
+fn all_paths(root: &Value) -> Vec<Vec<usize>> {
+    fn walk(node: &Value, path: Vec<usize>, paths: &mut Vec<Vec<usize>>) {
+        for (i, child) in children(node).iter().enumerate() {
+            let mut child_path = path.clone();
+            child_path.push(i);
+            paths.push(child_path.clone());
+            walk(child, child_path, paths);
+        }
+    }
+    let mut paths = vec![vec![]];
+    walk(root, vec![], &mut paths);
+    paths
+}
+
+fn is_leaf(node: &Value) -> bool {
+    node.get("text").is_some()
+}
+
+fn len(node: &Value) -> usize {
+    match node.get("text") {
+        Some(text) => text.as_str().unwrap().encode_utf16().count(),
+        None => children(node).len(),
+    }
+}
+
+/// Returns the UTF-16 offsets between the characters of a leaf, so that no
+/// offset splits a surrogate pair.
+fn boundaries(node: &Value) -> Vec<usize> {
+    let text = node["text"].as_str().unwrap();
+    let mut offsets = vec![0];
+    offsets.extend(text.chars().scan(0, |offset, c| {
+        *offset += c.len_utf16();
+        Some(*offset)
+    }));
+    offsets
+}
+
+fn properties(node: &Value) -> Map<String, Value> {
+    let mut props = node.as_object().cloned().unwrap_or_default();
+    props.remove("text");
+    props.remove("children");
+    props
+}
+
+fn pick<'a, T>(choices: &mut Choices, items: &'a [T]) -> Option<&'a T> {
+    match items.is_empty() {
+        true => None,
+        false => Some(&items[choices.pick(items.len())]),
+    }
+}
+
+fn random_operation(choices: &mut Choices, root: &Value) -> Option<Operation> {
+    let paths = all_paths(root);
+    let nodes: Vec<&Vec<usize>> = paths.iter().filter(|p| !p.is_empty()).collect();
+    let leaves: Vec<&Vec<usize>> = nodes
+        .iter()
+        .copied()
+        .filter(|p| is_leaf(node_at(root, p)))
+        .collect();
+    let path = |p: &[usize]| json!(p);
+
+    let op = match choices.pick(8) {
+        0 => {
+            let leaf = pick(choices, &leaves)?;
+            let offsets = boundaries(node_at(root, leaf));
+            let offset = *pick(choices, &offsets)?;
+            let text = ["x", "y😀"][choices.pick(2)];
+            json!({"type": "insert_text", "path": path(leaf), "offset": offset, "text": text})
+        }
+        1 => {
+            let leaf = pick(choices, &leaves)?;
+            let chars: Vec<char> = node_at(root, leaf)["text"].as_str()?.chars().collect();
+            if chars.is_empty() {
+                return None;
+            }
+            let start = choices.pick(chars.len());
+            let count = 1 + choices.pick(chars.len() - start);
+            let offset = boundaries(node_at(root, leaf))[start];
+            let text: String = chars[start..start + count].iter().collect();
+            json!({"type": "remove_text", "path": path(leaf), "offset": offset, "text": text})
+        }
+        2 => {
+            let parents: Vec<&Vec<usize>> = paths
+                .iter()
+                .filter(|p| !children(node_at(root, p)).is_empty())
+                .collect();
+            let parent = pick(choices, &parents)?;
+            let siblings = children(node_at(root, parent));
+            let mut at = (*parent).clone();
+            at.push(choices.pick(siblings.len() + 1));
+            let node = match is_leaf(&siblings[0]) {
+                true => json!({"text": "new"}),
+                false => json!({"type": "p", "children": [{"text": "new"}]}),
+            };
+            json!({"type": "insert_node", "path": path(&at), "node": node})
+        }
+        3 => {
+            let removable: Vec<&Vec<usize>> = nodes
+                .iter()
+                .copied()
+                .filter(|p| children(node_at(root, &p[..p.len() - 1])).len() > 1)
+                .collect();
+            let at = pick(choices, &removable)?;
+            json!({"type": "remove_node", "path": path(at), "node": node_at(root, at)})
+        }
+        4 => {
+            let at = pick(choices, &nodes)?;
+            let node = node_at(root, at);
+            let position = match is_leaf(node) {
+                true => *pick(choices, &boundaries(node))?,
+                false => choices.pick(len(node) + 1),
+            };
+            json!({"type": "split_node", "path": path(at), "position": position, "properties": properties(node)})
+        }
+        5 => {
+            let mergeable: Vec<&Vec<usize>> = nodes
+                .iter()
+                .copied()
+                .filter(|p| {
+                    let last = p[p.len() - 1];
+                    let mut prev = p.to_vec();
+                    prev[p.len() - 1] = last.wrapping_sub(1);
+                    last > 0 && is_leaf(node_at(root, p)) == is_leaf(node_at(root, &prev))
+                })
+                .collect();
+            let at = pick(choices, &mergeable)?;
+            let mut prev = at.to_vec();
+            *prev.last_mut().unwrap() -= 1;
+            let node = node_at(root, at);
+            json!({"type": "merge_node", "path": path(at), "position": len(node_at(root, &prev)), "properties": properties(node)})
+        }
+        6 => {
+            let from = pick(choices, &nodes)?.to_vec();
+            let leaf = is_leaf(node_at(root, &from));
+            // Pick the destination in the document without the node.
+            let mut removed = root.clone();
+            {
+                let parent =
+                    from[..from.len() - 1]
+                        .iter()
+                        .fold(&mut removed, |node, i| match node {
+                            Value::Array(c) => &mut c[*i],
+                            node => &mut node["children"][*i],
+                        });
+                let siblings = match parent {
+                    Value::Array(c) => c,
+                    node => node["children"].as_array_mut().unwrap(),
+                };
+                siblings.remove(from[from.len() - 1]);
+            }
+            let parents: Vec<Vec<usize>> = all_paths(&removed)
+                .into_iter()
+                .filter(|p| {
+                    let siblings = children(node_at(&removed, p));
+                    !siblings.is_empty() && is_leaf(&siblings[0]) == leaf
+                })
+                .collect();
+            let parent = pick(choices, &parents)?;
+            let mut to = parent.clone();
+            to.push(choices.pick(children(node_at(&removed, parent)).len() + 1));
+            let (from, to) = (Path::new(from), Path::new(to));
+            if from == to {
+                return None;
+            }
+            let mut new_path = to.to_vec();
+            let level = from.len() - 1;
+            if to.len() > from.len() && to[..level] == from[..level] && to[level] >= from[level] {
+                new_path[level] += 1;
+            }
+            json!({"type": "move_node", "path": from, "newPath": new_path})
+        }
+        _ => {
+            let at = pick(choices, &nodes)?;
+            let node = node_at(root, at);
+            let (key, values) = match is_leaf(node) {
+                true => ("bold", [json!(true), json!(null), json!(null)]),
+                false => match choices.flip() {
+                    true => ("id", [json!("x"), json!("y"), json!(null)]),
+                    false => ("type", [json!("p"), json!("h1"), json!("li")]),
+                },
+            };
+            let value = values[choices.pick(3)].clone();
+            let mut properties = Map::new();
+            if let Some(old) = node.get(key) {
+                properties.insert(key.to_string(), old.clone());
+            }
+            let mut new_properties = Map::new();
+            new_properties.insert(key.to_string(), value);
+            json!({"type": "set_node", "path": path(at), "properties": properties, "newProperties": new_properties})
+        }
+    };
+    Some(serde_json::from_value(op).unwrap())
+}
+
+/// Draws up to `count` operations, each made on the document the previous ones
+/// produce.
+fn random_operations(choices: &mut Choices, base: &Value, count: usize) -> Vec<Operation> {
+    let doc = Document::from_slate_value(base).unwrap();
+    let mut ops = vec![];
+    for _ in 0..count * 4 {
+        if ops.len() == count {
+            break;
+        }
+        if let Some(op) = random_operation(choices, &doc.to_slate_value()) {
+            doc.apply(&op)
+                .unwrap_or_else(|e| panic!("{}: {:?} on {}", e, op, doc.to_slate_value()));
+            ops.push(op);
+        }
+    }
+    ops
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn test_transform_converges(values in prop::collection::vec(any::<u32>(), 128)) {
+        let mut choices = Choices { values, index: 0 };
+        let base = random_document(&mut choices);
+        let a = random_operations(&mut choices, &base, 1);
+        let b = random_operations(&mut choices, &base, 1);
+        if let Err(e) = assert_converges(&base, &a, &b) {
+            panic!("{}\nbase: {}", e, base);
+        }
+    }
+
+    #[test]
+    fn test_transform_all_converges(values in prop::collection::vec(any::<u32>(), 256)) {
+        let mut choices = Choices { values, index: 0 };
+        let base = random_document(&mut choices);
+        let a = random_operations(&mut choices, &base, 3);
+        let b = random_operations(&mut choices, &base, 3);
+        if let Err(e) = assert_converges(&base, &a, &b) {
+            panic!("{}\nbase: {}", e, base);
+        }
+    }
+}
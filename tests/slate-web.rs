@@ -0,0 +1,142 @@
+use dom_query::{Document, ElementSchema, Schema};
+use serde_json::json;
+
+const PAGES: &[&str] = &[
+    include_str!("../test-pages/page.html"),
+    include_str!("../test-pages/page2.html"),
+    include_str!("../test-pages/rustwiki.html"),
+    include_str!("../test-pages/hacker_news.html"),
+    include_str!("../test-pages/wisburg.html"),
+];
+
+fn value(html: &str) -> serde_json::Value {
+    Document::from_web_html(html).to_slate_value()
+}
+
+#[test]
+fn test_web_marks() {
+    assert_eq!(
+        value("<p>a <b>b <i>c</i></b><u>d</u><code>e</code><del>f</del><sup>g</sup></p>"),
+        json!([{"type": "p", "children": [
+            {"text": "a "},
+            {"text": "b ", "bold": true},
+            {"text": "c", "bold": true, "italic": true},
+            {"text": "d", "underline": true},
+            {"text": "e", "code": true},
+            {"text": "f", "strikethrough": true},
+            {"text": "g", "superscript": true}
+        ]}])
+    );
+}
+
+#[test]
+fn test_web_blocks() {
+    let html = r#"
+        <article>
+          <h3>Title</h3>
+          <blockquote><p>quoted</p></blockquote>
+          <ol start="2">
+            <li>two</li>
+            <li><p>three</p><ul><li>nested</li></ul></li>
+          </ol>
+          <pre><code class="language-rust">fn main() {
+    x();
+}
+</code></pre>
+          <hr>
+          <p><img src="a.png" alt="A"></p>
+          <p>see <a href="/x">the <em>link</em></a>, or <a name="anchor">not</a></p>
+        </article>"#;
+    let lic = |text: &str| json!({"type": "lic", "children": [{"text": text}]});
+    assert_eq!(
+        value(html),
+        json!([
+            {"type": "h3", "children": [{"text": "Title"}]},
+            {"type": "blockquote", "children": [{"type": "p", "children": [{"text": "quoted"}]}]},
+            {"type": "ol", "start": 2, "children": [
+                {"type": "li", "children": [lic("two")]},
+                {"type": "li", "children": [
+                    lic("three"),
+                    {"type": "ul", "children": [{"type": "li", "children": [lic("nested")]}]}
+                ]}
+            ]},
+            {"type": "code_block", "lang": "rust", "children": [
+                {"type": "code_line", "children": [{"text": "fn main() {"}]},
+                {"type": "code_line", "children": [{"text": "    x();"}]},
+                {"type": "code_line", "children": [{"text": "}"}]}
+            ]},
+            {"type": "hr", "children": [{"text": ""}]},
+            {"type": "img", "url": "a.png", "alt": "A", "children": [{"text": ""}]},
+            {"type": "p", "children": [
+                {"text": "see "},
+                {"type": "a", "url": "/x", "children": [
+                    {"text": "the "},
+                    {"text": "link", "italic": true}
+                ]},
+                {"text": ", or not"}
+            ]}
+        ])
+    );
+}
+
+#[test]
+fn test_web_tables() {
+    let html = "<table><caption>c</caption><thead><tr><th>a</th><th> b </th></tr></thead>\
+                <tbody><tr><td><div>1</div><div>2</div></td><td></td></tr></tbody></table>";
+    let p = |text: &str| json!({"type": "p", "children": [{"text": text}]});
+    assert_eq!(
+        value(html),
+        json!([{"type": "table", "children": [
+            {"type": "tr", "children": [
+                {"type": "th", "children": [p("a")]},
+                {"type": "th", "children": [p("b")]}
+            ]},
+            {"type": "tr", "children": [
+                {"type": "td", "children": [p("1"), p("2")]},
+                {"type": "td", "children": [p("")]}
+            ]}
+        ]}])
+    );
+}
+
+#[test]
+fn test_web_flattening_and_whitespace() {
+    let html = "<html><head><title>t</title><style>p {}</style></head><body>
+        <script>alert(1)</script>
+        <div class=\"wrapper\">  loose   text <span>in a
+          span</span><br>after   break </div>
+        <section><div><p>  inner </p></div>tail</section>
+        <noscript>no</noscript><!-- comment -->
+        </body></html>";
+    assert_eq!(
+        value(html),
+        json!([
+            {"type": "p", "children": [{"text": "loose text in a span\nafter break"}]},
+            {"type": "p", "children": [{"text": "inner"}]},
+            {"type": "p", "children": [{"text": "tail"}]}
+        ])
+    );
+    assert_eq!(value(""), json!([]));
+}
+
+#[test]
+fn test_web_pages_are_valid() {
+    let schema = Schema::new()
+        .allow_unknown()
+        .element("a", ElementSchema::inline())
+        .element("img", ElementSchema::block().void());
+    for html in PAGES {
+        let doc = Document::from_web_html(html);
+        let violations: Vec<_> = doc
+            .validate(&schema)
+            .into_iter()
+            // Images are blocks, or inline in paragraphs.
+            .filter(|v| {
+                let node = doc.node_at_path(&v.path).unwrap();
+                node.node_name().as_deref() != Some("img")
+            })
+            .collect();
+        assert_eq!(violations, vec![]);
+        assert!(!doc.html().contains("<script"));
+    }
+}